@@ -84,11 +84,9 @@ macro_rules! item_parse {
     ($ty:ident, $local:ty, $expected:literal) => {
         impl $crate::parse::Parse for $local {
             fn parse(p: &mut $crate::parse::Parser<'_>) -> $crate::compile::Result<Self> {
-                let t = p.tok_at(0)?;
-
                 match $crate::ast::Item::parse(p)? {
                     $crate::ast::Item::$ty(item) => Ok(item),
-                    _ => Err($crate::compile::Error::expected(t, $expected)),
+                    item => Err($crate::compile::Error::expected(&item, $expected)),
                 }
             }
         }
@@ -4,6 +4,20 @@ use crate::ast::prelude::*;
 
 use super::Attribute;
 
+#[test]
+fn ast_parse() {
+    use crate::testing::rt;
+
+    assert_eq!(rt::<ast::Item>("use foo::bar").kind_name(), "use");
+    assert_eq!(rt::<ast::Item>("fn foo() {}").kind_name(), "fn");
+    assert_eq!(rt::<ast::Item>("enum Foo {}").kind_name(), "enum");
+    assert_eq!(rt::<ast::Item>("struct Foo").kind_name(), "struct");
+    assert_eq!(rt::<ast::Item>("impl Foo {}").kind_name(), "impl");
+    assert_eq!(rt::<ast::Item>("mod foo {}").kind_name(), "mod");
+    assert_eq!(rt::<ast::Item>("const FOO = 1").kind_name(), "const");
+    assert_eq!(rt::<ast::Item>("foo!()").kind_name(), "macro call");
+}
+
 /// A declaration.
 #[derive(Debug, TryClone, PartialEq, Eq, ToTokens, Spanned)]
 #[non_exhaustive]
@@ -28,6 +42,21 @@ pub enum Item {
 }
 
 impl Item {
+    /// Get a stable name for the kind of the item, like `"fn"` or
+    /// `"macro call"`, for use in diagnostics.
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Use(..) => "use",
+            Self::Fn(..) => "fn",
+            Self::Enum(..) => "enum",
+            Self::Struct(..) => "struct",
+            Self::Impl(..) => "impl",
+            Self::Mod(..) => "mod",
+            Self::Const(..) => "const",
+            Self::MacroCall(..) => "macro call",
+        }
+    }
+
     /// Get the item's attributes
     pub(crate) fn attributes(&self) -> &[ast::Attribute] {
         match self {
@@ -183,6 +212,12 @@ impl Item {
     }
 }
 
+impl IntoExpectation for &Item {
+    fn into_expectation(self) -> Expectation {
+        Expectation::Description(self.kind_name())
+    }
+}
+
 impl Parse for Item {
     fn parse(p: &mut Parser) -> Result<Self> {
         let attributes = p.parse()?;
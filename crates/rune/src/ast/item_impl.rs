@@ -10,6 +10,10 @@ fn ast_parse() {
         "#[variant(enum_= \"SuperHero\", x = \"1\")] impl Foo { fn test(self) { } }",
     );
     rt::<ast::ItemImpl>("#[xyz] impl Foo { #[jit] fn test(self) { } }");
+
+    let item = rt::<ast::ItemImpl>("impl Foo { const MAX = 100; const fn max() { Self::MAX } }");
+    assert_eq!(item.consts.len(), 1);
+    assert_eq!(item.functions.len(), 1);
 }
 
 /// An impl item.
@@ -25,6 +29,9 @@ pub struct ItemImpl {
     pub path: ast::Path,
     /// The open brace.
     pub open: T!['{'],
+    /// The collection of associated constants.
+    #[rune(iter)]
+    pub consts: Vec<(ast::ItemConst, T![;])>,
     /// The collection of functions.
     #[rune(iter)]
     pub functions: Vec<ast::ItemFn>,
@@ -42,10 +49,30 @@ impl ItemImpl {
         let path = parser.parse()?;
         let open = parser.parse()?;
 
+        let mut consts = Vec::new();
         let mut functions = Vec::new();
 
         while !parser.peek::<ast::CloseBrace>()? {
-            functions.try_push(ast::ItemFn::parse(parser)?)?;
+            let attributes = parser.parse()?;
+            let visibility = parser.parse()?;
+            let const_token = parser.parse::<Option<T![const]>>()?;
+
+            if let (Some(const_token), K![ident]) = (const_token, parser.nth(0)?) {
+                let item =
+                    ast::ItemConst::parse_with_meta(parser, attributes, visibility, const_token)?;
+                consts.try_push((item, parser.parse()?))?;
+                continue;
+            }
+
+            let async_token = parser.parse()?;
+
+            functions.try_push(ast::ItemFn::parse_with_meta(
+                parser,
+                attributes,
+                visibility,
+                const_token,
+                async_token,
+            )?)?;
         }
 
         let close = parser.parse()?;
@@ -55,6 +82,7 @@ impl ItemImpl {
             impl_,
             path,
             open,
+            consts,
             functions,
             close,
         })
@@ -47,7 +47,7 @@ cfg_std! {
 
 /// Indicates if we are parsing template escapes.
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct WithTemplate(pub(super) bool);
+pub(crate) struct WithTemplate(pub(crate) bool);
 
 impl ops::Deref for WithTemplate {
     type Target = bool;
@@ -59,7 +59,7 @@ impl ops::Deref for WithTemplate {
 
 /// Indicates if we are parsing line continuations or not.
 #[derive(Debug, Clone, Copy)]
-pub(super) struct WithLineCont(pub(super) bool);
+pub(crate) struct WithLineCont(pub(crate) bool);
 
 impl ops::Deref for WithLineCont {
     type Target = bool;
@@ -114,7 +114,7 @@ pub(super) fn parse_byte_escape(
 }
 
 /// Parse a byte escape sequence.
-pub(super) fn parse_char_escape(
+pub(crate) fn parse_char_escape(
     it: &mut Peekable<impl Iterator<Item = (usize, char)>>,
     with_template: WithTemplate,
     with_line_cont: WithLineCont,
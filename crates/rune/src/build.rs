@@ -3,15 +3,19 @@ use core::marker::PhantomData;
 use core::mem::take;
 
 use crate::alloc::{self, Vec};
-use crate::ast::{Span, Spanned};
+use crate::ast::{self, Span, Spanned};
 use crate::compile;
 #[cfg(feature = "std")]
 use crate::compile::FileSourceLoader as DefaultSourceLoader;
 #[cfg(not(feature = "std"))]
 use crate::compile::NoopSourceLoader as DefaultSourceLoader;
-use crate::compile::{CompileVisitor, Located, MetaError, Options, Pool, SourceLoader};
+use crate::compile::{
+    CompileVisitor, ItemBuf, Located, MetaError, NoopParseVisitor, Options, ParseVisitor, Pool,
+    SourceLoader,
+};
+use crate::query::ConstPath;
 use crate::runtime::unit::{DefaultStorage, UnitEncoder};
-use crate::runtime::Unit;
+use crate::runtime::{ConstValue, Unit};
 use crate::{Context, Diagnostics, SourceId, Sources};
 
 /// Error raised when we failed to load sources.
@@ -119,7 +123,9 @@ where
         diagnostics: None,
         options: None,
         visitors: Vec::new(),
+        parse_visitor: None,
         source_loader: None,
+        const_path: None,
         _unit_storage: PhantomData,
     }
 }
@@ -131,7 +137,9 @@ pub struct Build<'a, S> {
     diagnostics: Option<&'a mut Diagnostics>,
     options: Option<&'a Options>,
     visitors: Vec<&'a mut dyn compile::CompileVisitor>,
+    parse_visitor: Option<&'a mut dyn ParseVisitor>,
     source_loader: Option<&'a mut dyn SourceLoader>,
+    const_path: Option<ConstPath<'a>>,
     _unit_storage: PhantomData<S>,
 }
 
@@ -174,6 +182,66 @@ impl<'a> compile::CompileVisitor for CompileVisitorGroup<'a> {
         Ok(())
     }
 
+    fn visit_rejected_attribute(
+        &mut self,
+        source_id: SourceId,
+        item_span: &dyn Spanned,
+        attr_span: &dyn Spanned,
+        name: &str,
+    ) -> Result<(), MetaError> {
+        for v in self.visitors.iter_mut() {
+            v.visit_rejected_attribute(source_id, item_span, attr_span, name)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_budget_exceeded(
+        &mut self,
+        source_id: SourceId,
+        item: &compile::Item,
+        span: Span,
+        steps: usize,
+    ) -> Result<(), MetaError> {
+        for v in self.visitors.iter_mut() {
+            v.visit_budget_exceeded(source_id, item, span, steps)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_const_explained(
+        &mut self,
+        source_id: SourceId,
+        item: &compile::Item,
+        steps: &[compile::FoldStep],
+    ) -> Result<(), MetaError> {
+        for v in self.visitors.iter_mut() {
+            v.visit_const_explained(source_id, item, steps)?;
+        }
+
+        Ok(())
+    }
+
+    fn visit_const_graph(&mut self, graph: &compile::ConstGraph) {
+        for v in self.visitors.iter_mut() {
+            v.visit_const_graph(graph);
+        }
+    }
+
+    fn visit_macro_expansion(
+        &mut self,
+        source_id: SourceId,
+        call_span: Span,
+        expanded: &[ast::Item],
+    ) -> Result<(), MetaError> {
+        for v in self.visitors.iter_mut() {
+            v.visit_macro_expansion(source_id, call_span, expanded)?;
+        }
+
+        Ok(())
+    }
+
     fn visit_mod(&mut self, location: &dyn Located) -> Result<(), MetaError> {
         for v in self.visitors.iter_mut() {
             v.visit_mod(location)?;
@@ -250,6 +318,17 @@ impl<'a, S> Build<'a, S> {
         Ok(self)
     }
 
+    /// Modify the current [Build] to configure the given [ParseVisitor].
+    ///
+    /// A parse visitor is called for every top-level item as it's parsed,
+    /// before it is compiled. Like if you want to build an outline of a
+    /// document.
+    #[inline]
+    pub fn with_parse_visitor(mut self, parse_visitor: &'a mut dyn ParseVisitor) -> Self {
+        self.parse_visitor = Some(parse_visitor);
+        self
+    }
+
     /// Modify the current [Build] to configure the given [SourceLoader].
     ///
     /// Source loaders are used to determine how sources are loaded externally
@@ -260,8 +339,125 @@ impl<'a, S> Build<'a, S> {
         self
     }
 
+    /// Modify the current [Build] to rewrite the path of every constant item
+    /// with the given function.
+    ///
+    /// The rewritten path is what compile visitors see when constants are
+    /// registered, what [Build::build_consts] returns, and what constants are
+    /// stored under in the built [Unit]. Constants are still referred to by
+    /// their original path in the sources. It's an error for two constants to
+    /// be rewritten to the same path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Source, Sources};
+    /// use rune::compile::{Item, ItemBuf};
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(Source::memory("const MAX = 10;")?)?;
+    ///
+    /// let prefix = |item: &Item| -> rune::alloc::Result<ItemBuf> {
+    ///     let mut path = ItemBuf::with_item(["myplugin"])?;
+    ///     path.extend(item)?;
+    ///     Ok(path)
+    /// };
+    ///
+    /// let consts = rune::prepare(&mut sources)
+    ///     .with_const_path(&prefix)
+    ///     .build_consts()?;
+    ///
+    /// assert_eq!(consts[0].0.to_string(), "myplugin::MAX");
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    #[inline]
+    pub fn with_const_path(mut self, const_path: ConstPath<'a>) -> Self {
+        self.const_path = Some(const_path);
+        self
+    }
+
     /// Build a [`Unit`] with the current configuration.
-    pub fn build(mut self) -> Result<Unit<S>, BuildError>
+    pub fn build(self) -> Result<Unit<S>, BuildError>
+    where
+        S: Default + UnitEncoder,
+    {
+        self.compile(
+            None,
+            |mut unit, context, diagnostics, options, unit_storage| {
+                if options.link_checks {
+                    unit.link(context, diagnostics)?;
+                }
+
+                if diagnostics.has_error() {
+                    return Err(BuildError::default());
+                }
+
+                match unit.build(Span::empty(), unit_storage) {
+                    Ok(unit) => Ok(unit),
+                    Err(error) => {
+                        diagnostics.error(SourceId::empty(), error)?;
+                        Err(BuildError::default())
+                    }
+                }
+            },
+        )
+    }
+
+    /// Only evaluate the constant items in the current configuration, without
+    /// assembling any functions.
+    ///
+    /// This returns the value of every constant item, ordered by item. Errors
+    /// in functions which aren't used by constants are not reported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Source, Sources};
+    /// use rune::runtime::ConstValue;
+    ///
+    /// let mut sources = Sources::new();
+    ///
+    /// sources.insert(Source::memory(r#"
+    /// const A = 1 + 2;
+    /// const B = A * 2;
+    ///
+    /// pub fn main() {
+    ///     this_is_not_checked()
+    /// }
+    /// "#)?)?;
+    ///
+    /// let consts = rune::prepare(&mut sources).build_consts()?;
+    ///
+    /// let values = consts
+    ///     .iter()
+    ///     .map(|(item, value)| (item.to_string(), matches!(value, ConstValue::Integer(..))))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(values, [(String::from("A"), true), (String::from("B"), true)]);
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn build_consts(self) -> Result<Vec<(ItemBuf, ConstValue)>, BuildError>
+    where
+        S: Default + UnitEncoder,
+    {
+        let mut consts = Vec::new();
+        self.compile(Some(&mut consts), |_, _, _, _, _| Ok(()))?;
+        Ok(consts)
+    }
+
+    /// Compile the sources with the current configuration, and pass the
+    /// result to `finish` if compilation was successful.
+    fn compile<T>(
+        mut self,
+        consts: Option<&mut Vec<(ItemBuf, ConstValue)>>,
+        finish: impl FnOnce(
+            compile::UnitBuilder,
+            &Context,
+            &mut Diagnostics,
+            &Options,
+            S,
+        ) -> Result<T, BuildError>,
+    ) -> Result<T, BuildError>
     where
         S: Default + UnitEncoder,
     {
@@ -319,6 +515,16 @@ impl<'a, S> Build<'a, S> {
             }
         };
 
+        let mut default_parse_visitor;
+
+        let parse_visitor = match self.parse_visitor.take() {
+            Some(parse_visitor) => parse_visitor,
+            None => {
+                default_parse_visitor = NoopParseVisitor::new();
+                &mut default_parse_visitor
+            }
+        };
+
         let mut default_source_loader;
 
         let source_loader = match self.source_loader.take() {
@@ -339,30 +545,19 @@ impl<'a, S> Build<'a, S> {
             &mut pool,
             context,
             visitors,
+            parse_visitor,
             diagnostics,
             source_loader,
             options,
+            self.const_path,
             &mut unit_storage,
+            consts,
         )?;
 
         if diagnostics.has_error() {
             return Err(BuildError::default());
         }
 
-        if options.link_checks {
-            unit.link(context, diagnostics)?;
-        }
-
-        if diagnostics.has_error() {
-            return Err(BuildError::default());
-        }
-
-        match unit.build(Span::empty(), unit_storage) {
-            Ok(unit) => Ok(unit),
-            Err(error) => {
-                diagnostics.error(SourceId::empty(), error)?;
-                Err(BuildError::default())
-            }
-        }
+        finish(unit, context, diagnostics, options, unit_storage)
     }
 }
@@ -9,14 +9,24 @@ pub(crate) use self::assembly::{Assembly, AssemblyInst};
 pub(crate) mod attrs;
 
 pub(crate) mod error;
-pub use self::error::{Error, ImportStep, MetaError};
+pub use self::error::{ConstErrorKind, Error, ImportStep, MetaError};
 pub(crate) use self::error::{ErrorKind, IrErrorKind};
 
+mod const_graph;
+pub use self::const_graph::ConstGraph;
+
 mod compile_visitor;
 pub use self::compile_visitor::CompileVisitor;
 #[cfg(feature = "std")]
 pub(crate) use self::compile_visitor::NoopCompileVisitor;
 
+mod fold_step;
+pub use self::fold_step::FoldStep;
+
+mod parse_visitor;
+pub(crate) use self::parse_visitor::NoopParseVisitor;
+pub use self::parse_visitor::ParseVisitor;
+
 pub(crate) mod context;
 pub use self::context::Context;
 
@@ -38,7 +48,7 @@ pub use rune_core::{Component, ComponentRef, IntoComponent, Item, ItemBuf};
 
 mod source_loader;
 #[cfg(feature = "std")]
-pub use self::source_loader::FileSourceLoader;
+pub use self::source_loader::{FileIncludeSourceLoader, FileSourceLoader};
 pub use self::source_loader::{NoopSourceLoader, SourceLoader};
 
 mod unit_builder;
@@ -229,3 +229,22 @@ impl Attribute for Doc {
     /// Must match the specified name.
     const PATH: &'static str = "doc";
 }
+
+/// Conditionally include an item if the given flag is enabled, like
+/// `#[when("gpu")]`.
+#[derive(Parse)]
+pub(crate) struct When {
+    /// The open parenthesis.
+    #[allow(dead_code)]
+    pub open: T!['('],
+    /// The flag which must be enabled.
+    pub flag: LitStr,
+    /// The close parenthesis.
+    #[allow(dead_code)]
+    pub close: T![')'],
+}
+
+impl Attribute for When {
+    /// Must match the specified name.
+    const PATH: &'static str = "when";
+}
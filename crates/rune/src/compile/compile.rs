@@ -4,15 +4,16 @@ use crate::ast;
 use crate::ast::{Span, Spanned};
 use crate::compile::v1;
 use crate::compile::{
-    self, Assembly, CompileVisitor, Context, ErrorKind, Location, Options, Pool, Prelude,
-    SourceLoader, UnitBuilder,
+    self, Assembly, CompileVisitor, Context, ErrorKind, ItemBuf, Location, Options, ParseVisitor,
+    Pool, Prelude, SourceLoader, UnitBuilder,
 };
 use crate::hir;
 use crate::indexing::FunctionAst;
 use crate::macros::Storage;
 use crate::parse::Resolve;
-use crate::query::{Build, BuildEntry, GenericsParameters, Query, Used};
+use crate::query::{Build, BuildEntry, ConstPath, GenericsParameters, Query, Used};
 use crate::runtime::unit::UnitEncoder;
+use crate::runtime::ConstValue;
 use crate::shared::{Consts, Gen};
 use crate::worker::{LoadFileKind, Task, Worker};
 use crate::{Diagnostics, Sources};
@@ -25,10 +26,13 @@ pub(crate) fn compile(
     pool: &mut Pool,
     context: &Context,
     visitor: &mut dyn CompileVisitor,
+    parse_visitor: &mut dyn ParseVisitor,
     diagnostics: &mut Diagnostics,
     source_loader: &mut dyn SourceLoader,
     options: &Options,
+    const_path: Option<ConstPath<'_>>,
     unit_storage: &mut dyn UnitEncoder,
+    const_values: Option<&mut Vec<(ItemBuf, ConstValue)>>,
 ) -> alloc::Result<()> {
     // Shared id generator.
     let gen = Gen::new();
@@ -46,9 +50,11 @@ pub(crate) fn compile(
         sources,
         pool,
         visitor,
+        parse_visitor,
         diagnostics,
         source_loader,
         options,
+        const_path,
         &gen,
         context,
         &mut inner,
@@ -99,6 +105,17 @@ pub(crate) fn compile(
             tracing::trace!(item = ?worker.q.pool.item(entry.item_meta.item), "next build entry");
             let source_id = entry.item_meta.location.source_id;
 
+            // When only building constants, skip assembling anything which
+            // only exists at runtime.
+            if const_values.is_some()
+                && matches!(
+                    entry.build,
+                    Build::Function(..) | Build::Closure(..) | Build::AsyncBlock(..)
+                )
+            {
+                continue;
+            }
+
             let task = CompileBuildEntry {
                 options,
                 q: worker.q.borrow(),
@@ -120,6 +137,17 @@ pub(crate) fn compile(
         }
     }
 
+    if let Some(const_values) = const_values {
+        worker.q.const_values(const_values)?;
+    }
+
+    if options.const_graph {
+        worker
+            .q
+            .visitor
+            .visit_const_graph(&worker.q.inner.const_graph);
+    }
+
     Ok(())
 }
 
@@ -1,5 +1,5 @@
-use crate::ast::Spanned;
-use crate::compile::{Item, Located, MetaError, MetaRef};
+use crate::ast::{self, Span, Spanned};
+use crate::compile::{ConstGraph, FoldStep, Item, Located, MetaError, MetaRef};
 use crate::hash::Hash;
 use crate::SourceId;
 
@@ -25,6 +25,69 @@ pub trait CompileVisitor {
         Ok(())
     }
 
+    /// Visit an attribute which has been rejected on an item, right before the
+    /// error for it is raised.
+    fn visit_rejected_attribute(
+        &mut self,
+        _source_id: SourceId,
+        _item_span: &dyn Spanned,
+        _attr_span: &dyn Spanned,
+        _name: &str,
+    ) -> Result<(), MetaError> {
+        Ok(())
+    }
+
+    /// Visit a constant whose evaluation exceeded its budget, right before the
+    /// error for it is raised.
+    fn visit_budget_exceeded(
+        &mut self,
+        _source_id: SourceId,
+        _item: &Item,
+        _span: Span,
+        _steps: usize,
+    ) -> Result<(), MetaError> {
+        Ok(())
+    }
+
+    /// Visit the steps which were taken to fold a constant, right after it has
+    /// been evaluated.
+    ///
+    /// This is only called if [`Options::explain_const`] is enabled. If
+    /// evaluation failed, the steps are the ones which completed before the
+    /// error.
+    ///
+    /// [`Options::explain_const`]: crate::Options::explain_const
+    fn visit_const_explained(
+        &mut self,
+        _source_id: SourceId,
+        _item: &Item,
+        _steps: &[FoldStep],
+    ) -> Result<(), MetaError> {
+        Ok(())
+    }
+
+    /// Visit the graph of dependencies between constants once compilation has
+    /// finished.
+    ///
+    /// This is only called if [`Options::const_graph`] is enabled.
+    ///
+    /// [`Options::const_graph`]: crate::Options::const_graph
+    fn visit_const_graph(&mut self, _graph: &ConstGraph) {}
+
+    /// Visit the items which a macro call in item position expanded into,
+    /// right after it has been expanded.
+    ///
+    /// Items which are themselves macro calls are expanded later, and are
+    /// visited separately.
+    fn visit_macro_expansion(
+        &mut self,
+        _source_id: SourceId,
+        _call_span: Span,
+        _expanded: &[ast::Item],
+    ) -> Result<(), MetaError> {
+        Ok(())
+    }
+
     /// Visit something that is a module.
     fn visit_mod(&mut self, _location: &dyn Located) -> Result<(), MetaError> {
         Ok(())
@@ -0,0 +1,73 @@
+use crate::alloc::prelude::*;
+use crate::alloc::{self, BTreeSet, HashMap, Vec};
+use crate::compile::{Item, ItemBuf};
+use crate::Hash;
+
+/// A graph of constant items and constant functions, and which other
+/// constants and constant functions each of them depends on.
+///
+/// The graph is only recorded if [`Options::const_graph`] is enabled, and is
+/// passed to [`CompileVisitor::visit_const_graph`] once compilation has
+/// finished.
+///
+/// [`Options::const_graph`]: crate::Options::const_graph
+/// [`CompileVisitor::visit_const_graph`]: crate::compile::CompileVisitor::visit_const_graph
+#[derive(Debug, Default)]
+pub struct ConstGraph {
+    /// The items in the graph, in the order they were first encountered.
+    nodes: Vec<ItemBuf>,
+    /// Index of nodes by the type hash of their item.
+    hashes: HashMap<Hash, usize>,
+    /// Dependencies between nodes, as pairs of indexes into `nodes`.
+    edges: BTreeSet<(usize, usize)>,
+}
+
+impl ConstGraph {
+    /// Iterate over the items in the graph, in the order they were first
+    /// encountered.
+    pub fn nodes(&self) -> impl Iterator<Item = &Item> + '_ {
+        self.nodes.iter().map(|item| &**item)
+    }
+
+    /// Iterate over the dependencies in the graph, as pairs of an item and an
+    /// item which it depends on.
+    pub fn edges(&self) -> impl Iterator<Item = (&Item, &Item)> + '_ {
+        self.edges
+            .iter()
+            .map(|&(from, to)| (&*self.nodes[from], &*self.nodes[to]))
+    }
+
+    /// Iterate over the items which the given item depends on.
+    pub fn dependencies<'a>(&'a self, item: &Item) -> impl Iterator<Item = &'a Item> + 'a {
+        let index = self.nodes.iter().position(|node| **node == *item);
+
+        self.edges
+            .iter()
+            .filter(move |&&(from, _)| Some(from) == index)
+            .map(|&(_, to)| &*self.nodes[to])
+    }
+
+    /// Get the node for the item with the given type hash, if it's in the
+    /// graph.
+    pub(crate) fn node(&self, hash: Hash) -> Option<usize> {
+        self.hashes.get(&hash).copied()
+    }
+
+    /// Insert a node for the given item, returning its index.
+    pub(crate) fn insert_node(&mut self, hash: Hash, item: &Item) -> alloc::Result<usize> {
+        if let Some(index) = self.node(hash) {
+            return Ok(index);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.try_push(item.try_to_owned()?)?;
+        self.hashes.try_insert(hash, index)?;
+        Ok(index)
+    }
+
+    /// Insert a dependency between two nodes.
+    pub(crate) fn insert_edge(&mut self, from: usize, to: usize) -> alloc::Result<()> {
+        self.edges.try_insert((from, to))?;
+        Ok(())
+    }
+}
@@ -17,8 +17,8 @@ use crate::module::{
     TypeSpecification,
 };
 use crate::runtime::{
-    AttributeMacroHandler, ConstValue, FunctionHandler, MacroHandler, Protocol, RuntimeContext,
-    StaticType, TypeCheck, TypeInfo, VariantRtti,
+    AttributeMacroHandler, ConstKindHandler, ConstValue, FunctionHandler, MacroHandler, Protocol,
+    RuntimeContext, StaticType, TypeCheck, TypeInfo, VariantRtti,
 };
 use crate::Hash;
 
@@ -95,6 +95,10 @@ pub struct Context {
     item_to_hash: HashMap<ItemBuf, BTreeSet<Hash>>,
     /// Registered native function handlers.
     functions: hash::Map<Arc<FunctionHandler>>,
+    /// Native functions which are safe to call in constant contexts.
+    const_functions: HashSet<Hash>,
+    /// Host types which can be used as values in constant contexts.
+    const_kinds: HashMap<Hash, ConstKindHandler>,
     /// Registered deprecation mesages for native functions.
     deprecations: hash::Map<String>,
     /// Information on associated types.
@@ -321,6 +325,22 @@ impl Context {
         self.functions.get(&hash)
     }
 
+    /// Lookup the given native function handler in the context, but only if
+    /// it is safe to call in constant contexts.
+    pub(crate) fn lookup_const_function(&self, hash: Hash) -> Option<&Arc<FunctionHandler>> {
+        if !self.const_functions.contains(&hash) {
+            return None;
+        }
+
+        self.functions.get(&hash)
+    }
+
+    /// Lookup the handler used to convert values of the given host type into
+    /// constant values.
+    pub(crate) fn lookup_const_kind(&self, hash: Hash) -> Option<ConstKindHandler> {
+        self.const_kinds.get(&hash).copied()
+    }
+
     /// Get all associated types for the given hash.
     #[cfg(feature = "doc")]
     pub(crate) fn associated(&self, hash: Hash) -> impl Iterator<Item = Hash> + '_ {
@@ -436,6 +456,10 @@ impl Context {
             type_parameters: ty.type_parameters,
         })?;
 
+        if let Some(handler) = ty.const_kind {
+            self.const_kinds.try_insert(ty.hash, handler)?;
+        }
+
         let parameters = Hash::EMPTY.with_type_parameters(ty.type_parameters);
 
         let kind = if let Some(spec) = &ty.spec {
@@ -652,6 +676,10 @@ impl Context {
 
                 self.insert_native_fn(hash, &f.handler, module_item.common.deprecated.as_deref())?;
 
+                if f.const_fn {
+                    self.const_functions.try_insert(hash)?;
+                }
+
                 meta::Kind::Function {
                     associated: None,
                     signature,
@@ -854,10 +882,18 @@ impl Context {
                     )?;
 
                     self.insert_native_fn(*hash, &f.handler, assoc.common.deprecated.as_deref())?;
+
+                    if f.const_fn {
+                        self.const_functions.try_insert(*hash)?;
+                    }
                 }
 
                 self.insert_native_fn(hash, &f.handler, assoc.common.deprecated.as_deref())?;
 
+                if f.const_fn {
+                    self.const_functions.try_insert(hash)?;
+                }
+
                 meta::Kind::Function {
                     associated: Some(assoc.name.kind.try_clone()?),
                     signature,
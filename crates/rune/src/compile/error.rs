@@ -57,6 +57,47 @@ impl Error {
         }
     }
 
+    /// Get the category of the error if it was raised when evaluating a
+    /// constant expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::compile::ConstErrorKind;
+    /// use rune::diagnostics::{Diagnostic, FatalDiagnosticKind};
+    /// use rune::{Diagnostics, Sources};
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(rune::Source::memory(
+    ///     "const A = 9223372036854775807 + 1; pub fn main() { A }",
+    /// )?)?;
+    ///
+    /// let mut diagnostics = Diagnostics::new();
+    ///
+    /// let result = rune::prepare(&mut sources)
+    ///     .with_diagnostics(&mut diagnostics)
+    ///     .build();
+    ///
+    /// assert!(result.is_err());
+    ///
+    /// let Some(Diagnostic::Fatal(fatal)) = diagnostics.diagnostics().first() else {
+    ///     panic!("expected a fatal diagnostic");
+    /// };
+    ///
+    /// let FatalDiagnosticKind::CompileError(error) = fatal.kind() else {
+    ///     panic!("expected a compile error");
+    /// };
+    ///
+    /// assert_eq!(error.const_error_kind(), Some(ConstErrorKind::IntegerOverflow));
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn const_error_kind(&self) -> Option<ConstErrorKind> {
+        match &*self.kind {
+            ErrorKind::IrError(error) => Some(error.const_error_kind()),
+            _ => None,
+        }
+    }
+
     /// Get the kind of the error.
     #[cfg(feature = "emit")]
     pub(crate) fn kind(&self) -> &ErrorKind {
@@ -157,7 +198,35 @@ impl Error {
     where
         S: Spanned,
     {
-        Self::new(spanned, ErrorKind::ExpectedMeta { meta, expected })
+        Self::new(
+            spanned,
+            ErrorKind::ExpectedMeta {
+                meta,
+                expected,
+                needed_at: None,
+            },
+        )
+    }
+
+    /// An error raised when we expect a certain meta variant, with the span
+    /// of what established the expectation if it is known.
+    pub(crate) fn expected_meta_needed_at<S>(
+        spanned: S,
+        meta: MetaInfo,
+        expected: &'static str,
+        needed_at: Option<Span>,
+    ) -> Self
+    where
+        S: Spanned,
+    {
+        Self::new(
+            spanned,
+            ErrorKind::ExpectedMeta {
+                meta,
+                expected,
+                needed_at,
+            },
+        )
     }
 
     /// Construct an resolve expected error.
@@ -242,6 +311,11 @@ pub(crate) enum ErrorKind {
     ModNotFound {
         path: PathBuf,
     },
+    #[cfg(feature = "std")]
+    IncludeError {
+        path: PathBuf,
+        error: std::io::Error,
+    },
     ModAlreadyLoaded {
         item: ItemBuf,
         #[cfg(feature = "emit")]
@@ -372,6 +446,7 @@ pub(crate) enum ErrorKind {
     ExpectedMeta {
         expected: &'static str,
         meta: MetaInfo,
+        needed_at: Option<Span>,
     },
     NoSuchBuiltInMacro {
         name: Box<str>,
@@ -538,6 +613,7 @@ cfg_std! {
                 ErrorKind::MissingId(source) => Some(source),
                 ErrorKind::UnescapeError(source) => Some(source),
                 ErrorKind::SourceError { error, .. } => Some(error),
+                ErrorKind::IncludeError { error, .. } => Some(error),
                 _ => None,
             }
         }
@@ -608,6 +684,14 @@ impl fmt::Display for ErrorKind {
                     path = path.display()
                 )?;
             }
+            #[cfg(feature = "std")]
+            ErrorKind::IncludeError { path, error } => {
+                write!(
+                    f,
+                    "Failed to include `{path}`: {error}",
+                    path = path.display(),
+                )?;
+            }
             ErrorKind::ModAlreadyLoaded { item, .. } => {
                 write!(f, "Module `{item}` has already been loaded")?;
             }
@@ -827,7 +911,7 @@ impl fmt::Display for ErrorKind {
             ErrorKind::UnsupportedVisibility => {
                 write!(f, "Visibility modifier not supported")?;
             }
-            ErrorKind::ExpectedMeta { expected, meta } => {
+            ErrorKind::ExpectedMeta { expected, meta, .. } => {
                 write!(f, "Expected {expected} but got `{meta}`",)?;
             }
             ErrorKind::NoSuchBuiltInMacro { name } => {
@@ -1157,8 +1241,18 @@ pub(crate) enum IrErrorKind {
     /// Encountered an expression that is not supported as a constant
     /// expression.
     NotConst,
+    /// Encountered an expression which is not supported as a constant
+    /// expression for the given reason.
+    Unsupported {
+        /// Why the expression is not supported.
+        reason: &'static str,
+    },
     /// Trying to process a cycle of constants.
-    ConstCycle,
+    ConstCycle {
+        /// The chain of constants and constant functions which forms the
+        /// cycle, where the first and the last item are the same.
+        path: Box<[ItemBuf]>,
+    },
     /// Encountered a compile meta used in an inappropriate position.
     UnsupportedMeta {
         /// Unsupported compile meta.
@@ -1172,11 +1266,14 @@ pub(crate) enum IrErrorKind {
         actual: TypeInfo,
     },
     /// Exceeded evaluation budget.
-    BudgetExceeded,
-    /// Missing a tuple index.
+    BudgetExceeded {
+        /// The number of steps in the budget.
+        limit: usize,
+    },
+    /// Missing a tuple or vector index.
     MissingIndex {
         /// The index that was missing.
-        index: usize,
+        index: i64,
     },
     /// Missing an object field.
     MissingField {
@@ -1190,10 +1287,44 @@ pub(crate) enum IrErrorKind {
     },
     /// Error raised when trying to use a break outside of a loop.
     BreakOutsideOfLoop,
+    /// Error raised when trying to use a continue outside of a loop.
+    ContinueOutsideOfLoop,
     ArgumentCountMismatch {
         actual: usize,
         expected: usize,
     },
+    /// An integer operation overflowed.
+    IntegerOverflow,
+    /// A collection exceeded the maximum length allowed in constant contexts.
+    CollectionTooLarge {
+        /// The maximum length of a collection.
+        max: usize,
+    },
+    /// The recursion limit for constant evaluation was exceeded.
+    RecursionLimit {
+        /// The recursion limit.
+        limit: usize,
+    },
+    /// A panic was reached when evaluating a constant.
+    Panic {
+        /// The message of the panic.
+        message: Box<str>,
+    },
+    /// Exceeded the total number of bytes which can be allocated in constant
+    /// contexts.
+    AllocationLimit {
+        /// The maximum number of bytes.
+        limit: usize,
+    },
+    /// A native function was called when evaluating a constant while pure
+    /// constants are required.
+    ImpureConst {
+        /// The chain of constants and constant functions which led to the
+        /// call, starting with the constant being evaluated.
+        path: Box<[ItemBuf]>,
+        /// The native function which was called.
+        function: Box<str>,
+    },
 }
 
 cfg_std! {
@@ -1206,8 +1337,21 @@ impl fmt::Display for IrErrorKind {
             IrErrorKind::NotConst => {
                 write!(f, "Expected a constant expression")?;
             }
-            IrErrorKind::ConstCycle => {
-                write!(f, "Constant cycle detected")?;
+            IrErrorKind::Unsupported { reason } => {
+                write!(f, "{reason}")?;
+            }
+            IrErrorKind::ConstCycle { path } => {
+                write!(f, "Constant cycle detected: ")?;
+
+                let mut it = path.iter();
+
+                if let Some(item) = it.next() {
+                    write!(f, "{item}")?;
+                }
+
+                for item in it {
+                    write!(f, " -> {item}")?;
+                }
             }
             IrErrorKind::UnsupportedMeta { meta } => {
                 write!(f, "Item `{meta}` is not supported here",)?
@@ -1215,8 +1359,8 @@ impl fmt::Display for IrErrorKind {
             IrErrorKind::Expected { expected, actual } => {
                 write!(f, "Expected a value of type {expected} but got {actual}",)?
             }
-            IrErrorKind::BudgetExceeded => {
-                write!(f, "Evaluation budget exceeded")?;
+            IrErrorKind::BudgetExceeded { limit } => {
+                write!(f, "Evaluation budget of {limit} steps exceeded")?;
             }
             IrErrorKind::MissingIndex { index } => {
                 write!(f, "Missing index {index}",)?;
@@ -1230,18 +1374,133 @@ impl fmt::Display for IrErrorKind {
             IrErrorKind::BreakOutsideOfLoop => {
                 write!(f, "Break outside of supported loop")?;
             }
+            IrErrorKind::ContinueOutsideOfLoop => {
+                write!(f, "Continue outside of supported loop")?;
+            }
             IrErrorKind::ArgumentCountMismatch { actual, expected } => {
                 write!(
                     f,
                     "Argument count mismatch, got {actual} but expected {expected}",
                 )?;
             }
+            IrErrorKind::IntegerOverflow => {
+                write!(f, "Integer overflow")?;
+            }
+            IrErrorKind::CollectionTooLarge { max } => {
+                write!(
+                    f,
+                    "Collection exceeds the maximum length of {max} allowed in constant contexts"
+                )?;
+            }
+            IrErrorKind::RecursionLimit { limit } => {
+                write!(
+                    f,
+                    "Exceeded the recursion limit of {limit} in constant contexts"
+                )?;
+            }
+            IrErrorKind::Panic { message } => {
+                write!(f, "Panicked in constant context: {message}")?;
+            }
+            IrErrorKind::AllocationLimit { limit } => {
+                write!(
+                    f,
+                    "Exceeded the limit of {limit} bytes allocated in constant contexts"
+                )?;
+            }
+            IrErrorKind::ImpureConst { path, function } => {
+                write!(
+                    f,
+                    "Native function `{function}` called when evaluating constant"
+                )?;
+
+                if let Some(item) = path.first() {
+                    write!(f, " `{item}`")?;
+                }
+
+                write!(f, ": ")?;
+
+                for item in path.iter() {
+                    write!(f, "{item} -> ")?;
+                }
+
+                write!(f, "{function}")?;
+            }
         }
 
         Ok(())
     }
 }
 
+impl IrErrorKind {
+    /// Get the public category of the error.
+    fn const_error_kind(&self) -> ConstErrorKind {
+        match self {
+            IrErrorKind::NotConst => ConstErrorKind::NotConst,
+            IrErrorKind::Unsupported { .. } => ConstErrorKind::NotConst,
+            IrErrorKind::ConstCycle { .. } => ConstErrorKind::ConstCycle,
+            IrErrorKind::UnsupportedMeta { .. } => ConstErrorKind::UnsupportedMeta,
+            IrErrorKind::Expected { .. } => ConstErrorKind::Expected,
+            IrErrorKind::BudgetExceeded { .. } => ConstErrorKind::BudgetExceeded,
+            IrErrorKind::MissingIndex { .. } => ConstErrorKind::MissingIndex,
+            IrErrorKind::MissingField { .. } => ConstErrorKind::MissingField,
+            IrErrorKind::MissingConst { .. } => ConstErrorKind::MissingConst,
+            IrErrorKind::BreakOutsideOfLoop => ConstErrorKind::BreakOutsideOfLoop,
+            IrErrorKind::ContinueOutsideOfLoop => ConstErrorKind::ContinueOutsideOfLoop,
+            IrErrorKind::ArgumentCountMismatch { .. } => ConstErrorKind::ArgumentCountMismatch,
+            IrErrorKind::IntegerOverflow => ConstErrorKind::IntegerOverflow,
+            IrErrorKind::CollectionTooLarge { .. } => ConstErrorKind::CollectionTooLarge,
+            IrErrorKind::RecursionLimit { .. } => ConstErrorKind::RecursionLimit,
+            IrErrorKind::Panic { .. } => ConstErrorKind::Panic,
+            IrErrorKind::AllocationLimit { .. } => ConstErrorKind::AllocationLimit,
+            IrErrorKind::ImpureConst { .. } => ConstErrorKind::ImpureConst,
+        }
+    }
+}
+
+/// The category of an error raised when evaluating a constant expression.
+///
+/// See [`Error::const_error_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConstErrorKind {
+    /// Encountered an expression that is not supported as a constant
+    /// expression.
+    NotConst,
+    /// Constants or constant functions which depend on each other in a cycle.
+    ConstCycle,
+    /// Encountered an item used in an inappropriate position.
+    UnsupportedMeta,
+    /// A value didn't have the expected type.
+    Expected,
+    /// Exceeded the evaluation budget.
+    BudgetExceeded,
+    /// Missing a tuple or vector index.
+    MissingIndex,
+    /// Missing an object field.
+    MissingField,
+    /// Missing a constant or local variable.
+    MissingConst,
+    /// A break outside of a loop.
+    BreakOutsideOfLoop,
+    /// A continue outside of a loop.
+    ContinueOutsideOfLoop,
+    /// A constant function was called with the wrong number of arguments.
+    ArgumentCountMismatch,
+    /// An integer operation overflowed.
+    IntegerOverflow,
+    /// A collection exceeded the maximum length allowed in constant contexts.
+    CollectionTooLarge,
+    /// The recursion limit for constant evaluation was exceeded.
+    RecursionLimit,
+    /// A panic was reached.
+    Panic,
+    /// Exceeded the total number of bytes which can be allocated in constant
+    /// contexts.
+    AllocationLimit,
+    /// A native function was called while pure constants are required.
+    ImpureConst,
+}
+
 /// A single step in an import.
 ///
 /// This is used to indicate a step in an import chain in an error message.
@@ -1300,6 +1559,14 @@ pub(crate) enum MetaErrorKind {
         /// Parameters hash.
         parameters: Hash,
     },
+    ConstPathConflict {
+        /// The rewritten path of the constant.
+        item: ItemBuf,
+        /// The original path of the constant we tried to rewrite.
+        current: ItemBuf,
+        /// The original path of the constant which was already rewritten.
+        existing: ItemBuf,
+    },
 }
 
 impl fmt::Display for MetaError {
@@ -1313,6 +1580,13 @@ impl fmt::Display for MetaError {
             } => {
                 write!(f, "Can't insert item `{current}` ({parameters}) because conflicting meta `{existing}` already exists")
             }
+            MetaErrorKind::ConstPathConflict {
+                item,
+                current,
+                existing,
+            } => {
+                write!(f, "Constant `{current}` can't be rewritten to `{item}` since constant `{existing}` already was")
+            }
         }
     }
 }
@@ -0,0 +1,29 @@
+use crate::alloc::Vec;
+use crate::ast::Span;
+use crate::runtime::ConstValue;
+
+/// A single step taken when folding a constant expression.
+///
+/// Steps are only recorded if [`Options::explain_const`] is enabled, and are
+/// passed to [`CompileVisitor::visit_const_explained`] once a constant has been
+/// evaluated.
+///
+/// [`Options::explain_const`]: crate::Options::explain_const
+/// [`CompileVisitor::visit_const_explained`]: crate::compile::CompileVisitor::visit_const_explained
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct FoldStep {
+    /// The span of the expression which was folded.
+    pub span: Span,
+    /// The kind of expression which was folded, like `binary` or `call`.
+    pub kind: &'static str,
+    /// The indexes of the steps which were folded while evaluating this one,
+    /// like the operands of a binary operation.
+    ///
+    /// Since steps are recorded in the order that they complete, these always
+    /// refer to earlier steps.
+    pub inputs: Vec<usize>,
+    /// The value the expression was folded into, or `None` if it produced a
+    /// value which can't be represented as a constant, like an iterator.
+    pub output: Option<ConstValue>,
+}
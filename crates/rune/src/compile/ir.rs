@@ -3,66 +3,125 @@
 //!
 //! This is part of the [Rune Language](https://rune-rs.github.io).
 
+pub(crate) mod builtins;
 pub(crate) mod compiler;
 mod eval;
 mod interpreter;
 pub(crate) mod scopes;
 
-use core::ops::{AddAssign, MulAssign, ShlAssign, ShrAssign, SubAssign};
+use core::iter;
+use core::ops::{ShlAssign, ShrAssign};
 
 use crate as rune;
 use crate::alloc::prelude::*;
-use crate::alloc::{Box, Vec};
+use crate::alloc::{self, Box, HashMap, HashSet, String, Vec};
 use crate::ast::{self, Span, Spanned};
 use crate::compile::ir;
-use crate::compile::{self, WithSpan};
+use crate::compile::{self, IrErrorKind, WithSpan};
 use crate::hir;
 use crate::indexing::index;
 use crate::macros::MacroContext;
 use crate::parse::NonZeroId;
 use crate::query::Used;
-use crate::runtime::{Value, ValueKind};
+use crate::runtime::{ConstValue, TypeCheck, Value, ValueKind, VariantData};
+use crate::Hash;
 
 pub(crate) use self::compiler::Ctxt;
 pub(crate) use self::eval::{eval_ir, EvalOutcome};
-pub(crate) use self::interpreter::{Budget, Interpreter};
+pub(crate) use self::interpreter::{Budget, Explain, Interpreter};
 pub(crate) use self::scopes::Scopes;
 
 impl ast::Expr {
     pub(crate) fn eval(&self, cx: &mut MacroContext<'_, '_, '_>) -> compile::Result<Value> {
+        let ir = self.compile_const(cx, iter::empty())?;
+        let mut ir_interpreter = const_interpreter(cx)?;
+        ir_interpreter.eval_value(&ir, Used::Used)
+    }
+
+    /// Evaluate the expression once for each set of bindings, reusing the
+    /// compiled expression and the interpreter between evaluations.
+    pub(crate) fn eval_each(
+        &self,
+        cx: &mut MacroContext<'_, '_, '_>,
+        bindings: &[HashMap<String, ConstValue>],
+    ) -> compile::Result<Vec<Value>> {
+        let names = bindings
+            .iter()
+            .flat_map(|values| values.keys())
+            .map(String::as_str);
+
+        let ir = self.compile_const(cx, names)?;
+        let mut ir_interpreter = const_interpreter(cx)?;
+        let mut output = Vec::try_with_capacity(bindings.len())?;
+
+        for values in bindings {
+            ir_interpreter.reset();
+            ir_interpreter.scopes.decl_values(values).with_span(self)?;
+            output.try_push(ir_interpreter.eval_value(&ir, Used::Used)?)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Compile the expression into its constant representation, where `names`
+    /// are available as locals.
+    fn compile_const<'n, I>(
+        &self,
+        cx: &mut MacroContext<'_, '_, '_>,
+        names: I,
+    ) -> compile::Result<Ir>
+    where
+        I: IntoIterator<Item = &'n str>,
+    {
         let mut expr = self.try_clone()?;
         index::expr(cx.idx, &mut expr)?;
 
-        let ir = {
-            // TODO: avoid this arena?
-            let arena = hir::Arena::new();
-            let mut hir_ctx = hir::lowering::Ctxt::with_const(
-                &arena,
-                cx.idx.q.borrow(),
-                cx.item_meta.location.source_id,
-            )?;
-            let hir = hir::lowering::expr(&mut hir_ctx, &expr)?;
-
-            let mut cx = Ctxt {
-                source_id: cx.item_meta.location.source_id,
-                q: cx.idx.q.borrow(),
-            };
-
-            compiler::expr(&hir, &mut cx)?
-        };
+        // TODO: avoid this arena?
+        let arena = hir::Arena::new();
+        let mut hir_ctx = hir::lowering::Ctxt::with_const(
+            &arena,
+            cx.idx.q.borrow(),
+            cx.item_meta.location.source_id,
+        )?;
+
+        let mut locals = HashSet::new();
 
-        let mut ir_interpreter = Interpreter {
-            budget: Budget::new(1_000_000),
-            scopes: Scopes::new()?,
-            module: cx.item_meta.module,
+        for name in names {
+            locals.try_insert(name)?;
+        }
+
+        hir_ctx.define_locals(self, locals)?;
+
+        let hir = hir::lowering::expr(&mut hir_ctx, &expr)?;
+
+        let mut cx = Ctxt {
+            source_id: cx.item_meta.location.source_id,
             item: cx.item_meta.item,
             q: cx.idx.q.borrow(),
         };
 
-        ir_interpreter.eval_value(&ir, Used::Used)
+        compiler::expr(&hir, &mut cx)
     }
 }
 
+/// Construct an interpreter for evaluating constants in a macro context.
+fn const_interpreter<'a, 'arena>(
+    cx: &'a mut MacroContext<'_, '_, 'arena>,
+) -> alloc::Result<Interpreter<'a, 'arena>> {
+    Ok(Interpreter {
+        budget: Budget::new(
+            cx.idx.q.options.const_eval_budget,
+            cx.idx.q.options.const_recursion_limit,
+        ),
+        scopes: Scopes::new()?,
+        module: cx.item_meta.module,
+        item: cx.item_meta.item,
+        source_id: cx.item_meta.location.source_id,
+        explain: None,
+        q: cx.idx.q.borrow(),
+    })
+}
+
 macro_rules! decl_kind {
     (
         $(#[$meta:meta])*
@@ -124,8 +183,8 @@ pub(crate) enum IrTargetKind {
     Name(hir::OwnedName),
     /// A field target.
     Field(Box<IrTarget>, Box<str>),
-    /// An index target.
-    Index(Box<IrTarget>, usize),
+    /// An index target. Negative indexes count from the end of vectors.
+    Index(Box<IrTarget>, i64),
 }
 
 decl_kind! {
@@ -136,6 +195,8 @@ decl_kind! {
         Scope(IrScope),
         /// A binary operation.
         Binary(IrBinary),
+        /// A cast using `as`.
+        Cast(IrCast),
         /// Declare a local variable with the value of the operand.
         Decl(IrDecl),
         /// Set the given target.
@@ -153,10 +214,16 @@ decl_kind! {
         Value(Value),
         /// A sequence of conditional branches.
         Branches(IrBranches),
+        /// A match over a value.
+        Match(IrMatch),
         /// A loop.
         Loop(IrLoop),
         /// A break to the given target.
         Break(IrBreak),
+        /// A continue to the given target.
+        Continue(IrContinue),
+        /// A bounded range.
+        Range(IrRange),
         /// Constructing a vector.
         Vec(IrVec),
         /// Constructing a tuple.
@@ -165,9 +232,81 @@ decl_kind! {
         Object(IrObject),
         /// A call.
         Call(IrCall),
+        /// A call to a built-in method.
+        CallMethod(IrCallMethod),
+        /// A call to a built-in function.
+        CallBuiltin(IrCallBuiltin),
+        /// A call to a native function which is safe to call in constant
+        /// contexts.
+        CallNative(IrCallNative),
+        /// A unit struct or variant.
+        Unit(IrUnit),
+        /// A closure which is called in place by built-in methods.
+        Closure(IrClosure),
+    }
+}
+
+impl IrKind {
+    /// A human-readable name for the kind of operation.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            IrKind::Scope(..) => "scope",
+            IrKind::Binary(..) => "binary",
+            IrKind::Cast(..) => "cast",
+            IrKind::Decl(..) => "declaration",
+            IrKind::Set(..) => "set",
+            IrKind::Assign(..) => "assign",
+            IrKind::Template(..) => "template",
+            IrKind::Name(..) => "name",
+            IrKind::Target(..) => "target",
+            IrKind::Value(..) => "value",
+            IrKind::Branches(..) => "branches",
+            IrKind::Match(..) => "match",
+            IrKind::Loop(..) => "loop",
+            IrKind::Break(..) => "break",
+            IrKind::Continue(..) => "continue",
+            IrKind::Range(..) => "range",
+            IrKind::Vec(..) => "vec",
+            IrKind::Tuple(..) => "tuple",
+            IrKind::Object(..) => "object",
+            IrKind::Call(..) => "call",
+            IrKind::CallMethod(..) => "method call",
+            IrKind::CallBuiltin(..) => "builtin call",
+            IrKind::CallNative(..) => "native call",
+            IrKind::Unit(..) => "unit",
+            IrKind::Closure(..) => "closure",
+        }
     }
 }
 
+/// A closure in a constant context.
+///
+/// Closures can't be used as values, they can only be passed directly to
+/// built-in methods which call them in place.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrClosure {
+    /// The span of the closure.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The patterns of the arguments to the closure.
+    pub(crate) args: Vec<IrPat>,
+    /// The body of the closure.
+    pub(crate) body: Box<Ir>,
+}
+
+/// A reference to a unit struct or variant.
+///
+/// These can't be constructed in constant contexts, but can be used as
+/// patterns.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrUnit {
+    /// The span of the unit.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The hash of the unit struct or variant.
+    pub(crate) hash: Hash,
+}
+
 /// An interpeted function.
 #[derive(Debug, TryClone, Spanned)]
 pub(crate) struct IrFn {
@@ -236,6 +375,32 @@ pub(crate) struct IrBinary {
     pub(crate) rhs: Box<Ir>,
 }
 
+/// A cast using `as`.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrCast {
+    /// The span of the cast.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The value being cast.
+    pub(crate) value: Box<Ir>,
+    /// The hash of the type being cast to.
+    pub(crate) ty: Hash,
+}
+
+/// A bounded range, like `0..10` or `'a'..='z'`.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrRange {
+    /// The span of the range.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The start of the range.
+    pub(crate) start: Box<Ir>,
+    /// The end of the range.
+    pub(crate) end: Box<Ir>,
+    /// If the end of the range is inclusive.
+    pub(crate) inclusive: bool,
+}
+
 /// A local variable declaration.
 #[derive(Debug, TryClone, Spanned)]
 pub(crate) struct IrDecl {
@@ -246,6 +411,20 @@ pub(crate) struct IrDecl {
     pub(crate) name: hir::OwnedName,
     /// The value of the variable.
     pub(crate) value: Box<Ir>,
+    /// The type ascribed to the variable, if any.
+    pub(crate) ty: Option<IrDeclType>,
+}
+
+/// A type ascribed to a local variable declaration.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrDeclType {
+    /// The span of the ascribed type.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The hash of the ascribed type.
+    pub(crate) hash: Hash,
+    /// The name of the ascribed type.
+    pub(crate) name: Box<str>,
 }
 
 /// Set a target.
@@ -305,6 +484,33 @@ pub(crate) struct IrBranches {
     pub(crate) default_branch: Option<IrScope>,
 }
 
+/// A match over a value, where the first branch whose pattern and condition
+/// match is evaluated.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrMatch {
+    /// Span of the match.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The expression being matched over.
+    pub(crate) expr: Box<Ir>,
+    /// The branches of the match.
+    pub(crate) branches: Vec<IrMatchBranch>,
+}
+
+/// A single branch in a match.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrMatchBranch {
+    /// Span of the branch.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The pattern to match.
+    pub(crate) pat: IrPat,
+    /// The optional condition of the branch.
+    pub(crate) condition: Option<Ir>,
+    /// The body of the branch.
+    pub(crate) body: Ir,
+}
+
 /// The condition for a branch.
 #[derive(Debug, TryClone, Spanned)]
 pub(crate) enum IrCondition {
@@ -333,19 +539,109 @@ pub(crate) enum IrPat {
     Ignore,
     /// A named binding.
     Binding(hir::OwnedName),
+    /// A literal pattern, like `1` or `"foo"`.
+    Lit(Value),
+    /// An option pattern, like `Some(x)` or `None`.
+    Option(Option<Box<IrPat>>),
+    /// An `Ok(x)` pattern.
+    Ok(Box<IrPat>),
+    /// An `Err(x)` pattern.
+    Err(Box<IrPat>),
+    /// A pattern matching a tuple or unit variant of an enum, like `Enum::A`
+    /// or `Enum::B(x)`.
+    Variant(Hash, Vec<IrPat>),
+    /// A tuple pattern like `(a, b)`, which is open if it ends with `..`.
+    Tuple(Vec<IrPat>, bool),
 }
 
 impl IrPat {
-    fn compile_ast(hir: &hir::Pat<'_>) -> compile::Result<Self> {
+    fn compile_ast(hir: &hir::Pat<'_>, c: &mut Ctxt<'_, '_>) -> compile::Result<Self> {
         match hir.kind {
             hir::PatKind::Ignore => return Ok(ir::IrPat::Ignore),
             hir::PatKind::Path(&hir::PatPathKind::Ident(name)) => {
                 return Ok(ir::IrPat::Binding(hir::Name::Str(name).into_owned()?));
             }
+            hir::PatKind::Path(&hir::PatPathKind::Kind(kind)) => {
+                if let Some(pat) = Self::compile_sequence(kind, &[], c)? {
+                    return Ok(pat);
+                }
+            }
+            hir::PatKind::Sequence(&hir::PatSequence { ref kind, items }) => {
+                if let Some(pat) = Self::compile_sequence(kind, items, c)? {
+                    return Ok(pat);
+                }
+            }
+            hir::PatKind::Lit(expr) => {
+                if let ir::IrKind::Value(value) = ir::compiler::expr(expr, c)?.kind {
+                    return Ok(ir::IrPat::Lit(value));
+                }
+            }
             _ => (),
         }
 
-        Err(compile::Error::msg(hir, "pattern not supported yet"))
+        Err(compile::Error::new(hir, IrErrorKind::NotConst))
+    }
+
+    /// Compile a variant pattern, returning `None` if it's not supported.
+    fn compile_sequence(
+        kind: &hir::PatSequenceKind,
+        items: &[hir::Pat<'_>],
+        c: &mut Ctxt<'_, '_>,
+    ) -> compile::Result<Option<Self>> {
+        let pat = match (kind, items) {
+            (
+                hir::PatSequenceKind::BuiltInVariant {
+                    type_check: TypeCheck::Option(0),
+                },
+                [pat],
+            ) => ir::IrPat::Option(Some(Box::try_new(Self::compile_ast(pat, c)?)?)),
+            (
+                hir::PatSequenceKind::BuiltInVariant {
+                    type_check: TypeCheck::Option(1),
+                },
+                [],
+            ) => ir::IrPat::Option(None),
+            (
+                hir::PatSequenceKind::BuiltInVariant {
+                    type_check: TypeCheck::Result(0),
+                },
+                [pat],
+            ) => ir::IrPat::Ok(Box::try_new(Self::compile_ast(pat, c)?)?),
+            (
+                hir::PatSequenceKind::BuiltInVariant {
+                    type_check: TypeCheck::Result(1),
+                },
+                [pat],
+            ) => ir::IrPat::Err(Box::try_new(Self::compile_ast(pat, c)?)?),
+            (hir::PatSequenceKind::Variant { variant_hash, .. }, items) => {
+                let mut pats = Vec::try_with_capacity(items.len())?;
+
+                for pat in items {
+                    pats.try_push(Self::compile_ast(pat, c)?)?;
+                }
+
+                ir::IrPat::Variant(*variant_hash, pats)
+            }
+            (
+                hir::PatSequenceKind::Anonymous {
+                    type_check: TypeCheck::Tuple,
+                    is_open,
+                    ..
+                },
+                items,
+            ) => {
+                let mut pats = Vec::try_with_capacity(items.len())?;
+
+                for pat in items {
+                    pats.try_push(Self::compile_ast(pat, c)?)?;
+                }
+
+                ir::IrPat::Tuple(pats, *is_open)
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(pat))
     }
 
     fn matches<S>(
@@ -357,10 +653,92 @@ impl IrPat {
     where
         S: Spanned,
     {
+        let span = spanned.span();
+
         match self {
             IrPat::Ignore => Ok(true),
             IrPat::Binding(name) => {
-                interp.scopes.decl(name, value).with_span(spanned)?;
+                interp.scopes.decl(name, value).with_span(span)?;
+                Ok(true)
+            }
+            IrPat::Lit(literal) => Ok(Value::partial_eq(&value, literal)
+                .into_result()
+                .with_span(span)?),
+            IrPat::Option(pat) => {
+                let value = match &*value.borrow_kind_ref().with_span(span)? {
+                    ValueKind::Option(option) => option.clone(),
+                    actual => {
+                        return Err(compile::Error::expected_type::<_, Option<Value>>(
+                            span, actual,
+                        )
+                        .into());
+                    }
+                };
+
+                match (pat, value) {
+                    (Some(pat), Some(value)) => pat.matches(interp, value, span),
+                    (None, None) => Ok(true),
+                    _ => Ok(false),
+                }
+            }
+            IrPat::Ok(pat) | IrPat::Err(pat) => {
+                let value = match &*value.borrow_kind_ref().with_span(span)? {
+                    ValueKind::Result(result) => result.clone(),
+                    actual => {
+                        return Err(compile::Error::expected_type::<_, Result<Value, Value>>(
+                            span, actual,
+                        )
+                        .into());
+                    }
+                };
+
+                match (self, value) {
+                    (IrPat::Ok(..), Ok(value)) | (IrPat::Err(..), Err(value)) => {
+                        pat.matches(interp, value, span)
+                    }
+                    _ => Ok(false),
+                }
+            }
+            IrPat::Variant(hash, pats) => {
+                let values: Vec<Value> = match &*value.borrow_kind_ref().with_span(span)? {
+                    ValueKind::Variant(variant) if variant.rtti().hash == *hash => {
+                        match variant.data() {
+                            VariantData::Empty => Vec::new(),
+                            VariantData::Tuple(tuple) => tuple.iter().cloned().try_collect()?,
+                            VariantData::Struct(..) => return Ok(false),
+                        }
+                    }
+                    _ => return Ok(false),
+                };
+
+                if values.len() != pats.len() {
+                    return Ok(false);
+                }
+
+                for (pat, value) in pats.iter().zip(values) {
+                    if !pat.matches(interp, value, span)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            IrPat::Tuple(pats, is_open) => {
+                let values: Vec<Value> = match &*value.borrow_kind_ref().with_span(span)? {
+                    ValueKind::Tuple(tuple) => tuple.iter().cloned().try_collect()?,
+                    _ => return Ok(false),
+                };
+
+                if values.len() < pats.len() || !is_open && values.len() != pats.len() {
+                    return Ok(false);
+                }
+
+                for (pat, value) in pats.iter().zip(values) {
+                    if !pat.matches(interp, value, span)? {
+                        return Ok(false);
+                    }
+                }
+
                 Ok(true)
             }
         }
@@ -434,6 +812,39 @@ impl IrBreak {
     }
 }
 
+/// A continue operation.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrContinue {
+    /// The span of the continue.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The label of the continue.
+    pub(crate) label: Option<Box<str>>,
+}
+
+impl IrContinue {
+    fn compile_ast(span: Span, hir: &hir::ExprContinue) -> compile::Result<Self> {
+        let label = hir.label.map(TryInto::try_into).transpose()?;
+        Ok(ir::IrContinue { span, label })
+    }
+
+    /// Evaluate the continue into an [ir::EvalOutcome].
+    fn as_outcome(&self, interp: &mut Interpreter<'_, '_>) -> ir::EvalOutcome {
+        let span = self.span();
+
+        if let Err(e) = interp.budget.take(span) {
+            return e.into();
+        }
+
+        let label = match self.label.try_clone() {
+            Ok(label) => label,
+            Err(error) => return error.into(),
+        };
+
+        ir::EvalOutcome::Continue(span, label)
+    }
+}
+
 /// Tuple expression.
 #[derive(Debug, TryClone, Spanned)]
 pub(crate) struct Tuple {
@@ -466,6 +877,46 @@ pub(crate) struct IrCall {
     pub(crate) args: Vec<Ir>,
 }
 
+/// A call to a native function from the context.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrCallNative {
+    /// Span of the call.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The hash of the native function.
+    pub(crate) hash: Hash,
+    /// Arguments to the call.
+    pub(crate) args: Vec<Ir>,
+}
+
+/// A call to a built-in method.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrCallMethod {
+    /// Span of the call.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The value the method is called on.
+    pub(crate) target: Box<Ir>,
+    /// The method being called.
+    #[try_clone(copy)]
+    pub(crate) method: &'static builtins::Method,
+    /// Arguments to the call.
+    pub(crate) args: Vec<Ir>,
+}
+
+/// A call to a built-in function.
+#[derive(Debug, TryClone, Spanned)]
+pub(crate) struct IrCallBuiltin {
+    /// Span of the call.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The function being called.
+    #[try_clone(copy)]
+    pub(crate) function: &'static builtins::Function,
+    /// Arguments to the call.
+    pub(crate) args: Vec<Ir>,
+}
+
 /// Vector expression.
 #[derive(Debug, TryClone, Spanned)]
 pub(crate) struct IrVec {
@@ -498,6 +949,8 @@ pub(crate) enum IrBinaryOp {
     Lte,
     /// `==`,
     Eq,
+    /// `!=`,
+    Neq,
     /// `>`,
     Gt,
     /// `>=`,
@@ -549,13 +1002,19 @@ impl IrAssignOp {
     {
         match self {
             IrAssignOp::Add => {
-                target.add_assign(operand);
+                *target = target
+                    .checked_add(operand)
+                    .ok_or_else(|| compile::Error::new(spanned, IrErrorKind::IntegerOverflow))?;
             }
             IrAssignOp::Sub => {
-                target.sub_assign(operand);
+                *target = target
+                    .checked_sub(operand)
+                    .ok_or_else(|| compile::Error::new(spanned, IrErrorKind::IntegerOverflow))?;
             }
             IrAssignOp::Mul => {
-                target.mul_assign(operand);
+                *target = target
+                    .checked_mul(operand)
+                    .ok_or_else(|| compile::Error::new(spanned, IrErrorKind::IntegerOverflow))?;
             }
             IrAssignOp::Div => {
                 *target = target
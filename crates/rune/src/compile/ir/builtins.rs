@@ -0,0 +1,3014 @@
+//! Built-in functions and methods which can be called in constant contexts.
+
+use core::cmp::Ordering;
+use core::fmt;
+
+use crate::alloc::prelude::*;
+use crate::alloc::{try_format, try_vec, Box, String, Vec};
+use crate::ast::unescape;
+use crate::ast::{Span, Spanned};
+use crate::compile::ir;
+use crate::compile::{self, IrErrorKind, WithSpan};
+use crate::query::Used;
+use crate::runtime::{
+    self, Object, OwnedTuple, UnsupportedProtocolCaller, Value, ValueKind, VariantData,
+};
+use crate::Hash;
+
+/// The signature of a built-in function.
+type FunctionHandler = fn(&mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome>;
+
+/// The signature of a built-in method.
+type MethodHandler = fn(&mut Call<'_, '_, '_>, Value) -> Result<Value, ir::EvalOutcome>;
+
+/// A built-in function which can be called in a constant context.
+pub(crate) struct Function {
+    /// The name of the function.
+    pub(crate) name: &'static str,
+    /// The implementation of the function.
+    handler: FunctionHandler,
+}
+
+impl Function {
+    /// Call the function.
+    pub(crate) fn call(
+        &self,
+        interp: &mut ir::Interpreter<'_, '_>,
+        ir: &ir::IrCallBuiltin,
+        used: Used,
+    ) -> Result<Value, ir::EvalOutcome> {
+        let mut cx = Call {
+            interp,
+            span: ir.span,
+            target: ir.span,
+            args: &ir.args,
+            used,
+        };
+
+        (self.handler)(&mut cx)
+    }
+}
+
+impl fmt::Debug for Function {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// A built-in method which can be called in a constant context.
+pub(crate) struct Method {
+    /// The name of the method.
+    pub(crate) name: &'static str,
+    /// The implementation of the method.
+    handler: MethodHandler,
+}
+
+impl Method {
+    /// Call the method with the given evaluated target.
+    pub(crate) fn call(
+        &self,
+        interp: &mut ir::Interpreter<'_, '_>,
+        ir: &ir::IrCallMethod,
+        target: Value,
+        used: Used,
+    ) -> Result<Value, ir::EvalOutcome> {
+        let mut cx = Call {
+            interp,
+            span: ir.span,
+            target: ir.target.span(),
+            args: &ir.args,
+            used,
+        };
+
+        (self.handler)(&mut cx, target)
+    }
+}
+
+impl fmt::Debug for Method {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Method").field("name", &self.name).finish()
+    }
+}
+
+/// Look up a built-in function by the hash of its name.
+pub(crate) fn function(hash: Hash) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| Hash::ident(f.name) == hash)
+}
+
+/// Look up a built-in function which replaces the native function with the
+/// given hash in constant contexts.
+///
+/// This is used for `panic`, which is a native function in the prelude but
+/// fails compilation when it's reached in a constant context.
+pub(crate) fn native_function(hash: Hash) -> Option<&'static Function> {
+    if hash == Hash::type_hash(["std", "panic"]) {
+        return function(Hash::ident("panic"));
+    }
+
+    None
+}
+
+/// Look up a built-in method which is specialized by its generic parameters,
+/// returning the hash it should be called through.
+///
+/// Methods are otherwise untyped in constant contexts, but `collect::<Object>()`
+/// needs to produce an object rather than a vector, and `try_into::<T>()` needs
+/// to know which type it's converting into.
+pub(crate) fn generic_method(name: &str, parameters: Hash) -> Option<Hash> {
+    use runtime::static_type::{BYTE_TYPE, CHAR_TYPE, INTEGER_TYPE, OBJECT_TYPE};
+
+    if name == "collect" && parameters == Hash::parameters([OBJECT_TYPE.hash]) {
+        return Some(Hash::ident("collect::<Object>"));
+    }
+
+    if name == "try_into" {
+        if parameters == Hash::parameters([BYTE_TYPE.hash]) {
+            return Some(Hash::ident("try_into::<u8>"));
+        }
+
+        if parameters == Hash::parameters([INTEGER_TYPE.hash]) {
+            return Some(Hash::ident("try_into::<i64>"));
+        }
+
+        if parameters == Hash::parameters([CHAR_TYPE.hash]) {
+            return Some(Hash::ident("try_into::<char>"));
+        }
+    }
+
+    None
+}
+
+static FUNCTIONS: &[Function] = &[
+    Function {
+        name: "set_bit",
+        handler: set_bit,
+    },
+    Function {
+        name: "clear_bit",
+        handler: clear_bit,
+    },
+    Function {
+        name: "toggle_bit",
+        handler: toggle_bit,
+    },
+    Function {
+        name: "test_bit",
+        handler: test_bit,
+    },
+    Function {
+        name: "matches",
+        handler: matches,
+    },
+    Function {
+        name: "to_radix",
+        handler: to_radix,
+    },
+    Function {
+        name: "from_radix",
+        handler: from_radix,
+    },
+    Function {
+        name: "escape",
+        handler: escape,
+    },
+    Function {
+        name: "rng",
+        handler: rng,
+    },
+    Function {
+        name: "unescape",
+        handler: unescape,
+    },
+    Function {
+        name: "include_str",
+        handler: include_str,
+    },
+    Function {
+        name: "include_bytes",
+        handler: include_bytes,
+    },
+    Function {
+        name: "char::from_digit",
+        handler: char_from_digit,
+    },
+    Function {
+        name: "panic",
+        handler: panic,
+    },
+    Function {
+        name: "unreachable",
+        handler: unreachable,
+    },
+    Function {
+        name: "hash",
+        handler: hash,
+    },
+    Function {
+        name: "seconds",
+        handler: seconds,
+    },
+    Function {
+        name: "millis",
+        handler: millis,
+    },
+    Function {
+        name: "micros",
+        handler: micros,
+    },
+    Function {
+        name: "Object::from",
+        handler: object_from,
+    },
+];
+
+/// Look up a built-in method by the hash of its name.
+pub(crate) fn method(hash: Hash) -> Option<&'static Method> {
+    METHODS.iter().find(|m| Hash::ident(m.name) == hash)
+}
+
+static METHODS: &[Method] = &[
+    Method {
+        name: "union",
+        handler: union,
+    },
+    Method {
+        name: "intersection",
+        handler: intersection,
+    },
+    Method {
+        name: "difference",
+        handler: difference,
+    },
+    Method {
+        name: "repeat",
+        handler: repeat,
+    },
+    Method {
+        name: "iter",
+        handler: iter,
+    },
+    Method {
+        name: "chars",
+        handler: chars,
+    },
+    Method {
+        name: "lines",
+        handler: lines,
+    },
+    Method {
+        name: "count",
+        handler: iter_count,
+    },
+    Method {
+        name: "filter",
+        handler: filter,
+    },
+    Method {
+        name: "partition",
+        handler: partition,
+    },
+    Method {
+        name: "retain",
+        handler: retain,
+    },
+    Method {
+        name: "entries",
+        handler: entries,
+    },
+    Method {
+        name: "sum",
+        handler: sum,
+    },
+    Method {
+        name: "fold",
+        handler: fold,
+    },
+    Method {
+        name: "reduce",
+        handler: reduce,
+    },
+    Method {
+        name: "min_by_key",
+        handler: min_by_key,
+    },
+    Method {
+        name: "max_by_key",
+        handler: max_by_key,
+    },
+    Method {
+        name: "product",
+        handler: product,
+    },
+    Method {
+        name: "join",
+        handler: join,
+    },
+    Method {
+        name: "chunks",
+        handler: chunks,
+    },
+    Method {
+        name: "windows",
+        handler: windows,
+    },
+    Method {
+        name: "flatten",
+        handler: flatten,
+    },
+    Method {
+        name: "collect",
+        handler: collect,
+    },
+    Method {
+        name: "take",
+        handler: take,
+    },
+    Method {
+        name: "enumerate",
+        handler: enumerate,
+    },
+    Method {
+        name: "collect::<Object>",
+        handler: collect_object,
+    },
+    Method {
+        name: "reverse",
+        handler: reverse,
+    },
+    Method {
+        name: "is_empty",
+        handler: is_empty,
+    },
+    Method {
+        name: "clamp",
+        handler: clamp,
+    },
+    Method {
+        name: "abs",
+        handler: abs,
+    },
+    Method {
+        name: "signum",
+        handler: signum,
+    },
+    Method {
+        name: "checked_add",
+        handler: checked_add,
+    },
+    Method {
+        name: "checked_sub",
+        handler: checked_sub,
+    },
+    Method {
+        name: "checked_mul",
+        handler: checked_mul,
+    },
+    Method {
+        name: "checked_div",
+        handler: checked_div,
+    },
+    Method {
+        name: "wrapping_add",
+        handler: wrapping_add,
+    },
+    Method {
+        name: "wrapping_sub",
+        handler: wrapping_sub,
+    },
+    Method {
+        name: "wrapping_mul",
+        handler: wrapping_mul,
+    },
+    Method {
+        name: "wrapping_shl",
+        handler: wrapping_shl,
+    },
+    Method {
+        name: "wrapping_shr",
+        handler: wrapping_shr,
+    },
+    Method {
+        name: "next",
+        handler: next,
+    },
+    Method {
+        name: "split",
+        handler: split,
+    },
+    Method {
+        name: "splitn",
+        handler: splitn,
+    },
+    Method {
+        name: "starts_with",
+        handler: starts_with,
+    },
+    Method {
+        name: "ends_with",
+        handler: ends_with,
+    },
+    Method {
+        name: "contains",
+        handler: contains,
+    },
+    Method {
+        name: "len",
+        handler: len,
+    },
+    Method {
+        name: "to_uppercase",
+        handler: to_uppercase,
+    },
+    Method {
+        name: "to_lowercase",
+        handler: to_lowercase,
+    },
+    Method {
+        name: "trim",
+        handler: trim,
+    },
+    Method {
+        name: "replace",
+        handler: replace,
+    },
+    Method {
+        name: "find",
+        handler: find,
+    },
+    Method {
+        name: "position",
+        handler: position,
+    },
+    Method {
+        name: "binary_search",
+        handler: binary_search,
+    },
+    Method {
+        name: "to_vec",
+        handler: to_vec,
+    },
+    Method {
+        name: "to_tuple",
+        handler: to_tuple,
+    },
+    Method {
+        name: "map",
+        handler: map,
+    },
+    Method {
+        name: "and_then",
+        handler: and_then,
+    },
+    Method {
+        name: "and",
+        handler: and,
+    },
+    Method {
+        name: "zip",
+        handler: zip,
+    },
+    Method {
+        name: "or_else",
+        handler: or_else,
+    },
+    Method {
+        name: "map_err",
+        handler: map_err,
+    },
+    Method {
+        name: "expect",
+        handler: expect,
+    },
+    Method {
+        name: "fill",
+        handler: fill,
+    },
+    Method {
+        name: "resize",
+        handler: resize,
+    },
+    Method {
+        name: "to_digit",
+        handler: to_digit,
+    },
+    Method {
+        name: "push",
+        handler: push,
+    },
+    Method {
+        name: "insert",
+        handler: insert,
+    },
+    Method {
+        name: "remove",
+        handler: remove,
+    },
+    Method {
+        name: "swap",
+        handler: swap,
+    },
+    Method {
+        name: "rotate_left",
+        handler: rotate_left,
+    },
+    Method {
+        name: "rotate_right",
+        handler: rotate_right,
+    },
+    Method {
+        name: "split_at",
+        handler: split_at,
+    },
+    Method {
+        name: "first",
+        handler: first,
+    },
+    Method {
+        name: "last",
+        handler: last,
+    },
+    Method {
+        name: "get_or_insert",
+        handler: get_or_insert,
+    },
+    Method {
+        name: "has_path",
+        handler: has_path,
+    },
+    Method {
+        name: "get_path",
+        handler: get_path,
+    },
+    Method {
+        name: "try_into::<u8>",
+        handler: try_into_byte,
+    },
+    Method {
+        name: "try_into::<i64>",
+        handler: try_into_integer,
+    },
+    Method {
+        name: "try_into::<char>",
+        handler: try_into_char,
+    },
+];
+
+/// The context of a call to a built-in function or method.
+pub(crate) struct Call<'a, 'b, 'arena> {
+    /// The interpreter the call is evaluated in.
+    pub(crate) interp: &'a mut ir::Interpreter<'b, 'arena>,
+    /// The span of the whole call.
+    pub(crate) span: Span,
+    /// The span of the target of the call, or of the whole call for
+    /// functions.
+    pub(crate) target: Span,
+    /// Unevaluated arguments to the call.
+    pub(crate) args: &'a [ir::Ir],
+    /// Whether the result of the call is used.
+    pub(crate) used: Used,
+}
+
+impl Call<'_, '_, '_> {
+    /// Ensure that the call has the expected number of arguments.
+    pub(crate) fn expect_args(&self, expected: usize) -> compile::Result<()> {
+        if self.args.len() != expected {
+            return Err(compile::Error::new(
+                self.span,
+                IrErrorKind::ArgumentCountMismatch {
+                    actual: self.args.len(),
+                    expected,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the span of the argument at the given index.
+    pub(crate) fn arg_span(&self, index: usize) -> Span {
+        self.args.get(index).map(Spanned::span).unwrap_or(self.span)
+    }
+
+    /// Evaluate the argument at the given index.
+    pub(crate) fn arg(&mut self, index: usize) -> Result<Value, ir::EvalOutcome> {
+        let Some(ir) = self.args.get(index) else {
+            return Err(ir::EvalOutcome::not_const(self.span));
+        };
+
+        ir::eval_ir(ir, self.interp, self.used)
+    }
+}
+
+/// Get a non-negative count out of a value.
+fn count(value: &Value, span: Span) -> compile::Result<usize> {
+    let count = value.as_integer().with_span(span)?;
+
+    let Ok(count) = usize::try_from(count) else {
+        return Err(compile::Error::msg(span, "Count must not be negative"));
+    };
+
+    Ok(count)
+}
+
+/// Get a bit index out of a value.
+fn bit(value: &Value, span: Span) -> compile::Result<u32> {
+    let bit = value.as_integer().with_span(span)?;
+
+    match u32::try_from(bit) {
+        Ok(bit) if bit < i64::BITS => Ok(bit),
+        _ => Err(compile::Error::msg(
+            span,
+            try_format!("Bit index {bit} is out of range 0 to {}", i64::BITS - 1),
+        )),
+    }
+}
+
+/// Copy the elements out of a vector.
+fn vec_elements(value: &Value, span: Span) -> compile::Result<Vec<Value>> {
+    match &*value.borrow_kind_ref().with_span(span)? {
+        ValueKind::Vec(vec) => Ok(vec.iter().cloned().try_collect()?),
+        actual => Err(compile::Error::expected_type::<_, runtime::Vec>(
+            span, actual,
+        )),
+    }
+}
+
+/// Collect the values of a vector, tuple or iterator, charging the budget for
+/// each value.
+fn values(cx: &mut Call<'_, '_, '_>, value: &Value, span: Span) -> compile::Result<Vec<Value>> {
+    let mut output = Vec::new();
+
+    match &mut *value.borrow_kind_mut().with_span(span)? {
+        ValueKind::Vec(vec) => {
+            for value in vec.iter() {
+                cx.interp.budget.take(span)?;
+                output.try_push(value.clone())?;
+            }
+        }
+        ValueKind::Tuple(tuple) => {
+            for value in tuple.iter() {
+                cx.interp.budget.take(span)?;
+                output.try_push(value.clone())?;
+            }
+        }
+        ValueKind::Iterator(iter) => {
+            while let Some(value) = iter.next().into_result().with_span(span)? {
+                cx.interp.budget.take(span)?;
+                cx.interp.check_len(span, output.len() + 1)?;
+                cx.interp.alloc_values(span, 1)?;
+                output.try_push(value)?;
+            }
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Iterator>(
+                span, actual,
+            ));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Construct an iterator value over the given values.
+fn iter_value(name: &'static str, values: Vec<Value>, span: Span) -> compile::Result<Value> {
+    let iter = runtime::Iterator::from_double_ended(name, values.into_iter());
+    Ok(Value::try_from(iter).with_span(span)?)
+}
+
+/// Construct a vector value.
+fn vec_value(values: Vec<Value>, span: Span) -> compile::Result<Value> {
+    Ok(Value::try_from(runtime::Vec::from(values)).with_span(span)?)
+}
+
+/// Test if `values` contains a value equal to `value`, which takes a step from
+/// the budget for every comparison.
+fn contains_value(
+    cx: &mut Call<'_, '_, '_>,
+    values: &[Value],
+    value: &Value,
+) -> compile::Result<bool> {
+    for current in values {
+        cx.interp.budget.take(cx.span)?;
+
+        if Value::partial_eq(current, value)
+            .into_result()
+            .with_span(cx.span)?
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Filter the elements of the target by whether they're present in the
+/// argument, collapsing duplicates.
+fn set_filter(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    keep: bool,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let a = vec_elements(&target, cx.target)?;
+    let other = cx.arg(0)?;
+    let b = vec_elements(&other, cx.arg_span(0))?;
+
+    let mut output = Vec::new();
+
+    for value in a {
+        cx.interp.budget.take(cx.span)?;
+
+        if contains_value(cx, &b, &value)? == keep && !contains_value(cx, &output, &value)? {
+            cx.interp.check_len(cx.span, output.len() + 1)?;
+            cx.interp.alloc_values(cx.span, 1)?;
+            output.try_push(value)?;
+        }
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// Elements which are in either vector, in order of first appearance.
+fn union(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let a = vec_elements(&target, cx.target)?;
+    let other = cx.arg(0)?;
+    let b = vec_elements(&other, cx.arg_span(0))?;
+
+    let mut output = Vec::new();
+
+    for value in a.into_iter().chain(b) {
+        cx.interp.budget.take(cx.span)?;
+
+        if !contains_value(cx, &output, &value)? {
+            cx.interp.check_len(cx.span, output.len() + 1)?;
+            cx.interp.alloc_values(cx.span, 1)?;
+            output.try_push(value)?;
+        }
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// Elements of the target which are also in the argument.
+fn intersection(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    set_filter(cx, target, true)
+}
+
+/// Elements of the target which are not in the argument.
+fn difference(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    set_filter(cx, target, false)
+}
+
+/// Repeat a string the given number of times.
+fn repeat(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    let n = cx.arg(0)?;
+    let n = count(&n, cx.arg_span(0))?;
+
+    cx.interp.budget.take_many(cx.span, n)?;
+    cx.interp
+        .check_len(cx.span, string.len().saturating_mul(n))?;
+    cx.interp.alloc(cx.span, string.len().saturating_mul(n))?;
+
+    let mut output = String::try_with_capacity(string.len().saturating_mul(n))?;
+
+    for _ in 0..n {
+        output.try_push_str(&string)?;
+    }
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Construct an iterator over the values of a collection.
+fn iter(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let values = values(cx, &target, span)?;
+    Ok(iter_value("std::slice::Iter", values, cx.span)?)
+}
+
+/// Construct an iterator over the characters of a string.
+fn chars(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    let mut values = Vec::new();
+
+    for c in string.chars() {
+        cx.interp.budget.take(cx.span)?;
+        cx.interp.check_len(cx.span, values.len() + 1)?;
+        cx.interp.alloc_values(cx.span, 1)?;
+        values.try_push(Value::try_from(c).with_span(cx.span)?)?;
+    }
+
+    Ok(iter_value("std::str::Chars", values, cx.span)?)
+}
+
+/// Iterate over the lines of a string, split on `\n` or `\r\n`.
+///
+/// Like `str::lines`, a trailing line ending doesn't produce an empty line.
+fn lines(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    let mut values = Vec::new();
+
+    for line in string.lines() {
+        cx.interp.budget.take(cx.span)?;
+        cx.interp.check_len(cx.span, values.len() + 1)?;
+        cx.interp.alloc_values(cx.span, 1)?;
+        cx.interp.alloc(cx.span, line.len())?;
+        let line = Value::try_from(String::try_from(line)?).with_span(cx.span)?;
+        values.try_push(line)?;
+    }
+
+    Ok(iter_value("std::str::Lines", values, cx.span)?)
+}
+
+/// Count the number of values produced by an iterator.
+fn iter_count(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let values = values(cx, &target, span)?;
+
+    let Ok(count) = i64::try_from(values.len()) else {
+        return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+    };
+
+    Ok(Value::try_from(count).with_span(cx.span)?)
+}
+
+/// Only keep the values of an iterator for which a closure returns `true`.
+fn filter(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    if matches!(
+        &*target.borrow_kind_ref().with_span(cx.target)?,
+        ValueKind::Object(..)
+    ) {
+        return retain(cx, target);
+    }
+
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+    let span = cx.target;
+    let mut output = Vec::new();
+
+    for value in values(cx, &target, span)? {
+        let keep = call_closure(cx, closure, &[value.clone()])?;
+
+        if keep.as_bool().with_span(closure.span)? {
+            cx.interp.check_len(cx.span, output.len() + 1)?;
+            cx.interp.alloc_values(cx.span, 1)?;
+            output.try_push(value)?;
+        }
+    }
+
+    Ok(iter_value("std::iter::Filter", output, cx.span)?)
+}
+
+/// Split the values of a collection into a tuple of two vectors, the first
+/// containing the values for which a closure returns `true` and the second
+/// the rest.
+fn partition(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+    let span = cx.target;
+    let mut matching = Vec::new();
+    let mut rest = Vec::new();
+
+    for value in values(cx, &target, span)? {
+        let keep = call_closure(cx, closure, &[value.clone()])?;
+
+        let output = if keep.as_bool().with_span(closure.span)? {
+            &mut matching
+        } else {
+            &mut rest
+        };
+
+        cx.interp.check_len(cx.span, output.len() + 1)?;
+        cx.interp.alloc_values(cx.span, 1)?;
+        output.try_push(value)?;
+    }
+
+    cx.interp.alloc_values(cx.span, 2)?;
+    let matching = vec_value(matching, cx.span)?;
+    let rest = vec_value(rest, cx.span)?;
+    let tuple = OwnedTuple::try_from(try_vec![matching, rest])?;
+    Ok(Value::try_from(tuple).with_span(cx.span)?)
+}
+
+/// Construct a new object out of the entries of an object for which a closure
+/// called with the key and the value returns `true`.
+fn retain(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+
+    let mut entries = Vec::new();
+
+    match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Object(object) => {
+            for (key, value) in object.iter() {
+                entries.try_push((key.try_clone()?, value.clone()))?;
+            }
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, Object>(cx.target, actual).into());
+        }
+    }
+
+    let mut output = Object::new();
+
+    for (key, value) in entries {
+        cx.interp.budget.take(cx.span)?;
+        let key_value = Value::try_from(key.try_clone()?).with_span(cx.span)?;
+        let keep = call_closure(cx, closure, &[key_value, value.clone()])?;
+
+        if keep.as_bool().with_span(closure.span)? {
+            output.insert(key, value)?;
+        }
+    }
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Collect the entries of an object into a vector of `(key, value)` tuples,
+/// sorted by key so that the output is deterministic.
+fn entries(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let mut entries = Vec::new();
+
+    match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Object(object) => {
+            for (key, value) in object.iter() {
+                entries.try_push((key.try_clone()?, value.clone()))?;
+            }
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, Object>(cx.target, actual).into());
+        }
+    }
+
+    cx.interp.check_len(cx.span, entries.len())?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut output = Vec::try_with_capacity(entries.len())?;
+
+    for (key, value) in entries {
+        cx.interp.budget.take(cx.span)?;
+        cx.interp.alloc_values(cx.span, 3)?;
+        let key = Value::try_from(key).with_span(cx.span)?;
+        let tuple = OwnedTuple::try_from(try_vec![key, value])?;
+        output.try_push(Value::try_from(tuple).with_span(cx.span)?)?;
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// The absolute value of an integer or a float.
+///
+/// Taking the absolute value of the smallest integer overflows.
+fn abs(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let output = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Integer(value) => {
+            let Some(value) = value.checked_abs() else {
+                return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+            };
+
+            ValueKind::Integer(value)
+        }
+        ValueKind::Float(value) => ValueKind::Float(value.abs()),
+        actual => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Checked conversion of an integer, byte or character into a byte.
+///
+/// Like `TryFrom`, this produces `Err` with a description of the failure
+/// rather than failing compilation when the value is out of range.
+fn try_into_byte(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let result = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Byte(value) => Ok(*value),
+        ValueKind::Integer(value) => {
+            u8::try_from(*value).map_err(|_| "out of range integral type conversion attempted")
+        }
+        ValueKind::Char(value) => {
+            u8::try_from(*value).map_err(|_| "unicode code point out of range for byte")
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    conversion_result(cx, result.map(ValueKind::Byte))
+}
+
+/// Checked conversion of an integer, byte or character into an integer.
+///
+/// This can't fail, but is provided so that `try_into::<i64>()` can be used
+/// generically in constant contexts.
+fn try_into_integer(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let value = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Byte(value) => i64::from(*value),
+        ValueKind::Integer(value) => *value,
+        ValueKind::Char(value) => i64::from(u32::from(*value)),
+        actual => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    conversion_result(cx, Ok(ValueKind::Integer(value)))
+}
+
+/// Checked conversion of an integer or a byte into a character, producing
+/// `Err` if the integer isn't a valid Unicode scalar value.
+fn try_into_char(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let result = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Byte(value) => Ok(char::from(*value)),
+        ValueKind::Integer(value) => u32::try_from(*value)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or("converted integer out of range for `char`"),
+        ValueKind::Char(value) => Ok(*value),
+        actual => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    conversion_result(cx, result.map(ValueKind::Char))
+}
+
+/// Build the `Result` of a checked conversion, where errors are described by a
+/// string.
+fn conversion_result(
+    cx: &mut Call<'_, '_, '_>,
+    result: Result<ValueKind, &'static str>,
+) -> Result<Value, ir::EvalOutcome> {
+    let result = match result {
+        Ok(value) => Ok(Value::try_from(value).with_span(cx.span)?),
+        Err(message) => {
+            cx.interp.alloc(cx.span, message.len())?;
+            Err(Value::try_from(String::try_from(message)?).with_span(cx.span)?)
+        }
+    };
+
+    Ok(Value::try_from(result).with_span(cx.span)?)
+}
+
+/// The sign of an integer or a float.
+///
+/// For integers this is `-1`, `0` or `1`. For floats this is `-1.0` or `1.0`
+/// depending on the sign, including the sign of zero, and `NaN` for `NaN`.
+fn signum(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let output = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Integer(value) => ValueKind::Integer(value.signum()),
+        ValueKind::Float(value) => ValueKind::Float(value.signum()),
+        actual => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Add two integers, producing `None` on overflow.
+fn checked_add(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    checked(cx, target, i64::checked_add)
+}
+
+/// Subtract two integers, producing `None` on overflow.
+fn checked_sub(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    checked(cx, target, i64::checked_sub)
+}
+
+/// Multiply two integers, producing `None` on overflow.
+fn checked_mul(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    checked(cx, target, i64::checked_mul)
+}
+
+/// Divide two integers, producing `None` on overflow or division by zero.
+fn checked_div(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    checked(cx, target, i64::checked_div)
+}
+
+/// Apply a checked integer operation to the target and the only argument,
+/// producing an option.
+fn checked(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    op: fn(i64, i64) -> Option<i64>,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = target.as_integer().with_span(cx.target)?;
+    let span = cx.arg_span(0);
+    let other = cx.arg(0)?;
+    let other = other.as_integer().with_span(span)?;
+
+    let output = match op(value, other) {
+        Some(n) => Some(Value::try_from(n).with_span(cx.span)?),
+        None => None,
+    };
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Add two integers, wrapping around at the boundary of the type.
+fn wrapping_add(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    wrapping(cx, target, i64::wrapping_add)
+}
+
+/// Subtract two integers, wrapping around at the boundary of the type.
+fn wrapping_sub(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    wrapping(cx, target, i64::wrapping_sub)
+}
+
+/// Multiply two integers, wrapping around at the boundary of the type.
+fn wrapping_mul(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    wrapping(cx, target, i64::wrapping_mul)
+}
+
+/// Shift an integer left, taking the shift amount modulo 64 like at runtime.
+fn wrapping_shl(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    wrapping(cx, target, |a, b| a.wrapping_shl(b as u32))
+}
+
+/// Shift an integer right, taking the shift amount modulo 64 like at runtime.
+fn wrapping_shr(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    wrapping(cx, target, |a, b| a.wrapping_shr(b as u32))
+}
+
+/// Apply a wrapping operation to an integer and the only argument, which never
+/// fails.
+fn wrapping(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    op: fn(i64, i64) -> i64,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = target.as_integer().with_span(cx.target)?;
+    let span = cx.arg_span(0);
+    let other = cx.arg(0)?;
+    let other = other.as_integer().with_span(span)?;
+    Ok(Value::try_from(op(value, other)).with_span(cx.span)?)
+}
+
+/// Restrict an integer or a float to the given bounds.
+fn clamp(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let lo = cx.arg(0)?;
+    let hi = cx.arg(1)?;
+    let bounds = cx.arg_span(0).join(cx.arg_span(1));
+
+    let value = target.borrow_kind_ref().with_span(cx.target)?;
+    let lo = lo.borrow_kind_ref().with_span(cx.arg_span(0))?;
+    let hi = hi.borrow_kind_ref().with_span(cx.arg_span(1))?;
+
+    let output = match (&*value, &*lo, &*hi) {
+        (ValueKind::Integer(value), ValueKind::Integer(lo), ValueKind::Integer(hi)) => {
+            if lo > hi {
+                return Err(compile::Error::msg(
+                    bounds,
+                    "Lower bound must be less than or equal to the upper bound",
+                )
+                .into());
+            }
+
+            ValueKind::Integer((*value).clamp(*lo, *hi))
+        }
+        (ValueKind::Float(value), ValueKind::Float(lo), ValueKind::Float(hi)) => {
+            if lo.is_nan() || hi.is_nan() || lo > hi {
+                return Err(compile::Error::msg(
+                    bounds,
+                    "Lower bound must be less than or equal to the upper bound",
+                )
+                .into());
+            }
+
+            ValueKind::Float(value.clamp(*lo, *hi))
+        }
+        (ValueKind::Integer(..), ValueKind::Integer(..), actual) => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.arg_span(1), actual).into());
+        }
+        (ValueKind::Integer(..), actual, _) => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.arg_span(0), actual).into());
+        }
+        (ValueKind::Float(..), ValueKind::Float(..), actual) => {
+            return Err(compile::Error::expected_type::<_, f64>(cx.arg_span(1), actual).into());
+        }
+        (ValueKind::Float(..), actual, _) => {
+            return Err(compile::Error::expected_type::<_, f64>(cx.arg_span(0), actual).into());
+        }
+        (actual, _, _) => {
+            return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+        }
+    };
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Reverse a vector or a string.
+///
+/// Strings are reversed by characters, which keeps multibyte characters intact
+/// but doesn't respect grapheme clusters such as combining characters.
+fn reverse(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let value = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Vec(vec) => {
+            cx.interp.budget.take_many(cx.span, vec.len())?;
+            cx.interp.check_len(cx.span, vec.len())?;
+            cx.interp.alloc_values(cx.span, vec.len())?;
+            let values = vec.iter().rev().cloned().try_collect::<Vec<_>>()?;
+            vec_value(values, cx.span)?
+        }
+        ValueKind::String(string) => {
+            cx.interp.budget.take_many(cx.span, string.len())?;
+            cx.interp.check_len(cx.span, string.len())?;
+            cx.interp.alloc(cx.span, string.len())?;
+            let mut output = String::try_with_capacity(string.len())?;
+
+            for c in string.chars().rev() {
+                output.try_push(c)?;
+            }
+
+            Value::try_from(output).with_span(cx.span)?
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    Ok(value)
+}
+
+/// Test if a string, vector, tuple, object or byte string is empty.
+fn is_empty(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let is_empty = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::String(string) => string.is_empty(),
+        ValueKind::Vec(vec) => vec.is_empty(),
+        ValueKind::Tuple(tuple) => tuple.is_empty(),
+        ValueKind::Object(object) => object.is_empty(),
+        ValueKind::Bytes(bytes) => bytes.is_empty(),
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    Ok(Value::try_from(is_empty).with_span(cx.span)?)
+}
+
+/// Advance an iterator, producing an option with its next value.
+fn next(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let value = match &mut *target.borrow_kind_mut().with_span(cx.target)? {
+        ValueKind::Iterator(iter) => iter.next().into_result().with_span(cx.span)?,
+        actual => {
+            return Err(
+                compile::Error::expected_type::<_, runtime::Iterator>(cx.target, actual).into(),
+            );
+        }
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Split a string by a separator into a vector of strings.
+fn split(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    split_string(cx, target, 0, None)
+}
+
+/// Split a string by a separator into a vector of at most `n` strings.
+fn splitn(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let n = cx.arg(0)?;
+    let n = count(&n, cx.arg_span(0))?;
+    split_string(cx, target, 1, Some(n))
+}
+
+/// Split the target string by the separator at argument `sep`, charging the
+/// budget for each produced string.
+fn split_string(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    sep: usize,
+    n: Option<usize>,
+) -> Result<Value, ir::EvalOutcome> {
+    let span = cx.arg_span(sep);
+    let sep = cx.arg(sep)?;
+    let sep = sep.borrow_string_ref().with_span(span)?;
+
+    if sep.is_empty() {
+        return Err(compile::Error::msg(span, "Separator must not be empty").into());
+    }
+
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+
+    let mut output = Vec::new();
+
+    let mut push = |part: &str| -> compile::Result<()> {
+        cx.interp.budget.take(cx.span)?;
+        cx.interp.check_len(cx.span, output.len() + 1)?;
+        cx.interp.alloc_values(cx.span, 1)?;
+        cx.interp.alloc(cx.span, part.len())?;
+        let part = Value::try_from(String::try_from(part)?).with_span(cx.span)?;
+        output.try_push(part)?;
+        Ok(())
+    };
+
+    match n {
+        Some(n) => {
+            for part in string.splitn(n, &*sep) {
+                push(part)?;
+            }
+        }
+        None => {
+            for part in string.split(&*sep) {
+                push(part)?;
+            }
+        }
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// Test if a string starts with another string.
+fn starts_with(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    string_test(cx, target, |string, other| string.starts_with(other))
+}
+
+/// Test if a string ends with another string.
+fn ends_with(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    string_test(cx, target, |string, other| string.ends_with(other))
+}
+
+/// Test if a string contains another string, or if a vector contains a value.
+fn contains(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let is_vec = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::String(..) => false,
+        ValueKind::Vec(..) => true,
+        _ => return Err(ir::EvalOutcome::not_const(cx.span)),
+    };
+
+    if is_vec {
+        return vec_contains(cx, target);
+    }
+
+    string_test(cx, target, |string, other| string.contains(other))
+}
+
+/// Test if a vector contains a value.
+fn vec_contains(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let values = vec_elements(&target, cx.target)?;
+    let value = cx.arg(0)?;
+    let contains = contains_value(cx, &values, &value)?;
+    Ok(Value::try_from(contains).with_span(cx.span)?)
+}
+
+/// Apply a test to the target string and the only string argument.
+fn string_test(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    test: fn(&str, &str) -> bool,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let other = cx.arg(0)?;
+    let other = other.borrow_string_ref().with_span(cx.arg_span(0))?;
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    cx.interp.budget.take_many(cx.span, string.len())?;
+    Ok(Value::try_from(test(&string, &other)).with_span(cx.span)?)
+}
+
+/// Get the length of a string in bytes, or the number of elements in a
+/// vector, tuple, object or byte string.
+fn len(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let len = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::String(string) => string.len(),
+        ValueKind::Vec(vec) => vec.len(),
+        ValueKind::Tuple(tuple) => tuple.len(),
+        ValueKind::Object(object) => object.len(),
+        ValueKind::Bytes(bytes) => bytes.len(),
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    let Ok(len) = i64::try_from(len) else {
+        return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+    };
+
+    Ok(Value::try_from(len).with_span(cx.span)?)
+}
+
+/// Convert a string to upper case.
+fn to_uppercase(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    change_case(cx, target, |c, output| {
+        for c in c.to_uppercase() {
+            output.try_push(c)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Convert a string to lower case.
+fn to_lowercase(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    change_case(cx, target, |c, output| {
+        for c in c.to_lowercase() {
+            output.try_push(c)?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Build a new string by converting each character of the target string.
+fn change_case(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    convert: fn(char, &mut String) -> crate::alloc::Result<()>,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    cx.interp.budget.take_many(cx.span, string.len())?;
+
+    let mut output = String::try_with_capacity(string.len())?;
+
+    for c in string.chars() {
+        convert(c, &mut output)?;
+    }
+
+    cx.interp.alloc(cx.span, output.len())?;
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Remove leading and trailing whitespace from a string.
+fn trim(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+    let trimmed = string.trim();
+    cx.interp.budget.take_many(cx.span, string.len())?;
+    cx.interp.alloc(cx.span, trimmed.len())?;
+    Ok(Value::try_from(String::try_from(trimmed)?).with_span(cx.span)?)
+}
+
+/// Replace all occurrences of a pattern in a string with another string.
+fn replace(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let from = cx.arg(0)?;
+    let from = from.borrow_string_ref().with_span(cx.arg_span(0))?;
+    let to = cx.arg(1)?;
+    let to = to.borrow_string_ref().with_span(cx.arg_span(1))?;
+    let string = target.borrow_string_ref().with_span(cx.target)?;
+
+    let mut output = String::new();
+    let mut last = 0;
+
+    for (index, part) in string.match_indices(&*from) {
+        cx.interp.budget.take(cx.span)?;
+        output.try_push_str(&string[last..index])?;
+        output.try_push_str(&to)?;
+        last = index + part.len();
+        cx.interp.check_len(cx.span, output.len())?;
+    }
+
+    output.try_push_str(&string[last..])?;
+    cx.interp.budget.take_many(cx.span, string.len())?;
+    cx.interp.check_len(cx.span, output.len())?;
+    cx.interp.alloc(cx.span, output.len())?;
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Find the first element of a vector which is equal to the argument.
+fn find(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let value = search(cx, &target)?.map(|(_, value)| value);
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Find the index of the first element of a vector which is equal to the
+/// argument.
+fn position(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let index = match search(cx, &target)? {
+        Some((index, _)) => {
+            let index = i64::try_from(index)
+                .map_err(|_| compile::Error::new(cx.span, IrErrorKind::IntegerOverflow))?;
+            Some(Value::try_from(index).with_span(cx.span)?)
+        }
+        None => None,
+    };
+
+    Ok(Value::try_from(index).with_span(cx.span)?)
+}
+
+/// Binary search a sorted vector for the only argument.
+///
+/// Like at runtime, this produces `Ok` with the index of a matching element,
+/// or `Err` with the index where the value could be inserted to keep the
+/// vector sorted. The budget is charged for each element compared.
+fn binary_search(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = cx.arg(0)?;
+    let values = vec_elements(&target, cx.target)?;
+
+    let mut low = 0;
+    let mut high = values.len();
+
+    let result = loop {
+        if low >= high {
+            break Err(low);
+        }
+
+        let mid = low + (high - low) / 2;
+        cx.interp.budget.take(cx.span)?;
+
+        let ordering = values[mid]
+            .cmp_with(&value, &mut UnsupportedProtocolCaller)
+            .into_result()
+            .with_span(cx.target)?;
+
+        match ordering {
+            Ordering::Less => low = mid + 1,
+            Ordering::Greater => high = mid,
+            Ordering::Equal => break Ok(mid),
+        }
+    };
+
+    let index = |index: usize| -> compile::Result<Value> {
+        let index = i64::try_from(index)
+            .map_err(|_| compile::Error::new(cx.span, IrErrorKind::IntegerOverflow))?;
+        Ok(Value::try_from(index).with_span(cx.span)?)
+    };
+
+    let result = match result {
+        Ok(found) => Ok(index(found)?),
+        Err(insert) => Err(index(insert)?),
+    };
+
+    Ok(Value::try_from(result).with_span(cx.span)?)
+}
+
+/// Search a vector for the first element which is equal to the only argument,
+/// charging the budget for each element compared.
+fn search(
+    cx: &mut Call<'_, '_, '_>,
+    target: &Value,
+) -> Result<Option<(usize, Value)>, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let values = vec_elements(target, cx.target)?;
+    let value = cx.arg(0)?;
+
+    for (index, current) in values.into_iter().enumerate() {
+        cx.interp.budget.take(cx.span)?;
+
+        if Value::partial_eq(&current, &value)
+            .into_result()
+            .with_span(cx.span)?
+        {
+            return Ok(Some((index, current)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Convert a tuple or a vector into a vector.
+fn to_vec(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let values = sequence(cx, &target)?;
+    Ok(vec_value(values, cx.span)?)
+}
+
+/// Convert a tuple or a vector into a tuple.
+fn to_tuple(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let values = sequence(cx, &target)?;
+    let tuple = OwnedTuple::try_from(values)?;
+    Ok(Value::try_from(tuple).with_span(cx.span)?)
+}
+
+/// Copy the elements of a tuple or a vector, charging the budget and the
+/// allocation limits for each element.
+fn sequence(cx: &mut Call<'_, '_, '_>, target: &Value) -> compile::Result<Vec<Value>> {
+    let span = cx.target;
+
+    let values = match &*target.borrow_kind_ref().with_span(span)? {
+        ValueKind::Vec(vec) => vec.iter().cloned().try_collect::<Vec<_>>()?,
+        ValueKind::Tuple(tuple) => tuple.iter().cloned().try_collect::<Vec<_>>()?,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(
+                span, actual,
+            ));
+        }
+    };
+
+    cx.interp.budget.take_many(span, values.len())?;
+    cx.interp.check_len(cx.span, values.len())?;
+    cx.interp.alloc_values(cx.span, values.len())?;
+    Ok(values)
+}
+
+/// The first element of a vector or tuple, or `None` if it's empty.
+fn first(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    endpoint(cx, target, <[Value]>::first)
+}
+
+/// The last element of a vector or tuple, or `None` if it's empty.
+fn last(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    endpoint(cx, target, <[Value]>::last)
+}
+
+/// Pick an element out of a vector or tuple as an option.
+fn endpoint(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    pick: fn(&[Value]) -> Option<&Value>,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+
+    let value = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Vec(vec) => pick(vec).cloned(),
+        ValueKind::Tuple(tuple) => pick(tuple).cloned(),
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Overwrite every element of a vector with a value.
+fn fill(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = cx.arg(0)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    cx.interp.budget.take_many(cx.span, vec.len())?;
+
+    for slot in vec.iter_mut() {
+        *slot = value.clone();
+    }
+
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Resize a vector to the given length, filling new slots with a value.
+fn resize(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let span = cx.arg_span(0);
+    let len = cx.arg(0)?;
+    let len = count(&len, span)?;
+    cx.interp.check_len(span, len)?;
+    let value = cx.arg(1)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    cx.interp
+        .budget
+        .take_many(cx.span, len.saturating_sub(vec.len()))?;
+    cx.interp
+        .alloc_values(cx.span, len.saturating_sub(vec.len()))?;
+
+    while vec.len() > len {
+        vec.pop();
+    }
+
+    while vec.len() < len {
+        vec.push(value.clone())?;
+    }
+
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Get an index into a vector out of a value, which must be less than `bound`.
+fn vec_index(value: i64, bound: usize, span: Span) -> compile::Result<usize> {
+    let Ok(index) = usize::try_from(value) else {
+        return Err(compile::Error::msg(span, "Index must not be negative"));
+    };
+
+    if index >= bound {
+        return Err(compile::Error::new(
+            span,
+            IrErrorKind::MissingIndex { index: value },
+        ));
+    }
+
+    Ok(index)
+}
+
+/// Append a value to the end of a vector.
+fn push(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = cx.arg(0)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    cx.interp.budget.take(cx.span)?;
+    cx.interp.check_len(cx.span, vec.len().saturating_add(1))?;
+    cx.interp.alloc_values(cx.span, 1)?;
+    vec.push(value)?;
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Insert a value into a vector at the given index, shifting later elements
+/// to the right.
+fn insert(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let span = cx.arg_span(0);
+    let index = cx.arg(0)?;
+    let index = index.as_integer().with_span(span)?;
+    let value = cx.arg(1)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    let index = vec_index(index, vec.len().saturating_add(1), span)?;
+    cx.interp.budget.take(cx.span)?;
+    cx.interp.check_len(cx.span, vec.len().saturating_add(1))?;
+    cx.interp.alloc_values(cx.span, 1)?;
+    vec.insert(index, value).into_result().with_span(cx.span)?;
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Remove and return the value at the given index of a vector, shifting later
+/// elements to the left.
+fn remove(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let index = cx.arg(0)?;
+    let index = index.as_integer().with_span(span)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    let index = vec_index(index, vec.len(), span)?;
+    cx.interp.budget.take(cx.span)?;
+    Ok(vec.remove(index))
+}
+
+/// Swap two elements of a vector.
+fn swap(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let a_span = cx.arg_span(0);
+    let a = cx.arg(0)?;
+    let a = a.as_integer().with_span(a_span)?;
+    let b_span = cx.arg_span(1);
+    let b = cx.arg(1)?;
+    let b = b.as_integer().with_span(b_span)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    let a = vec_index(a, vec.len(), a_span)?;
+    let b = vec_index(b, vec.len(), b_span)?;
+    cx.interp.budget.take(cx.span)?;
+    vec.swap(a, b);
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Rotate the elements of a vector in place.
+fn rotate(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    op: fn(&mut [Value], usize),
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let n = cx.arg(0)?;
+    let n = n.as_integer().with_span(span)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let vec = match &mut *kind {
+        ValueKind::Vec(vec) => vec,
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    // Rotating by the length of the vector is permitted, and a no-op.
+    let n = vec_index(n, vec.len().saturating_add(1), span)?;
+    cx.interp.budget.take_many(cx.span, vec.len())?;
+    op(vec, n);
+    Ok(Value::empty().with_span(cx.span)?)
+}
+
+/// Rotate a vector in place so that the first `n` elements move to the end.
+fn rotate_left(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    rotate(cx, target, <[Value]>::rotate_left)
+}
+
+/// Rotate a vector in place so that the last `n` elements move to the front.
+fn rotate_right(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    rotate(cx, target, <[Value]>::rotate_right)
+}
+
+/// Split a vector or a string into a tuple of two pieces at the given index.
+fn split_at(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let index = cx.arg(0)?;
+    let index = index.as_integer().with_span(span)?;
+
+    let (head, tail) = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Vec(vec) => {
+            let index = vec_index(index, vec.len().saturating_add(1), span)?;
+            cx.interp.budget.take_many(cx.span, vec.len())?;
+            cx.interp.alloc_values(cx.span, vec.len())?;
+            let (head, tail) = vec.split_at(index);
+            let head = head.iter().cloned().try_collect::<Vec<_>>()?;
+            let tail = tail.iter().cloned().try_collect::<Vec<_>>()?;
+            (vec_value(head, cx.span)?, vec_value(tail, cx.span)?)
+        }
+        ValueKind::String(string) => {
+            let index = vec_index(index, string.len().saturating_add(1), span)?;
+
+            if !string.is_char_boundary(index) {
+                return Err(
+                    compile::Error::msg(span, "Index is not on a character boundary").into(),
+                );
+            }
+
+            cx.interp.budget.take_many(cx.span, string.len())?;
+            cx.interp.alloc(cx.span, string.len())?;
+            let (head, tail) = string.split_at(index);
+            let head = Value::try_from(String::try_from(head)?).with_span(cx.span)?;
+            let tail = Value::try_from(String::try_from(tail)?).with_span(cx.span)?;
+            (head, tail)
+        }
+        actual => {
+            return Err(compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into());
+        }
+    };
+
+    let tuple = OwnedTuple::try_from(try_vec![head, tail])?;
+    Ok(Value::try_from(tuple).with_span(cx.span)?)
+}
+
+/// Get the value of a key in an object, inserting a default value first if
+/// the key is absent.
+fn get_or_insert(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let key_span = cx.arg_span(0);
+    let key = cx.arg(0)?;
+    let key = key.borrow_string_ref().with_span(key_span)?;
+    let default = cx.arg(1)?;
+
+    let mut kind = target.borrow_kind_mut().with_span(cx.target)?;
+
+    let object = match &mut *kind {
+        ValueKind::Object(object) => object,
+        actual => {
+            return Err(compile::Error::expected_type::<_, Object>(cx.target, actual).into());
+        }
+    };
+
+    if let Some(value) = object.get(&*key) {
+        return Ok(value.clone());
+    }
+
+    cx.interp
+        .check_len(cx.span, object.len().saturating_add(1))?;
+    cx.interp.alloc_values(cx.span, 1)?;
+    cx.interp.alloc(cx.span, key.len())?;
+    object.insert(key.try_to_owned()?, default.clone())?;
+    Ok(default)
+}
+
+/// Test if a dot-separated path of keys leads to a value through nested
+/// objects.
+fn has_path(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let value = object_path(cx, target)?;
+    Ok(Value::try_from(value.is_some()).with_span(cx.span)?)
+}
+
+/// Get the value at the end of a dot-separated path of keys through nested
+/// objects, or `None` if the path doesn't lead anywhere.
+fn get_path(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let value = object_path(cx, target)?;
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Walk a dot-separated path of keys through nested objects.
+///
+/// The target itself has to be an object, but running into anything else
+/// along the way just means that the path doesn't exist.
+fn object_path(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Option<Value>, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let path_span = cx.arg_span(0);
+    let path = cx.arg(0)?;
+    let path = path.borrow_string_ref().with_span(path_span)?;
+
+    match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Object(..) => {}
+        actual => {
+            return Err(compile::Error::expected_type::<_, Object>(cx.target, actual).into());
+        }
+    }
+
+    let mut current = target;
+
+    for key in path.split('.') {
+        cx.interp.budget.take(cx.span)?;
+
+        let next = match &*current.borrow_kind_ref().with_span(cx.target)? {
+            ValueKind::Object(object) => object.get(key).cloned(),
+            _ => None,
+        };
+
+        let Some(next) = next else {
+            return Ok(None);
+        };
+
+        current = next;
+    }
+
+    Ok(Some(current))
+}
+
+/// The contents of an `Option` or a `Result`.
+enum Fallible {
+    Option(Option<Value>),
+    Result(Result<Value, Value>),
+}
+
+/// Get the contents of an `Option` or a `Result`.
+fn fallible(cx: &Call<'_, '_, '_>, target: &Value) -> compile::Result<Fallible> {
+    match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Option(value) => Ok(Fallible::Option(value.clone())),
+        ValueKind::Result(value) => Ok(Fallible::Result(value.clone())),
+        actual => Err(compile::Error::expected_type::<_, Option<Value>>(
+            cx.target, actual,
+        )),
+    }
+}
+
+/// Get the closure passed as the argument at the given index.
+///
+/// Closures are called in place, so they have to be passed directly to the
+/// method rather than through a variable.
+fn closure_arg<'a>(cx: &Call<'a, '_, '_>, index: usize) -> compile::Result<&'a ir::IrClosure> {
+    match cx.args.get(index).map(|ir| &ir.kind) {
+        Some(ir::IrKind::Closure(closure)) => Ok(closure),
+        _ => Err(compile::Error::msg(
+            cx.arg_span(index),
+            "Expected a closure",
+        )),
+    }
+}
+
+/// Call a closure in place with the given arguments.
+fn call_closure(
+    cx: &mut Call<'_, '_, '_>,
+    closure: &ir::IrClosure,
+    args: &[Value],
+) -> Result<Value, ir::EvalOutcome> {
+    if closure.args.len() != args.len() {
+        return Err(compile::Error::new(
+            closure.span,
+            IrErrorKind::ArgumentCountMismatch {
+                actual: closure.args.len(),
+                expected: args.len(),
+            },
+        )
+        .into());
+    }
+
+    let guard = cx.interp.scopes.push()?;
+
+    for (pat, value) in closure.args.iter().zip(args) {
+        if !pat.matches(cx.interp, value.clone(), closure.span)? {
+            return Err(compile::Error::msg(
+                closure.span,
+                "Closure argument doesn't match its pattern",
+            )
+            .into());
+        }
+    }
+
+    let value = ir::eval_ir(&closure.body, cx.interp, cx.used)?;
+    cx.interp.scopes.pop(guard).with_span(closure.span)?;
+    Ok(value)
+}
+
+/// Map the value of `Some` or `Ok`, or each value of an iterator, with a
+/// closure.
+fn map(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+
+    if matches!(
+        &*target.borrow_kind_ref().with_span(cx.target)?,
+        ValueKind::Iterator(..)
+    ) {
+        let span = cx.target;
+        let mut output = Vec::new();
+
+        for value in values(cx, &target, span)? {
+            output.try_push(call_closure(cx, closure, &[value])?)?;
+        }
+
+        return Ok(iter_value("std::iter::Map", output, cx.span)?);
+    }
+
+    match fallible(cx, &target)? {
+        Fallible::Option(Some(value)) => {
+            let value = call_closure(cx, closure, &[value])?;
+            Ok(Value::try_from(Some(value)).with_span(cx.span)?)
+        }
+        Fallible::Result(Ok(value)) => {
+            let value = call_closure(cx, closure, &[value])?;
+            Ok(Value::try_from(Ok::<_, Value>(value)).with_span(cx.span)?)
+        }
+        _ => Ok(target),
+    }
+}
+
+/// Replace the value of `Some` or `Ok` with the result of a closure.
+fn and_then(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+
+    match fallible(cx, &target)? {
+        Fallible::Option(Some(value)) | Fallible::Result(Ok(value)) => {
+            call_closure(cx, closure, &[value])
+        }
+        _ => Ok(target),
+    }
+}
+
+/// Replace `Some` or `Ok` with the argument, keeping `None` or `Err` as they
+/// are.
+fn and(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let other = cx.arg(0)?;
+
+    match fallible(cx, &target)? {
+        Fallible::Option(Some(..)) | Fallible::Result(Ok(..)) => Ok(other),
+        _ => Ok(target),
+    }
+}
+
+/// Zip two options into an option of a pair, which is only `Some` if both
+/// options are.
+fn zip(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let other = cx.arg(0)?;
+
+    let a = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Option(value) => value.clone(),
+        actual => {
+            return Err(
+                compile::Error::expected_type::<_, Option<Value>>(cx.target, actual).into(),
+            );
+        }
+    };
+
+    let b = match &*other.borrow_kind_ref().with_span(span)? {
+        ValueKind::Option(value) => value.clone(),
+        actual => {
+            return Err(compile::Error::expected_type::<_, Option<Value>>(span, actual).into());
+        }
+    };
+
+    let value = match (a, b) {
+        (Some(a), Some(b)) => {
+            let tuple = OwnedTuple::try_from(try_vec![a, b])?;
+            Some(Value::try_from(tuple).with_span(cx.span)?)
+        }
+        _ => None,
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Replace `None` or the value of `Err` with the result of a closure.
+fn or_else(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+
+    match fallible(cx, &target)? {
+        Fallible::Option(None) => call_closure(cx, closure, &[]),
+        Fallible::Result(Err(error)) => call_closure(cx, closure, &[error]),
+        _ => Ok(target),
+    }
+}
+
+/// Map the value of `Err` with a closure.
+fn map_err(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+
+    let error = match &*target.borrow_kind_ref().with_span(cx.target)? {
+        ValueKind::Result(result) => result.as_ref().err().cloned(),
+        actual => {
+            return Err(compile::Error::expected_type::<_, Result<Value, Value>>(
+                cx.target, actual,
+            )
+            .into());
+        }
+    };
+
+    let Some(error) = error else {
+        return Ok(target);
+    };
+
+    let error = call_closure(cx, closure, &[error])?;
+    Ok(Value::try_from(Err::<Value, _>(error)).with_span(cx.span)?)
+}
+
+/// Unwrap the value of `Some` or `Ok`, or fail compilation with the message
+/// which is the only argument if the value is `None` or `Err`.
+fn expect(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let message = cx.arg(0)?;
+    let message = message.borrow_string_ref().with_span(span)?;
+
+    match fallible(cx, &target)? {
+        Fallible::Option(Some(value)) | Fallible::Result(Ok(value)) => Ok(value),
+        Fallible::Option(None) | Fallible::Result(Err(..)) => Err(compile::Error::new(
+            cx.span,
+            IrErrorKind::Panic {
+                message: Box::try_from(&*message)?,
+            },
+        )
+        .into()),
+    }
+}
+
+/// Collect the values of a collection into a vector.
+fn collect(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let values = values(cx, &target, span)?;
+    Ok(vec_value(values, cx.span)?)
+}
+
+/// Take at most the given number of values from a collection.
+///
+/// Iterators are only advanced as far as needed, so this can be used with
+/// infinite iterators like the one produced by `rng`.
+fn take(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let n = cx.arg(0)?;
+    let n = count(&n, span)?;
+    let span = cx.target;
+
+    let mut output = Vec::new();
+
+    if let ValueKind::Iterator(iter) = &mut *target.borrow_kind_mut().with_span(span)? {
+        while output.len() < n {
+            let Some(value) = iter.next().into_result().with_span(span)? else {
+                break;
+            };
+
+            cx.interp.budget.take(span)?;
+            cx.interp.check_len(span, output.len() + 1)?;
+            cx.interp.alloc_values(span, 1)?;
+            output.try_push(value)?;
+        }
+
+        return Ok(iter_value("std::iter::Take", output, cx.span)?);
+    }
+
+    output = values(cx, &target, span)?;
+    output.truncate(n);
+    Ok(iter_value("std::iter::Take", output, cx.span)?)
+}
+
+/// Pair each value of a collection with its index.
+fn enumerate(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let values = values(cx, &target, span)?;
+
+    let mut output = Vec::try_with_capacity(values.len())?;
+
+    for (index, value) in values.into_iter().enumerate() {
+        cx.interp.alloc_values(cx.span, 2)?;
+
+        let Ok(index) = i64::try_from(index) else {
+            return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+        };
+
+        let index = Value::try_from(index).with_span(cx.span)?;
+        let tuple = OwnedTuple::try_from(try_vec![index, value])?;
+        output.try_push(Value::try_from(tuple).with_span(cx.span)?)?;
+    }
+
+    Ok(iter_value("std::iter::Enumerate", output, cx.span)?)
+}
+
+/// Get the non-zero size which is the only argument to a call.
+fn size_arg(cx: &mut Call<'_, '_, '_>, message: &'static str) -> Result<usize, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let size = cx.arg(0)?;
+    let size = count(&size, span)?;
+
+    if size == 0 {
+        return Err(compile::Error::msg(span, message).into());
+    }
+
+    Ok(size)
+}
+
+/// Construct a vector of vectors out of the given slices, charging the budget
+/// and the allocation limits for each copied value.
+fn slices<'a, I>(cx: &mut Call<'_, '_, '_>, slices: I) -> Result<Value, ir::EvalOutcome>
+where
+    I: IntoIterator<Item = &'a [Value]>,
+{
+    let mut output = Vec::new();
+
+    for slice in slices {
+        cx.interp.budget.take_many(cx.span, slice.len())?;
+        cx.interp.check_len(cx.span, output.len() + 1)?;
+        cx.interp
+            .alloc_values(cx.span, slice.len().saturating_add(1))?;
+        let slice = slice.iter().cloned().try_collect::<Vec<_>>()?;
+        output.try_push(vec_value(slice, cx.span)?)?;
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// Split a vector into chunks of the given size.
+fn chunks(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let size = size_arg(cx, "Chunk size must be non-zero")?;
+    let values = vec_elements(&target, cx.target)?;
+    slices(cx, values.chunks(size))
+}
+
+/// Construct overlapping windows of the given size over a vector.
+fn windows(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let size = size_arg(cx, "Window size must be non-zero")?;
+    let values = vec_elements(&target, cx.target)?;
+    slices(cx, values.windows(size))
+}
+
+/// Flatten a vector of vectors exactly one level deep.
+///
+/// Elements which aren't vectors are passed through as they are, unless the
+/// call is `flatten(true)` in which case they are reported as an error. Nested
+/// vectors are never flattened further than one level.
+fn flatten(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    let strict = if cx.args.is_empty() {
+        false
+    } else {
+        cx.expect_args(1)?;
+        let span = cx.arg_span(0);
+        let strict = cx.arg(0)?;
+        strict.as_bool().with_span(span)?
+    };
+
+    let values = vec_elements(&target, cx.target)?;
+    let mut output = Vec::new();
+
+    for value in values {
+        match &*value.borrow_kind_ref().with_span(cx.target)? {
+            ValueKind::Vec(vec) => {
+                cx.interp.budget.take_many(cx.span, vec.len())?;
+                cx.interp
+                    .check_len(cx.span, output.len().saturating_add(vec.len()))?;
+                cx.interp.alloc_values(cx.span, vec.len())?;
+
+                for value in vec.iter() {
+                    output.try_push(value.clone())?;
+                }
+            }
+            actual if strict => {
+                return Err(
+                    compile::Error::expected_type::<_, runtime::Vec>(cx.target, actual).into(),
+                );
+            }
+            _ => {
+                cx.interp.budget.take(cx.span)?;
+                cx.interp.check_len(cx.span, output.len() + 1)?;
+                cx.interp.alloc_values(cx.span, 1)?;
+                output.try_push(value.clone())?;
+            }
+        }
+    }
+
+    Ok(vec_value(output, cx.span)?)
+}
+
+/// Fold the numeric values of a collection.
+fn fold_numbers(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    identity: i64,
+    integer: fn(i64, i64) -> Option<i64>,
+    float: fn(f64, f64) -> f64,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let values = values(cx, &target, span)?;
+
+    let mut output = ValueKind::Integer(identity);
+
+    for (index, value) in values.iter().enumerate() {
+        let value = value.borrow_kind_ref().with_span(cx.target)?;
+
+        output = match (&output, &*value) {
+            (ValueKind::Integer(a), ValueKind::Integer(b)) => {
+                let Some(n) = integer(*a, *b) else {
+                    return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+                };
+
+                ValueKind::Integer(n)
+            }
+            (ValueKind::Integer(..), ValueKind::Float(b)) if index == 0 => {
+                ValueKind::Float(float(identity as f64, *b))
+            }
+            (ValueKind::Float(a), ValueKind::Float(b)) => ValueKind::Float(float(*a, *b)),
+            (ValueKind::Float(..), actual) => {
+                return Err(compile::Error::expected_type::<_, f64>(cx.target, actual).into());
+            }
+            (_, actual) => {
+                return Err(compile::Error::expected_type::<_, i64>(cx.target, actual).into());
+            }
+        };
+    }
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Sum the numeric values of a collection.
+fn sum(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    fold_numbers(cx, target, 0, i64::checked_add, |a, b| a + b)
+}
+
+/// Multiply the numeric values of a collection.
+fn product(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    fold_numbers(cx, target, 1, i64::checked_mul, |a, b| a * b)
+}
+
+/// Fold the values of a collection into an accumulator, starting with the
+/// first argument and calling a closure with the accumulator and each value.
+fn fold(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let mut acc = cx.arg(0)?;
+    let closure = closure_arg(cx, 1)?;
+    let span = cx.target;
+
+    for value in values(cx, &target, span)? {
+        acc = call_closure(cx, closure, &[acc, value])?;
+    }
+
+    Ok(acc)
+}
+
+/// Like `fold`, but uses the first value of a collection as the initial
+/// accumulator, producing `None` if the collection is empty.
+fn reduce(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+    let span = cx.target;
+
+    let mut acc = None;
+
+    for value in values(cx, &target, span)? {
+        acc = Some(match acc {
+            Some(acc) => call_closure(cx, closure, &[acc, value])?,
+            None => value,
+        });
+    }
+
+    Ok(Value::try_from(acc).with_span(cx.span)?)
+}
+
+/// The value of a collection with the smallest key, or `None` if it's empty.
+///
+/// Like at runtime, the first of several equally small values is picked.
+fn min_by_key(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    by_key(cx, target, Ordering::is_lt)
+}
+
+/// The value of a collection with the largest key, or `None` if it's empty.
+///
+/// Like at runtime, the last of several equally large values is picked.
+fn max_by_key(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    by_key(cx, target, Ordering::is_ge)
+}
+
+/// Select a value of a collection by comparing keys computed with a closure,
+/// replacing the current selection whenever `replace` holds for the ordering
+/// of the new key against the selected one.
+fn by_key(
+    cx: &mut Call<'_, '_, '_>,
+    target: Value,
+    replace: fn(Ordering) -> bool,
+) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let closure = closure_arg(cx, 0)?;
+    let span = cx.target;
+
+    let mut selected: Option<(Value, Value)> = None;
+
+    for value in values(cx, &target, span)? {
+        let key = call_closure(cx, closure, &[value.clone()])?;
+
+        if let Some((current, _)) = &selected {
+            let ordering = Value::partial_cmp_with(&key, current, &mut UnsupportedProtocolCaller)
+                .into_result()
+                .with_span(closure.span)?;
+
+            let Some(ordering) = ordering else {
+                return Err(compile::Error::msg(closure.span, "Keys can't be compared").into());
+            };
+
+            if !replace(ordering) {
+                continue;
+            }
+        }
+
+        selected = Some((key, value));
+    }
+
+    let value = selected.map(|(_, value)| value);
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Include the contents of a file as a string.
+fn include_str(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let bytes = include(cx)?;
+
+    let Ok(string) = String::from_utf8(bytes) else {
+        return Err(compile::Error::msg(cx.span, "Included file is not valid UTF-8").into());
+    };
+
+    Ok(Value::try_from(string).with_span(cx.span)?)
+}
+
+/// Include the contents of a file as bytes.
+fn include_bytes(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let bytes = include(cx)?;
+    Ok(Value::try_from(runtime::Bytes::from_vec(bytes)).with_span(cx.span)?)
+}
+
+/// Load a file relative to the source being evaluated through the source
+/// loader, which decides what can be included.
+fn include(cx: &mut Call<'_, '_, '_>) -> Result<Vec<u8>, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let path_span = cx.arg_span(0);
+    let path = cx.arg(0)?;
+    let path = path.borrow_string_ref().with_span(path_span)?;
+
+    let q = &mut cx.interp.q;
+
+    let Some(root) = q.sources.get(cx.interp.source_id).and_then(|s| s.path()) else {
+        return Err(compile::Error::msg(
+            cx.span,
+            "Cannot include files relative to a source without a path",
+        )
+        .into());
+    };
+
+    let bytes = q.source_loader.include(root, &path, &cx.span)?;
+    cx.interp.check_len(cx.span, bytes.len())?;
+    cx.interp.alloc(cx.span, bytes.len())?;
+    Ok(bytes)
+}
+
+/// Evaluate the integer and bit index arguments of a bit operation.
+fn bit_args(cx: &mut Call<'_, '_, '_>) -> Result<(i64, u32), ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let value = cx.arg(0)?;
+    let value = value.as_integer().with_span(cx.arg_span(0))?;
+    let index = cx.arg(1)?;
+    let index = bit(&index, cx.arg_span(1))?;
+    Ok((value, index))
+}
+
+/// Set the given bit in an integer.
+fn set_bit(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let (value, bit) = bit_args(cx)?;
+    Ok(Value::try_from(value | (1 << bit)).with_span(cx.span)?)
+}
+
+/// Clear the given bit in an integer.
+fn clear_bit(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let (value, bit) = bit_args(cx)?;
+    Ok(Value::try_from(value & !(1 << bit)).with_span(cx.span)?)
+}
+
+/// Toggle the given bit in an integer.
+fn toggle_bit(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let (value, bit) = bit_args(cx)?;
+    Ok(Value::try_from(value ^ (1 << bit)).with_span(cx.span)?)
+}
+
+/// Test if the given bit is set in an integer.
+fn test_bit(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let (value, bit) = bit_args(cx)?;
+    Ok(Value::try_from(value & (1 << bit) != 0).with_span(cx.span)?)
+}
+
+/// Get a radix between 2 and 36 out of a value.
+fn radix_arg(value: &Value, span: Span) -> compile::Result<u32> {
+    let radix = value.as_integer().with_span(span)?;
+
+    match u32::try_from(radix) {
+        Ok(radix @ 2..=36) => Ok(radix),
+        _ => Err(compile::Error::msg(
+            span,
+            try_format!("Radix {radix} is out of range 2 to 36"),
+        )),
+    }
+}
+
+/// Format an integer as a string in the given radix.
+fn to_radix(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let value = cx.arg(0)?;
+    let value = value.as_integer().with_span(cx.arg_span(0))?;
+    let radix = cx.arg(1)?;
+    let radix = radix_arg(&radix, cx.arg_span(1))?;
+
+    let mut digits = Vec::new();
+    let mut n = value.unsigned_abs();
+
+    loop {
+        let digit = (n % u64::from(radix)) as u32;
+        digits.try_push(char::from_digit(digit, radix).unwrap_or('?'))?;
+        n /= u64::from(radix);
+
+        if n == 0 {
+            break;
+        }
+    }
+
+    let mut output = String::try_with_capacity(digits.len() + 1)?;
+
+    if value < 0 {
+        output.try_push('-')?;
+    }
+
+    for c in digits.into_iter().rev() {
+        output.try_push(c)?;
+    }
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Parse an integer from a string in the given radix.
+fn from_radix(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let string = cx.arg(0)?;
+    let string = string.borrow_string_ref().with_span(cx.arg_span(0))?;
+    let radix = cx.arg(1)?;
+    let radix = radix_arg(&radix, cx.arg_span(1))?;
+
+    let Ok(value) = i64::from_str_radix(&string, radix) else {
+        return Err(compile::Error::msg(
+            cx.arg_span(0),
+            try_format!("Invalid digits for an integer in radix {radix}"),
+        )
+        .into());
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Escape a string so that it can be embedded in a string literal.
+///
+/// Backslashes and both kinds of quotes are escaped with a backslash, and
+/// `\n`, `\r`, `\t` and `\0` use their short escapes. Any other control
+/// character is escaped as `\u{..}`, and everything else is left as-is.
+fn escape(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let string = cx.arg(0)?;
+    let string = string.borrow_string_ref().with_span(cx.arg_span(0))?;
+
+    let mut output = String::try_with_capacity(string.len())?;
+
+    for c in string.chars() {
+        match c {
+            '\\' => output.try_push_str("\\\\")?,
+            '"' => output.try_push_str("\\\"")?,
+            '\'' => output.try_push_str("\\'")?,
+            '\n' => output.try_push_str("\\n")?,
+            '\r' => output.try_push_str("\\r")?,
+            '\t' => output.try_push_str("\\t")?,
+            '\0' => output.try_push_str("\\0")?,
+            c if c.is_control() => {
+                output.try_push_str(&try_format!("\\u{{{:x}}}", u32::from(c)))?
+            }
+            c => output.try_push(c)?,
+        }
+    }
+
+    cx.interp.alloc(cx.span, output.len())?;
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Resolve the escape sequences in a string, using the same rules as string
+/// literals.
+///
+/// Invalid escape sequences are an error at the span of the string.
+fn unescape(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let string = cx.arg(0)?;
+    let string = string.borrow_string_ref().with_span(span)?;
+
+    let mut output = String::try_with_capacity(string.len())?;
+    let mut it = string.char_indices().peekable();
+
+    while let Some((_, c)) = it.next() {
+        let c = match c {
+            '\\' => unescape::parse_char_escape(
+                &mut it,
+                unescape::WithTemplate(false),
+                unescape::WithLineCont(true),
+            )
+            .map_err(|kind| compile::Error::new(span, kind))?,
+            c => Some(c),
+        };
+
+        if let Some(c) = c {
+            output.try_push(c)?;
+        }
+    }
+
+    cx.interp.alloc(cx.span, output.len())?;
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
+
+/// Convert a digit in the given radix into a character.
+///
+/// Produces `None` if the number isn't a digit in the radix.
+fn char_from_digit(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let num_span = cx.arg_span(0);
+    let num = cx.arg(0)?;
+    let num = num.as_integer().with_span(num_span)?;
+    let radix_span = cx.arg_span(1);
+    let radix = cx.arg(1)?;
+    let radix = radix_arg(&radix, radix_span)?;
+
+    let c = match u32::try_from(num) {
+        Ok(num) => char::from_digit(num, radix),
+        Err(..) => None,
+    };
+
+    let value = match c {
+        Some(c) => Some(Value::try_from(c).with_span(cx.span)?),
+        None => None,
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Convert a character into a digit in the given radix.
+///
+/// Produces `None` if the character isn't a digit in the radix.
+fn to_digit(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let radix = cx.arg(0)?;
+    let radix = radix_arg(&radix, span)?;
+    let c = target.as_char().with_span(cx.target)?;
+
+    let value = match c.to_digit(radix) {
+        Some(digit) => Some(Value::try_from(i64::from(digit)).with_span(cx.span)?),
+        None => None,
+    };
+
+    Ok(Value::try_from(value).with_span(cx.span)?)
+}
+
+/// Fail compilation with the given message.
+///
+/// This is only an error if it's actually reached when evaluating a constant,
+/// so it can be used in branches which are expected to never be taken.
+fn panic(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let message = cx.arg(0)?;
+    let message = message.borrow_string_ref().with_span(span)?;
+
+    Err(compile::Error::new(
+        cx.span,
+        IrErrorKind::Panic {
+            message: Box::try_from(&*message)?,
+        },
+    )
+    .into())
+}
+
+/// Fail compilation because code which is expected to be unreachable has been
+/// reached, with an optional message.
+fn unreachable(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    let mut message = String::try_from("Entered unreachable code")?;
+
+    if !cx.args.is_empty() {
+        cx.expect_args(1)?;
+        let span = cx.arg_span(0);
+        let value = cx.arg(0)?;
+        let value = value.borrow_string_ref().with_span(span)?;
+        message.try_push_str(": ")?;
+        message.try_push_str(&value)?;
+    }
+
+    Err(compile::Error::new(
+        cx.span,
+        IrErrorKind::Panic {
+            message: message.try_into_boxed_str()?,
+        },
+    )
+    .into())
+}
+
+/// Hash a value the same way as `std::ops::hash` does at runtime.
+///
+/// Integers, bytes, floats, strings and byte strings can be hashed, as well as
+/// tuples and vectors of them. There's no virtual machine which can call the
+/// `HASH` protocol in constant contexts, so any other value is an error.
+///
+/// Like at runtime, the hash is only stable within a single process. So this
+/// requires [`Options::const_hash`] to be enabled, by which the caller promises
+/// that the unit is run by the process which compiled it.
+///
+/// [`Options::const_hash`]: crate::Options::const_hash
+fn hash(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    if !cx.interp.q.options.const_hash {
+        return Err(compile::Error::msg(
+            cx.span,
+            "Hashing in constant contexts requires the `const-hash` option",
+        )
+        .into());
+    }
+
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let value = cx.arg(0)?;
+
+    let hash = crate::modules::ops::hash_with(&value, &mut UnsupportedProtocolCaller)
+        .into_result()
+        .with_span(span)?;
+
+    Ok(Value::try_from(hash).with_span(cx.span)?)
+}
+
+/// An infinite iterator of pseudo-random integers, seeded by the only
+/// argument.
+///
+/// The generator is SplitMix64, and each value is the top 63 bits of its
+/// output so that values are never negative. The same seed always produces
+/// the same sequence, regardless of platform.
+fn rng(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let seed = cx.arg(0)?;
+    let seed = seed.as_integer().with_span(cx.arg_span(0))?;
+    let iter = runtime::Iterator::from("rng", SplitMix64 { state: seed as u64 });
+    Ok(Value::try_from(iter).with_span(cx.span)?)
+}
+
+/// The SplitMix64 generator used by `rng`.
+struct SplitMix64 {
+    state: u64,
+}
+
+impl Iterator for SplitMix64 {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Some((z >> 1) as i64)
+    }
+}
+
+/// Convert a number of seconds into microseconds.
+fn seconds(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    micros_scaled(cx, 1_000_000)
+}
+
+/// Convert a number of milliseconds into microseconds.
+fn millis(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    micros_scaled(cx, 1_000)
+}
+
+/// A number of microseconds.
+///
+/// This is the unit which all of the time helpers produce, so that they can be
+/// freely added together.
+fn micros(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    micros_scaled(cx, 1)
+}
+
+/// Scale the single integer argument into microseconds.
+fn micros_scaled(cx: &mut Call<'_, '_, '_>, scale: i64) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let value = cx.arg(0)?;
+    let value = value.as_integer().with_span(cx.arg_span(0))?;
+
+    let Some(micros) = value.checked_mul(scale) else {
+        return Err(compile::Error::new(cx.span, IrErrorKind::IntegerOverflow).into());
+    };
+
+    Ok(Value::try_from(micros).with_span(cx.span)?)
+}
+
+/// Construct an object out of a collection of key-value tuples.
+///
+/// Later values replace earlier values with the same key.
+fn object_from(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let span = cx.arg_span(0);
+    let pairs = cx.arg(0)?;
+    let pairs = values(cx, &pairs, span)?;
+
+    let object = object_from_pairs(pairs, |index| {
+        pair_spans(cx.args.first(), index).unwrap_or((span, span))
+    })?;
+
+    Ok(Value::try_from(object).with_span(cx.span)?)
+}
+
+/// Collect an iterator of key-value tuples into an object.
+///
+/// This is what `collect::<Object>()` resolves to, later values replace
+/// earlier values with the same key.
+fn collect_object(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(0)?;
+    let span = cx.target;
+    let pairs = values(cx, &target, span)?;
+    let object = object_from_pairs(pairs, |_| (span, span))?;
+    Ok(Value::try_from(object).with_span(cx.span)?)
+}
+
+/// Build an object out of key-value tuples, where `spans` provides the span
+/// of each pair and of its key for error reporting.
+fn object_from_pairs(
+    pairs: Vec<Value>,
+    spans: impl Fn(usize) -> (Span, Span),
+) -> compile::Result<Object> {
+    let mut object = Object::with_capacity(pairs.len())?;
+
+    for (index, pair) in pairs.into_iter().enumerate() {
+        let (pair_span, key_span) = spans(index);
+
+        let pair = pair.borrow_kind_ref().with_span(pair_span)?;
+
+        let ValueKind::Tuple(tuple) = &*pair else {
+            return Err(compile::Error::expected_type::<_, OwnedTuple>(
+                pair_span, &*pair,
+            ));
+        };
+
+        let [key, value] = &tuple[..] else {
+            return Err(compile::Error::msg(
+                pair_span,
+                "Expected a tuple of a key and a value",
+            ));
+        };
+
+        let key = key.borrow_string_ref().with_span(key_span)?;
+        object.insert((*key).try_to_owned()?, value.clone())?;
+    }
+
+    Ok(object)
+}
+
+/// Get the span of the pair at the given index and of its key if the pairs
+/// are written out literally.
+fn pair_spans(pairs: Option<&ir::Ir>, index: usize) -> Option<(Span, Span)> {
+    let items = match &pairs?.kind {
+        ir::IrKind::Vec(vec) => &vec.items,
+        ir::IrKind::Tuple(tuple) => &tuple.items,
+        _ => return None,
+    };
+
+    let pair = items.get(index)?;
+
+    let key = match &pair.kind {
+        ir::IrKind::Tuple(tuple) => tuple.items.first().map(Spanned::span),
+        _ => None,
+    };
+
+    Some((pair.span(), key.unwrap_or(pair.span())))
+}
+
+/// Test if a value matches a literal or a unit struct or variant pattern.
+fn matches(cx: &mut Call<'_, '_, '_>) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(2)?;
+    let value = cx.arg(0)?;
+    let pattern = &cx.args[1];
+
+    let matches = match &pattern.kind {
+        ir::IrKind::Value(literal) => Value::partial_eq(&value, literal)
+            .into_result()
+            .with_span(pattern)?,
+        ir::IrKind::Unit(unit) => match &*value.borrow_kind_ref().with_span(cx.arg_span(0))? {
+            ValueKind::EmptyStruct(empty) => empty.rtti().hash == unit.hash,
+            ValueKind::Variant(variant) => {
+                variant.rtti().hash == unit.hash && matches!(variant.data(), VariantData::Empty)
+            }
+            _ => false,
+        },
+        _ => return Err(ir::EvalOutcome::not_const(pattern)),
+    };
+
+    Ok(Value::try_from(matches).with_span(cx.span)?)
+}
+
+/// Join a vector of strings with a separator.
+fn join(cx: &mut Call<'_, '_, '_>, target: Value) -> Result<Value, ir::EvalOutcome> {
+    cx.expect_args(1)?;
+    let values = vec_elements(&target, cx.target)?;
+    let sep = cx.arg(0)?;
+    let sep = sep.borrow_string_ref().with_span(cx.arg_span(0))?;
+
+    let mut output = String::new();
+
+    for (index, value) in values.iter().enumerate() {
+        let value = value.borrow_string_ref().with_span(cx.target)?;
+
+        if index > 0 {
+            cx.interp.budget.take_many(cx.span, sep.len())?;
+            cx.interp.alloc(cx.span, sep.len())?;
+            output.try_push_str(&sep)?;
+        }
+
+        cx.interp.budget.take_many(cx.span, value.len())?;
+        output.try_push_str(&value)?;
+        cx.interp.check_len(cx.span, output.len())?;
+        cx.interp.alloc(cx.span, value.len())?;
+    }
+
+    Ok(Value::try_from(output).with_span(cx.span)?)
+}
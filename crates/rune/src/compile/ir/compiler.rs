@@ -4,7 +4,7 @@ use crate::alloc::prelude::*;
 use crate::alloc::{try_format, Box, Vec};
 use crate::ast::{self, Span, Spanned};
 use crate::compile::ir;
-use crate::compile::{self, ErrorKind, WithSpan};
+use crate::compile::{self, ComponentRef, ErrorKind, IrErrorKind, ItemId, WithSpan};
 use crate::hir;
 use crate::query::Query;
 use crate::runtime::{Bytes, Value};
@@ -16,6 +16,9 @@ use rune_macros::instrument;
 pub(crate) struct Ctxt<'a, 'arena> {
     /// The source id of the source.
     pub(crate) source_id: SourceId,
+    /// The item which is being compiled, which constant warnings are recorded
+    /// against.
+    pub(crate) item: ItemId,
     /// Query associated with the compiler.
     pub(crate) q: Query<'a, 'arena>,
 }
@@ -27,22 +30,29 @@ pub(crate) fn expr(hir: &hir::Expr<'_>, c: &mut Ctxt<'_, '_>) -> compile::Result
     Ok(match hir.kind {
         hir::ExprKind::Vec(hir) => ir::Ir::new(span, expr_vec(span, c, hir)?),
         hir::ExprKind::Tuple(hir) => expr_tuple(c, span, hir)?,
+        hir::ExprKind::Range(hir) => ir::Ir::new(span, expr_range(span, c, hir)?),
         hir::ExprKind::Object(hir) => ir::Ir::new(span, expr_object(span, c, hir)?),
         hir::ExprKind::Group(hir) => expr(hir, c)?,
         hir::ExprKind::Binary(hir) => expr_binary(span, c, hir)?,
         hir::ExprKind::Assign(hir) => expr_assign(span, c, hir)?,
-        hir::ExprKind::Call(hir) => ir::Ir::new(span, expr_call(span, c, hir)?),
+        hir::ExprKind::Call(hir) => expr_call(span, c, hir)?,
         hir::ExprKind::If(hir) => ir::Ir::new(span, expr_if(span, c, hir)?),
+        hir::ExprKind::Match(hir) => ir::Ir::new(span, expr_match(span, c, hir)?),
         hir::ExprKind::Loop(hir) => ir::Ir::new(span, expr_loop(span, c, hir)?),
         hir::ExprKind::Lit(hir) => lit(c, span, hir)?,
         hir::ExprKind::Block(hir) => ir::Ir::new(span, block(hir, c)?),
-        hir::ExprKind::FieldAccess(..) => ir::Ir::new(span, ir_target(hir)?),
+        hir::ExprKind::FieldAccess(..) | hir::ExprKind::Index(..) => {
+            ir::Ir::new(span, ir_target(hir)?)
+        }
         hir::ExprKind::Break(hir) => ir::Ir::new(span, ir::IrBreak::compile_ast(span, c, hir)?),
+        hir::ExprKind::Continue(hir) => ir::Ir::new(span, ir::IrContinue::compile_ast(span, hir)?),
         hir::ExprKind::Template(template) => {
             let ir_template = builtin_template(template, c)?;
             ir::Ir::new(hir.span(), ir_template)
         }
         hir::ExprKind::Const(hash) => {
+            c.q.insert_const_dependency_hash(c.item, hash)?;
+
             let Some(value) = c.q.get_const_value(hash) else {
                 return Err(compile::Error::msg(
                     hir,
@@ -56,10 +66,13 @@ pub(crate) fn expr(hir: &hir::Expr<'_>, c: &mut Ctxt<'_, '_>) -> compile::Result
         hir::ExprKind::Variable(name) => {
             return Ok(ir::Ir::new(span, name.into_owned()?));
         }
+        hir::ExprKind::ConstClosure(hir) => ir::Ir::new(span, expr_closure(span, c, hir)?),
         _ => {
-            return Err(compile::Error::msg(
+            return Err(compile::Error::new(
                 hir,
-                "Expression kind not supported yet in constant contexts",
+                IrErrorKind::Unsupported {
+                    reason: "Expression kind not supported yet in constant contexts",
+                },
             ))
         }
     })
@@ -85,6 +98,10 @@ fn ir_target(expr: &hir::Expr<'_>) -> compile::Result<ir::IrTarget> {
                     });
                 }
                 hir::ExprField::Index(index) => {
+                    let Ok(index) = i64::try_from(index) else {
+                        return Err(compile::Error::new(expr, ErrorKind::BadFieldAccess));
+                    };
+
                     return Ok(ir::IrTarget {
                         span: expr.span(),
                         kind: ir::IrTargetKind::Index(Box::try_new(target)?, index),
@@ -95,6 +112,24 @@ fn ir_target(expr: &hir::Expr<'_>) -> compile::Result<ir::IrTarget> {
                 }
             }
         }
+        hir::ExprKind::Index(expr_index) => {
+            let target = ir_target(&expr_index.target)?;
+
+            let hir::ExprKind::Lit(hir::Lit::Integer(index)) = expr_index.index.kind else {
+                return Err(compile::Error::new(
+                    &expr_index.index,
+                    IrErrorKind::Unsupported {
+                        reason:
+                            "Only integer literals are supported as indexes in constant contexts",
+                    },
+                ));
+            };
+
+            return Ok(ir::IrTarget {
+                span: expr.span(),
+                kind: ir::IrTargetKind::Index(Box::try_new(target)?, index),
+            });
+        }
         _ => (),
     }
 
@@ -120,27 +155,140 @@ fn expr_assign(
 }
 
 #[instrument]
-fn expr_call(
-    span: Span,
-    c: &mut Ctxt<'_, '_>,
-    hir: &hir::ExprCall<'_>,
-) -> compile::Result<ir::IrCall> {
+fn expr_call(span: Span, c: &mut Ctxt<'_, '_>, hir: &hir::ExprCall<'_>) -> compile::Result<ir::Ir> {
     let mut args = Vec::try_with_capacity(hir.args.len())?;
 
     for e in hir.args {
         args.try_push(expr(e, c)?)?;
     }
 
-    if let hir::Call::ConstFn { id, .. } = hir.call {
-        return Ok(ir::IrCall { span, id, args });
+    match hir.call {
+        hir::Call::ConstFn { id, .. } => {
+            if c.q.options.const_graph {
+                let item = match c.q.pending_const_fn_item(id) {
+                    Some(item) => item,
+                    None => c.q.const_fn_for(id).with_span(span)?.item_meta.item,
+                };
+
+                c.q.insert_const_dependency(c.item, item)?;
+            }
+
+            return Ok(ir::Ir::new(span, ir::IrCall { span, id, args }));
+        }
+        hir::Call::Associated { target, hash } => {
+            let Some(method) = ir::builtins::method(hash) else {
+                return Err(compile::Error::new(
+                    span,
+                    IrErrorKind::Unsupported {
+                        reason: "Method not supported in constant contexts",
+                    },
+                ));
+            };
+
+            let target = Box::try_new(expr(target, c)?)?;
+
+            return Ok(ir::Ir::new(
+                span,
+                ir::IrCallMethod {
+                    span,
+                    target,
+                    method,
+                    args,
+                },
+            ));
+        }
+        hir::Call::ConstBuiltin { hash } => {
+            let Some(function) = ir::builtins::function(hash) else {
+                return Err(compile::Error::new(
+                    span,
+                    IrErrorKind::Unsupported {
+                        reason: "Function not supported in constant contexts",
+                    },
+                ));
+            };
+
+            return Ok(ir::Ir::new(
+                span,
+                ir::IrCallBuiltin {
+                    span,
+                    function,
+                    args,
+                },
+            ));
+        }
+        hir::Call::Meta { hash } => {
+            if let Some(function) = ir::builtins::native_function(hash) {
+                return Ok(ir::Ir::new(
+                    span,
+                    ir::IrCallBuiltin {
+                        span,
+                        function,
+                        args,
+                    },
+                ));
+            }
+
+            if c.q.context.lookup_function(hash).is_some() {
+                // NB: native functions are impure regardless of whether they're
+                // safe to call in constant contexts.
+                if c.q.options.require_pure_consts {
+                    return Err(c.q.impure_const_error(&span, hash)?);
+                }
+
+                if c.q.context.lookup_const_function(hash).is_none() {
+                    return Err(compile::Error::new(
+                        span,
+                        IrErrorKind::Unsupported {
+                            reason:
+                                "Native function is not marked as safe to call in constant contexts",
+                        },
+                    ));
+                }
+
+                return Ok(ir::Ir::new(span, ir::IrCallNative { span, hash, args }));
+            }
+
+            if args.is_empty() {
+                return Ok(ir::Ir::new(span, ir::IrUnit { span, hash }));
+            }
+        }
+        _ => {}
     }
 
-    Err(compile::Error::msg(
+    Err(compile::Error::new(
         span,
-        "Call not supported in constant contexts",
+        IrErrorKind::Unsupported {
+            reason: "Call not supported in constant contexts",
+        },
     ))
 }
 
+#[instrument]
+fn expr_closure(
+    span: Span,
+    c: &mut Ctxt<'_, '_>,
+    hir: &hir::ExprClosure<'_>,
+) -> compile::Result<ir::IrClosure> {
+    let mut args = Vec::try_with_capacity(hir.args.len())?;
+
+    for arg in hir.args {
+        let hir::FnArg::Pat(pat) = arg else {
+            return Err(compile::Error::msg(
+                arg,
+                "Unsupported argument in constant closure",
+            ));
+        };
+
+        args.try_push(ir::IrPat::compile_ast(pat, c)?)?;
+    }
+
+    Ok(ir::IrClosure {
+        span,
+        args,
+        body: Box::try_new(expr(&hir.body, c)?)?,
+    })
+}
+
 #[instrument]
 fn expr_binary(
     span: Span,
@@ -171,6 +319,21 @@ fn expr_binary(
         ));
     }
 
+    if let ast::BinOp::As(..) = hir.op {
+        let hir::ExprKind::Type(ty) = hir.rhs.kind else {
+            return Err(compile::Error::msg(hir.rhs, "Expected a type to cast to"));
+        };
+
+        return Ok(ir::Ir::new(
+            span,
+            ir::IrCast {
+                span,
+                value: Box::try_new(expr(&hir.lhs, c)?)?,
+                ty: ty.into_hash(),
+            },
+        ));
+    }
+
     let lhs = expr(&hir.lhs, c)?;
     let rhs = expr(&hir.rhs, c)?;
 
@@ -184,6 +347,7 @@ fn expr_binary(
         ast::BinOp::Lt(..) => ir::IrBinaryOp::Lt,
         ast::BinOp::Lte(..) => ir::IrBinaryOp::Lte,
         ast::BinOp::Eq(..) => ir::IrBinaryOp::Eq,
+        ast::BinOp::Neq(..) => ir::IrBinaryOp::Neq,
         ast::BinOp::Gt(..) => ir::IrBinaryOp::Gt,
         ast::BinOp::Gte(..) => ir::IrBinaryOp::Gte,
         _ => return Err(compile::Error::msg(hir.op, "op not supported yet")),
@@ -276,6 +440,33 @@ fn expr_vec(
     })
 }
 
+#[instrument]
+fn expr_range(
+    span: Span,
+    c: &mut Ctxt<'_, '_>,
+    hir: &hir::ExprRange<'_>,
+) -> compile::Result<ir::IrRange> {
+    let (start, end, inclusive) = match hir {
+        hir::ExprRange::Range { start, end } => (start, end, false),
+        hir::ExprRange::RangeInclusive { start, end } => (start, end, true),
+        _ => {
+            return Err(compile::Error::new(
+                span,
+                IrErrorKind::Unsupported {
+                    reason: "Only bounded ranges are supported in constant contexts",
+                },
+            ))
+        }
+    };
+
+    Ok(ir::IrRange {
+        span,
+        start: Box::try_new(expr(start, c)?)?,
+        end: Box::try_new(expr(end, c)?)?,
+        inclusive,
+    })
+}
+
 #[instrument]
 fn expr_object(
     span: Span,
@@ -307,7 +498,7 @@ pub(crate) fn block(hir: &hir::Block<'_>, c: &mut Ctxt<'_, '_>) -> compile::Resu
         let (e, term) = match stmt {
             hir::Stmt::Local(l) => {
                 if let Some((e, _)) = take(&mut last) {
-                    instructions.try_push(expr(e, c)?)?;
+                    instructions.try_push(stmt_expr(e, span, c)?)?;
                 }
 
                 instructions.try_push(local(l, c)?)?;
@@ -319,13 +510,13 @@ pub(crate) fn block(hir: &hir::Block<'_>, c: &mut Ctxt<'_, '_>) -> compile::Resu
         };
 
         if let Some((e, _)) = replace(&mut last, Some((e, term))) {
-            instructions.try_push(expr(e, c)?)?;
+            instructions.try_push(stmt_expr(e, span, c)?)?;
         }
     }
 
     let last = if let Some((e, term)) = last {
         if term {
-            instructions.try_push(expr(e, c)?)?;
+            instructions.try_push(stmt_expr(e, span, c)?)?;
             None
         } else {
             Some(Box::try_new(expr(e, c)?)?)
@@ -341,6 +532,40 @@ pub(crate) fn block(hir: &hir::Block<'_>, c: &mut Ctxt<'_, '_>) -> compile::Resu
     })
 }
 
+/// Compile an expression used as a statement, warning if it produces a value
+/// which is never used.
+fn stmt_expr(hir: &hir::Expr<'_>, context: Span, c: &mut Ctxt<'_, '_>) -> compile::Result<ir::Ir> {
+    if produces_value(hir) {
+        c.q.diagnostics.const_statement_unused(
+            c.source_id,
+            hir,
+            Some(context),
+            c.q.pool.item(c.item),
+        )?;
+    }
+
+    expr(hir, c)
+}
+
+/// Test if the given expression unconditionally produces a value without
+/// having any side effects.
+fn produces_value(hir: &hir::Expr<'_>) -> bool {
+    match hir.kind {
+        hir::ExprKind::Group(hir) => produces_value(hir),
+        hir::ExprKind::Lit(..)
+        | hir::ExprKind::Const(..)
+        | hir::ExprKind::Variable(..)
+        | hir::ExprKind::Template(..)
+        | hir::ExprKind::Vec(..)
+        | hir::ExprKind::Object(..)
+        | hir::ExprKind::FieldAccess(..)
+        | hir::ExprKind::Index(..) => true,
+        hir::ExprKind::Tuple(hir) => !hir.items.is_empty(),
+        hir::ExprKind::Binary(hir) => !hir.op.is_assign(),
+        _ => false,
+    }
+}
+
 #[instrument]
 fn builtin_template(
     template: &hir::BuiltInTemplate,
@@ -376,12 +601,22 @@ fn local(hir: &hir::Local<'_>, c: &mut Ctxt<'_, '_>) -> compile::Result<ir::Ir>
         }
     };
 
+    let ty = match hir.ty {
+        Some(ty) => Some(ir::IrDeclType {
+            span: ty.span,
+            hash: ty.hash,
+            name: ty.name.try_into()?,
+        }),
+        None => None,
+    };
+
     Ok(ir::Ir::new(
         span,
         ir::IrDecl {
             span,
             name: hir::Name::Str(name).into_owned()?,
             value: Box::try_new(expr(&hir.expr, c)?)?,
+            ty,
         },
     ))
 }
@@ -391,7 +626,7 @@ fn condition(hir: &hir::Condition<'_>, c: &mut Ctxt<'_, '_>) -> compile::Result<
     match hir {
         hir::Condition::Expr(e) => Ok(ir::IrCondition::Ir(expr(e, c)?)),
         hir::Condition::ExprLet(hir) => {
-            let pat = ir::IrPat::compile_ast(&hir.pat)?;
+            let pat = ir::IrPat::compile_ast(&hir.pat, c)?;
             let ir = expr(&hir.expr, c)?;
 
             Ok(ir::IrCondition::Let(ir::IrLet {
@@ -431,6 +666,117 @@ fn expr_if(
     })
 }
 
+#[instrument]
+fn expr_match(
+    span: Span,
+    c: &mut Ctxt<'_, '_>,
+    hir: &hir::ExprMatch<'_>,
+) -> compile::Result<ir::IrMatch> {
+    let target = expr(&hir.expr, c)?;
+    let mut branches = Vec::new();
+
+    for hir in hir.branches {
+        let pat = ir::IrPat::compile_ast(&hir.pat, c)?;
+
+        let condition = match hir.condition {
+            Some(hir) => Some(expr(hir, c)?),
+            None => None,
+        };
+
+        let body = expr(&hir.body, c)?;
+
+        branches.try_push(ir::IrMatchBranch {
+            span: hir.span,
+            pat,
+            condition,
+            body,
+        })?;
+    }
+
+    match_exhaustive(span, hir, c)?;
+
+    Ok(ir::IrMatch {
+        span,
+        expr: Box::try_new(target)?,
+        branches,
+    })
+}
+
+/// Warn if a match over a script enum doesn't handle all of its variants and
+/// has no wildcard branch.
+fn match_exhaustive(
+    span: Span,
+    hir: &hir::ExprMatch<'_>,
+    c: &mut Ctxt<'_, '_>,
+) -> compile::Result<()> {
+    let mut enum_hash = None;
+    let mut handled = Vec::new();
+
+    for branch in hir.branches {
+        let kind = match branch.pat.kind {
+            hir::PatKind::Ignore | hir::PatKind::Path(&hir::PatPathKind::Ident(..)) => {
+                if branch.condition.is_none() {
+                    return Ok(());
+                }
+
+                continue;
+            }
+            hir::PatKind::Path(&hir::PatPathKind::Kind(kind)) => kind,
+            hir::PatKind::Sequence(&hir::PatSequence { ref kind, .. }) => kind,
+            _ => return Ok(()),
+        };
+
+        let &hir::PatSequenceKind::Variant {
+            variant_hash,
+            enum_hash: hash,
+            ..
+        } = kind
+        else {
+            return Ok(());
+        };
+
+        if *enum_hash.get_or_insert(hash) != hash {
+            return Ok(());
+        }
+
+        handled.try_push(variant_hash)?;
+    }
+
+    let Some(enum_hash) = enum_hash else {
+        return Ok(());
+    };
+
+    let Some(variants) = c.q.enum_variants(&span, enum_hash)? else {
+        return Ok(());
+    };
+
+    let mut missing = Vec::new();
+
+    for (hash, item) in variants {
+        if handled.contains(&hash) {
+            continue;
+        }
+
+        if let Some(ComponentRef::Str(name)) = c.q.pool.item(item).last() {
+            missing.try_push(name.try_to_owned()?)?;
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    c.q.diagnostics.non_exhaustive_const_match(
+        c.source_id,
+        &hir.expr,
+        Some(span),
+        missing,
+        c.q.pool.item(c.item),
+    )?;
+
+    Ok(())
+}
+
 #[instrument]
 fn expr_loop(
     span: Span,
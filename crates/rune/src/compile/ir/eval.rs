@@ -1,13 +1,14 @@
-use core::ops::{Add, Mul, Shl, Shr, Sub};
+use core::ops::{Shl, Shr};
 
 use crate::alloc::fmt::TryWrite;
 use crate::alloc::prelude::*;
+use crate::alloc::try_format;
 use crate::alloc::{Box, String, Vec};
 use crate::ast::{Span, Spanned};
 use crate::compile::ir::{self};
-use crate::compile::{self, WithSpan};
+use crate::compile::{self, IrErrorKind, WithSpan};
 use crate::query::Used;
-use crate::runtime::{Object, OwnedTuple, Value, ValueKind};
+use crate::runtime::{static_type, ConstOp, ConstValue, Object, OwnedTuple, Value, ValueKind};
 
 /// The outcome of a constant evaluation.
 pub enum EvalOutcome {
@@ -17,6 +18,9 @@ pub enum EvalOutcome {
     Error(compile::Error),
     /// Break until the next loop, or the optional label.
     Break(Span, Option<Box<str>>, Option<Value>),
+    /// Continue with the next iteration of the next loop, or the optional
+    /// label.
+    Continue(Span, Option<Box<str>>),
 }
 
 impl EvalOutcome {
@@ -46,13 +50,58 @@ fn eval_ir_assign(
     interp.budget.take(ir)?;
     let value = eval_ir(&ir.value, interp, used)?;
 
+    let limit = interp.q.options.const_recursion_limit;
+
     interp
         .scopes
-        .mut_target(&ir.target, move |t| ir.op.assign(ir, t, value))?;
+        .mut_target(&ir.target, move |t| ir.op.assign(ir, t, value), limit)?;
 
     Ok(Value::empty().with_span(ir)?)
 }
 
+fn eval_ir_cast(
+    ir: &ir::IrCast,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    interp.budget.take(ir)?;
+    let value = eval_ir(&ir.value, interp, used)?;
+
+    macro_rules! convert {
+        ($value:expr) => {
+            match ir.ty {
+                static_type::FLOAT_TYPE_HASH => ValueKind::Float($value as f64),
+                static_type::BYTE_TYPE_HASH => ValueKind::Byte($value as u8),
+                static_type::INTEGER_TYPE_HASH => ValueKind::Integer($value as i64),
+                _ => return Err(EvalOutcome::not_const(ir)),
+            }
+        };
+    }
+
+    let kind = match (&*value.borrow_kind_ref().with_span(ir)?, ir.ty) {
+        (ValueKind::Bool(..), static_type::INTEGER_TYPE_HASH | static_type::BYTE_TYPE_HASH) => {
+            return Err(compile::Error::msg(
+                ir,
+                "Booleans can't be cast to integers, use `if b { 1 } else { 0 }` instead",
+            )
+            .into());
+        }
+        (ValueKind::Integer(..), static_type::BOOL_TYPE_HASH) => {
+            return Err(compile::Error::msg(
+                ir,
+                "Integers can't be cast to `bool`, compare against zero instead, like `n != 0`",
+            )
+            .into());
+        }
+        (ValueKind::Integer(value), _) => convert!(*value),
+        (ValueKind::Float(value), _) => convert!(*value),
+        (ValueKind::Byte(value), _) => convert!(*value),
+        _ => return Err(EvalOutcome::not_const(ir)),
+    };
+
+    Ok(Value::try_from(kind).with_span(ir)?)
+}
+
 fn eval_ir_binary(
     ir: &ir::IrBinary,
     interp: &mut ir::Interpreter<'_, '_>,
@@ -65,12 +114,20 @@ fn eval_ir_binary(
         Ok(out)
     }
 
+    fn checked(span: Span, value: Option<i64>) -> compile::Result<i64> {
+        value.ok_or_else(|| compile::Error::new(span, IrErrorKind::IntegerOverflow))
+    }
+
     let span = ir.span();
     interp.budget.take(span)?;
 
     let a = eval_ir(&ir.lhs, interp, used)?;
     let b = eval_ir(&ir.rhs, interp, used)?;
 
+    if matches!(&*a.borrow_kind_ref().with_span(ir)?, ValueKind::Any(..)) {
+        return eval_ir_binary_extension(ir, interp, &a, &b);
+    }
+
     let a = a.borrow_kind_ref().with_span(ir)?;
     let b = b.borrow_kind_ref().with_span(ir)?;
 
@@ -78,13 +135,13 @@ fn eval_ir_binary(
         match (&*a, &*b) {
             (ValueKind::Integer(a), ValueKind::Integer(b)) => match ir.op {
                 ir::IrBinaryOp::Add => {
-                    break 'out ValueKind::Integer(a.add(b));
+                    break 'out ValueKind::Integer(checked(span, a.checked_add(*b))?);
                 }
                 ir::IrBinaryOp::Sub => {
-                    break 'out ValueKind::Integer(a.sub(b));
+                    break 'out ValueKind::Integer(checked(span, a.checked_sub(*b))?);
                 }
                 ir::IrBinaryOp::Mul => {
-                    break 'out ValueKind::Integer(a.mul(b));
+                    break 'out ValueKind::Integer(checked(span, a.checked_mul(*b))?);
                 }
                 ir::IrBinaryOp::Div => {
                     let number = a
@@ -111,6 +168,7 @@ fn eval_ir_binary(
                 ir::IrBinaryOp::Lt => break 'out ValueKind::Bool(a < b),
                 ir::IrBinaryOp::Lte => break 'out ValueKind::Bool(a <= b),
                 ir::IrBinaryOp::Eq => break 'out ValueKind::Bool(a == b),
+                ir::IrBinaryOp::Neq => break 'out ValueKind::Bool(a != b),
                 ir::IrBinaryOp::Gt => break 'out ValueKind::Bool(a > b),
                 ir::IrBinaryOp::Gte => break 'out ValueKind::Bool(a >= b),
             },
@@ -124,16 +182,35 @@ fn eval_ir_binary(
                     ir::IrBinaryOp::Lt => break 'out ValueKind::Bool(a < b),
                     ir::IrBinaryOp::Lte => break 'out ValueKind::Bool(a <= b),
                     ir::IrBinaryOp::Eq => break 'out ValueKind::Bool(a == b),
+                    ir::IrBinaryOp::Neq => break 'out ValueKind::Bool(a != b),
                     ir::IrBinaryOp::Gt => break 'out ValueKind::Bool(a > b),
                     ir::IrBinaryOp::Gte => break 'out ValueKind::Bool(a >= b),
                     _ => (),
                 };
             }
-            (ValueKind::String(a), ValueKind::String(b)) => {
-                if let ir::IrBinaryOp::Add = ir.op {
+            (ValueKind::Char(a), ValueKind::Char(b)) => match ir.op {
+                ir::IrBinaryOp::Lt => break 'out ValueKind::Bool(a < b),
+                ir::IrBinaryOp::Lte => break 'out ValueKind::Bool(a <= b),
+                ir::IrBinaryOp::Eq => break 'out ValueKind::Bool(a == b),
+                ir::IrBinaryOp::Neq => break 'out ValueKind::Bool(a != b),
+                ir::IrBinaryOp::Gt => break 'out ValueKind::Bool(a > b),
+                ir::IrBinaryOp::Gte => break 'out ValueKind::Bool(a >= b),
+                _ => (),
+            },
+            (ValueKind::String(a), ValueKind::String(b)) => match ir.op {
+                ir::IrBinaryOp::Add => {
+                    interp.check_len(span, a.len().saturating_add(b.len()))?;
+                    interp.alloc(span, a.len().saturating_add(b.len()))?;
                     break 'out ValueKind::String(add_strings(a, b).with_span(span)?);
                 }
-            }
+                ir::IrBinaryOp::Lt => break 'out ValueKind::Bool(a < b),
+                ir::IrBinaryOp::Lte => break 'out ValueKind::Bool(a <= b),
+                ir::IrBinaryOp::Eq => break 'out ValueKind::Bool(a == b),
+                ir::IrBinaryOp::Neq => break 'out ValueKind::Bool(a != b),
+                ir::IrBinaryOp::Gt => break 'out ValueKind::Bool(a > b),
+                ir::IrBinaryOp::Gte => break 'out ValueKind::Bool(a >= b),
+                _ => (),
+            },
             _ => (),
         }
 
@@ -143,6 +220,46 @@ fn eval_ir_binary(
     Ok(Value::try_from(kind).with_span(span)?)
 }
 
+/// Fold a binary operation where the left-hand side is a host value, which is
+/// delegated to its [`ConstKind`] implementation.
+///
+/// [`ConstKind`]: crate::runtime::ConstKind
+fn eval_ir_binary_extension(
+    ir: &ir::IrBinary,
+    interp: &mut ir::Interpreter<'_, '_>,
+    a: &Value,
+    b: &Value,
+) -> Result<Value, EvalOutcome> {
+    let span = ir.span();
+
+    let op = match ir.op {
+        ir::IrBinaryOp::Add => ConstOp::Add,
+        ir::IrBinaryOp::Sub => ConstOp::Sub,
+        ir::IrBinaryOp::Mul => ConstOp::Mul,
+        ir::IrBinaryOp::Div => ConstOp::Div,
+        ir::IrBinaryOp::Lt => ConstOp::Lt,
+        ir::IrBinaryOp::Lte => ConstOp::Lte,
+        ir::IrBinaryOp::Eq => ConstOp::Eq,
+        ir::IrBinaryOp::Gt => ConstOp::Gt,
+        ir::IrBinaryOp::Gte => ConstOp::Gte,
+        ir::IrBinaryOp::Neq | ir::IrBinaryOp::Shl | ir::IrBinaryOp::Shr => {
+            return Err(EvalOutcome::not_const(span))
+        }
+    };
+
+    let ConstValue::Extension(a) = interp.const_value(&*ir.lhs, a)? else {
+        return Err(EvalOutcome::not_const(span));
+    };
+
+    let b = interp.const_value(&*ir.rhs, b)?;
+
+    let Some(value) = a.fold(op, &b).into_result().with_span(span)? else {
+        return Err(EvalOutcome::not_const(span));
+    };
+
+    Ok(value.as_value().with_span(span)?)
+}
+
 fn eval_ir_branches(
     ir: &ir::IrBranches,
     interp: &mut ir::Interpreter<'_, '_>,
@@ -173,6 +290,43 @@ fn eval_ir_branches(
     Ok(Value::empty().with_span(ir)?)
 }
 
+fn eval_ir_match(
+    ir: &ir::IrMatch,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    let value = eval_ir(&ir.expr, interp, used)?;
+
+    for branch in &ir.branches {
+        interp.budget.take(branch)?;
+        let guard = interp.scopes.push()?;
+
+        let mut matched = branch.pat.matches(interp, value.clone(), &*ir.expr)?;
+
+        if matched {
+            if let Some(condition) = &branch.condition {
+                matched = eval_ir(condition, interp, used)?
+                    .as_bool()
+                    .with_span(condition)?;
+            }
+        }
+
+        let output = if matched {
+            Some(eval_ir(&branch.body, interp, used)?)
+        } else {
+            None
+        };
+
+        interp.scopes.pop(guard).with_span(branch)?;
+
+        if let Some(output) = output {
+            return Ok(output);
+        }
+    }
+
+    Err(compile::Error::msg(ir, "No branch matched the value").into())
+}
+
 fn eval_ir_call(
     ir: &ir::IrCall,
     interp: &mut ir::Interpreter<'_, '_>,
@@ -187,6 +341,37 @@ fn eval_ir_call(
     Ok(interp.call_const_fn(ir, ir.id, args, used)?)
 }
 
+fn eval_ir_call_native(
+    ir: &ir::IrCallNative,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    let mut args = Vec::new();
+
+    for arg in &ir.args {
+        args.try_push(eval_ir(arg, interp, used)?)?;
+    }
+
+    Ok(interp.call_native(ir, ir.hash, args)?)
+}
+
+fn eval_ir_call_method(
+    ir: &ir::IrCallMethod,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    let target = eval_ir(&ir.target, interp, used)?;
+    ir.method.call(interp, ir, target, used)
+}
+
+fn eval_ir_call_builtin(
+    ir: &ir::IrCallBuiltin,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    ir.function.call(interp, ir, used)
+}
+
 fn eval_ir_condition(
     ir: &ir::IrCondition,
     interp: &mut ir::Interpreter<'_, '_>,
@@ -199,7 +384,7 @@ fn eval_ir_condition(
         }
         ir::IrCondition::Let(ir_let) => {
             let value = eval_ir(&ir_let.ir, interp, used)?;
-            ir_let.pat.matches(interp, value, ir)?
+            ir_let.pat.matches(interp, value, &ir_let.ir)?
         }
     };
 
@@ -213,10 +398,34 @@ fn eval_ir_decl(
 ) -> Result<Value, EvalOutcome> {
     interp.budget.take(ir)?;
     let value = eval_ir(&ir.value, interp, used)?;
+
+    if let Some(ty) = &ir.ty {
+        if value.type_hash().with_span(&*ir.value)? != ty.hash {
+            return Err(compile::Error::msg(
+                &*ir.value,
+                try_format!(
+                    "Expected a value of type `{}` but got `{}`",
+                    ty.name,
+                    value.type_info().with_span(&*ir.value)?
+                ),
+            )
+            .into());
+        }
+    }
+
     interp.scopes.decl(&ir.name, value).with_span(ir)?;
     Ok(Value::empty().with_span(ir)?)
 }
 
+/// Test if a `break` or `continue` with the given label targets the loop. An
+/// unlabeled `break` or `continue` targets the innermost loop.
+fn is_loop_label(ir: &ir::IrLoop, label: Option<&str>) -> bool {
+    match label {
+        Some(label) => ir.label.as_deref() == Some(label),
+        None => true,
+    }
+}
+
 fn eval_ir_loop(
     ir: &ir::IrLoop,
     interp: &mut ir::Interpreter<'_, '_>,
@@ -242,12 +451,17 @@ fn eval_ir_loop(
             Ok(..) => (),
             Err(outcome) => match outcome {
                 EvalOutcome::Break(span, label, expr) => {
-                    if label.as_deref() == ir.label.as_deref() {
+                    if is_loop_label(ir, label.as_deref()) {
                         break expr;
                     } else {
                         return Err(EvalOutcome::Break(span, label, expr));
                     }
                 }
+                EvalOutcome::Continue(span, label) => {
+                    if !is_loop_label(ir, label.as_deref()) {
+                        return Err(EvalOutcome::Continue(span, label));
+                    }
+                }
                 outcome => return Err(outcome),
             },
         };
@@ -274,6 +488,8 @@ fn eval_ir_object(
     interp: &mut ir::Interpreter<'_, '_>,
     used: Used,
 ) -> Result<Value, EvalOutcome> {
+    interp.check_len(ir, ir.assignments.len())?;
+    interp.alloc_values(ir, ir.assignments.len())?;
     let mut object = Object::with_capacity(ir.assignments.len())?;
 
     for (key, value) in ir.assignments.iter() {
@@ -313,7 +529,8 @@ fn eval_ir_set(
 ) -> Result<Value, EvalOutcome> {
     interp.budget.take(ir)?;
     let value = eval_ir(&ir.value, interp, used)?;
-    interp.scopes.set_target(&ir.target, value)?;
+    let limit = interp.q.options.const_recursion_limit;
+    interp.scopes.set_target(&ir.target, value, limit)?;
     Ok(Value::empty().with_span(ir)?)
 }
 
@@ -355,8 +572,12 @@ fn eval_ir_template(
                 }
             }
         }
+
+        interp.check_len(ir, buf.len())?;
     }
 
+    interp.alloc(ir, buf.len())?;
+
     Ok(Value::try_from(buf).with_span(ir)?)
 }
 
@@ -380,6 +601,8 @@ fn eval_ir_vec(
     interp: &mut ir::Interpreter<'_, '_>,
     used: Used,
 ) -> Result<Value, EvalOutcome> {
+    interp.check_len(ir, ir.items.len())?;
+    interp.alloc_values(ir, ir.items.len())?;
     let mut vec = Vec::try_with_capacity(ir.items.len())?;
 
     for item in ir.items.iter() {
@@ -390,30 +613,163 @@ fn eval_ir_vec(
     Ok(Value::try_from(vec).with_span(ir)?)
 }
 
+/// Ranges are evaluated eagerly into an iterator over their elements, charging
+/// the budget for each produced element.
+fn eval_ir_range(
+    ir: &ir::IrRange,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    fn push(
+        interp: &mut ir::Interpreter<'_, '_>,
+        span: Span,
+        values: &mut Vec<Value>,
+        kind: ValueKind,
+    ) -> compile::Result<()> {
+        interp.budget.take(span)?;
+        interp.check_len(span, values.len() + 1)?;
+        interp.alloc_values(span, 1)?;
+        values.try_push(Value::try_from(kind).with_span(span)?)?;
+        Ok(())
+    }
+
+    let span = ir.span();
+    let start = eval_ir(&ir.start, interp, used)?;
+    let end = eval_ir(&ir.end, interp, used)?;
+
+    let start = start.borrow_kind_ref().with_span(span)?;
+    let end = end.borrow_kind_ref().with_span(span)?;
+
+    let mut values = Vec::new();
+
+    match (&*start, &*end) {
+        (ValueKind::Integer(start), ValueKind::Integer(end)) => {
+            if ir.inclusive {
+                for n in *start..=*end {
+                    push(interp, span, &mut values, ValueKind::Integer(n))?;
+                }
+            } else {
+                for n in *start..*end {
+                    push(interp, span, &mut values, ValueKind::Integer(n))?;
+                }
+            }
+        }
+        (ValueKind::Char(start), ValueKind::Char(end)) => {
+            if start > end {
+                return Err(compile::Error::msg(
+                    span,
+                    "Character range must not start after it ends",
+                )
+                .into());
+            }
+
+            if (*start as u32) >> 16 != (*end as u32) >> 16 {
+                return Err(compile::Error::msg(
+                    span,
+                    "Character range must not cross a Unicode plane",
+                )
+                .into());
+            }
+
+            if ir.inclusive {
+                for c in *start..=*end {
+                    push(interp, span, &mut values, ValueKind::Char(c))?;
+                }
+            } else {
+                for c in *start..*end {
+                    push(interp, span, &mut values, ValueKind::Char(c))?;
+                }
+            }
+        }
+        _ => return Err(EvalOutcome::not_const(span)),
+    }
+
+    let name = if ir.inclusive {
+        "std::ops::RangeInclusive"
+    } else {
+        "std::ops::Range"
+    };
+
+    let iter = crate::runtime::Iterator::from_double_ended(name, values.into_iter());
+    Ok(Value::try_from(iter).with_span(span)?)
+}
+
 /// IrEval the interior expression.
 pub(crate) fn eval_ir(
     ir: &ir::Ir,
     interp: &mut ir::Interpreter<'_, '_>,
     used: Used,
+) -> Result<Value, EvalOutcome> {
+    if interp.explain.is_some() {
+        return eval_ir_explained(ir, interp, used);
+    }
+
+    eval_ir_kind(ir, interp, used)
+}
+
+/// Evaluate the interior expression while recording it as a fold step.
+#[cold]
+fn eval_ir_explained(
+    ir: &ir::Ir,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
+) -> Result<Value, EvalOutcome> {
+    if let Some(explain) = &mut interp.explain {
+        explain.enter()?;
+    }
+
+    let result = eval_ir_kind(ir, interp, used);
+
+    let output = match &result {
+        Ok(value) => Some(interp.const_value(ir, value).ok()),
+        Err(..) => None,
+    };
+
+    if let Some(explain) = &mut interp.explain {
+        match output {
+            Some(output) => explain.exit(ir.span(), ir.kind.name(), output)?,
+            None => explain.abandon()?,
+        }
+    }
+
+    result
+}
+
+fn eval_ir_kind(
+    ir: &ir::Ir,
+    interp: &mut ir::Interpreter<'_, '_>,
+    used: Used,
 ) -> Result<Value, EvalOutcome> {
     interp.budget.take(ir)?;
 
     match &ir.kind {
         ir::IrKind::Scope(ir) => eval_ir_scope(ir, interp, used),
         ir::IrKind::Binary(ir) => eval_ir_binary(ir, interp, used),
+        ir::IrKind::Cast(ir) => eval_ir_cast(ir, interp, used),
         ir::IrKind::Decl(ir) => eval_ir_decl(ir, interp, used),
         ir::IrKind::Set(ir) => eval_ir_set(ir, interp, used),
         ir::IrKind::Assign(ir) => eval_ir_assign(ir, interp, used),
         ir::IrKind::Template(ir) => eval_ir_template(ir, interp, used),
         ir::IrKind::Name(name) => Ok(interp.resolve_var(ir, name, used)?),
-        ir::IrKind::Target(target) => Ok(interp.scopes.get_target(target)?),
+        ir::IrKind::Target(target) => {
+            let limit = interp.q.options.const_recursion_limit;
+            Ok(interp.scopes.get_target(target, limit)?)
+        }
         ir::IrKind::Value(value) => Ok(value.try_clone()?),
         ir::IrKind::Branches(ir) => eval_ir_branches(ir, interp, used),
+        ir::IrKind::Match(ir) => eval_ir_match(ir, interp, used),
         ir::IrKind::Loop(ir) => eval_ir_loop(ir, interp, used),
         ir::IrKind::Break(ir) => Err(ir.as_outcome(interp, used)),
+        ir::IrKind::Continue(ir) => Err(ir.as_outcome(interp)),
+        ir::IrKind::Range(ir) => eval_ir_range(ir, interp, used),
         ir::IrKind::Vec(ir) => eval_ir_vec(ir, interp, used),
         ir::IrKind::Tuple(ir) => eval_ir_tuple(ir, interp, used),
         ir::IrKind::Object(ir) => eval_ir_object(ir, interp, used),
         ir::IrKind::Call(ir) => eval_ir_call(ir, interp, used),
+        ir::IrKind::CallMethod(ir) => eval_ir_call_method(ir, interp, used),
+        ir::IrKind::CallBuiltin(ir) => eval_ir_call_builtin(ir, interp, used),
+        ir::IrKind::CallNative(ir) => eval_ir_call_native(ir, interp, used),
+        ir::IrKind::Unit(ir) => Err(EvalOutcome::not_const(ir)),
+        ir::IrKind::Closure(ir) => Err(EvalOutcome::not_const(ir)),
     }
 }
@@ -1,14 +1,20 @@
+use core::mem;
+
 use crate::alloc::prelude::*;
-use crate::alloc::{try_format, Vec};
-use crate::ast::Spanned;
+use crate::alloc::{self, try_format, Box, HashMap, Vec};
+use crate::ast::{Span, Spanned};
 use crate::compile::ir;
 use crate::compile::ir::scopes::MissingLocal;
 use crate::compile::meta;
-use crate::compile::{self, IrErrorKind, ItemId, ModId, WithSpan};
+use crate::compile::{self, FoldStep, IrErrorKind, ItemId, ModId, WithSpan};
+use crate::hash::ParametersBuilder;
 use crate::hir;
 use crate::parse::NonZeroId;
 use crate::query::{Query, Used};
-use crate::runtime::{ConstValue, Object, OwnedTuple, Value, ValueKind};
+use crate::runtime::{
+    ConstValue, Object, OwnedTuple, Stack, Value, ValueKind, VmError, VmErrorKind,
+};
+use crate::{Hash, SourceId};
 
 /// The interpreter that executed [Ir][crate::ir::Ir].
 pub struct Interpreter<'a, 'arena> {
@@ -19,6 +25,10 @@ pub struct Interpreter<'a, 'arena> {
     pub(crate) module: ModId,
     /// The item where the constant expression is located.
     pub(crate) item: ItemId,
+    /// The source where the constant expression is located.
+    pub(crate) source_id: SourceId,
+    /// Steps recorded when explaining how a constant was folded.
+    pub(crate) explain: Option<Explain>,
     /// Constant scopes.
     pub(crate) scopes: ir::Scopes,
     /// Query engine to look for constant expressions.
@@ -30,59 +40,234 @@ impl Interpreter<'_, '_> {
     pub(crate) fn eval_const(&mut self, ir: &ir::Ir, used: Used) -> compile::Result<ConstValue> {
         tracing::trace!("processing constant: {}", self.q.pool.item(self.item));
 
-        if let Some(const_value) = self.q.consts.get(self.item) {
-            return Ok(const_value.try_clone()?);
-        }
+        let no_cache = self.q.options.const_eval_no_cache;
 
-        if !self.q.consts.mark(self.item)? {
-            return Err(compile::Error::new(ir, IrErrorKind::ConstCycle));
+        if !no_cache {
+            if let Some(const_value) = self.q.consts.get(self.item) {
+                return Ok(const_value.try_clone()?);
+            }
         }
 
-        let ir_value = match ir::eval_ir(ir, self, used) {
-            Ok(ir_value) => ir_value,
-            Err(outcome) => match outcome {
-                ir::EvalOutcome::Error(error) => {
-                    return Err(error);
-                }
-                ir::EvalOutcome::NotConst(span) => {
-                    return Err(compile::Error::new(span, IrErrorKind::NotConst))
-                }
-                ir::EvalOutcome::Break(span, _, _) => {
-                    return Err(compile::Error::new(span, IrErrorKind::BreakOutsideOfLoop))
-                }
-            },
-        };
-
-        let const_value: ConstValue = crate::from_value(ir_value).with_span(ir)?;
+        let result = self.eval_value(ir, used);
+        self.visit_explained(ir)?;
+        let ir_value = result?;
+        let const_value = self.const_value(ir, &ir_value)?;
 
+        // NB: with caching disabled the constant is expected to have been
+        // evaluated before, cycles are instead detected when it's marked.
         if self
             .q
             .consts
             .insert(self.item, const_value.try_clone()?)?
             .is_some()
+            && !no_cache
         {
-            return Err(compile::Error::new(ir, IrErrorKind::ConstCycle));
+            return Err(self.q.const_cycle_error(ir, self.item)?);
         }
 
         Ok(const_value)
     }
 
-    /// Evaluate to an ir value.
+    /// Reset the budget and scopes of the interpreter so that it can be reused
+    /// for another evaluation without reallocating.
+    pub(crate) fn reset(&mut self) {
+        self.budget.reset();
+        self.scopes.reset();
+    }
+
+    /// Ensure that a collection of the given length doesn't exceed the
+    /// maximum length allowed in constant contexts.
+    pub(crate) fn check_len<S>(&self, spanned: S, len: usize) -> compile::Result<()>
+    where
+        S: Spanned,
+    {
+        let max = self.q.options.const_max_collection_len;
+
+        if len > max {
+            return Err(compile::Error::new(
+                spanned,
+                IrErrorKind::CollectionTooLarge { max },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Account for `bytes` being allocated when evaluating a constant, and
+    /// ensure that the total across the compilation doesn't exceed the limit.
+    pub(crate) fn alloc<S>(&mut self, spanned: S, bytes: usize) -> compile::Result<()>
+    where
+        S: Spanned,
+    {
+        let limit = self.q.options.const_total_alloc_limit;
+        let total = self.q.inner.const_alloc.saturating_add(bytes);
+
+        if total > limit {
+            return Err(compile::Error::new(
+                spanned,
+                IrErrorKind::AllocationLimit { limit },
+            ));
+        }
+
+        self.q.inner.const_alloc = total;
+        Ok(())
+    }
+
+    /// Account for `count` values being allocated when evaluating a constant.
+    pub(crate) fn alloc_values<S>(&mut self, spanned: S, count: usize) -> compile::Result<()>
+    where
+        S: Spanned,
+    {
+        self.alloc(spanned, count.saturating_mul(mem::size_of::<Value>()))
+    }
+
+    /// Convert a value into a constant value.
+    ///
+    /// Unlike [`FromValue`], this doesn't take the contents of the value and
+    /// supports host types which have been registered as constant kinds.
+    ///
+    /// [`FromValue`]: crate::runtime::FromValue
+    pub(crate) fn const_value<S>(&self, spanned: S, value: &Value) -> compile::Result<ConstValue>
+    where
+        S: Copy + Spanned,
+    {
+        let kind = value.borrow_kind_ref().with_span(spanned)?;
+
+        Ok(match &*kind {
+            ValueKind::EmptyTuple => ConstValue::EmptyTuple,
+            ValueKind::Byte(b) => ConstValue::Byte(*b),
+            ValueKind::Char(c) => ConstValue::Char(*c),
+            ValueKind::Bool(b) => ConstValue::Bool(*b),
+            ValueKind::Integer(n) => ConstValue::Integer(*n),
+            ValueKind::Float(f) => ConstValue::Float(*f),
+            ValueKind::String(s) => ConstValue::String(s.try_clone()?),
+            ValueKind::Bytes(b) => ConstValue::Bytes(b.try_clone()?),
+            ValueKind::Option(option) => ConstValue::Option(match option {
+                Some(some) => Some(Box::try_new(self.const_value(spanned, some)?)?),
+                None => None,
+            }),
+            ValueKind::Result(result) => ConstValue::Result(match result {
+                Ok(ok) => Ok(Box::try_new(self.const_value(spanned, ok)?)?),
+                Err(err) => Err(Box::try_new(self.const_value(spanned, err)?)?),
+            }),
+            ValueKind::Vec(vec) => {
+                let mut const_vec = Vec::try_with_capacity(vec.len())?;
+
+                for value in vec.iter() {
+                    const_vec.try_push(self.const_value(spanned, value)?)?;
+                }
+
+                ConstValue::Vec(const_vec)
+            }
+            ValueKind::Tuple(tuple) => {
+                let mut const_tuple = Vec::try_with_capacity(tuple.len())?;
+
+                for value in tuple.iter() {
+                    const_tuple.try_push(self.const_value(spanned, value)?)?;
+                }
+
+                ConstValue::Tuple(const_tuple.try_into_boxed_slice()?)
+            }
+            ValueKind::Object(object) => {
+                let mut const_object = HashMap::try_with_capacity(object.len())?;
+
+                for (key, value) in object.iter() {
+                    const_object.try_insert(key.try_clone()?, self.const_value(spanned, value)?)?;
+                }
+
+                ConstValue::Object(const_object)
+            }
+            ValueKind::Any(any) => {
+                let handler = self.q.context.lookup_const_kind(any.type_hash());
+
+                match handler.and_then(|handler| handler(any)) {
+                    Some(extension) => ConstValue::Extension(extension),
+                    None => {
+                        return Err(compile::Error::new(
+                            spanned,
+                            VmError::from(VmErrorKind::ConstNotSupported {
+                                actual: any.type_info(),
+                            }),
+                        ))
+                    }
+                }
+            }
+            actual => {
+                return Err(compile::Error::new(
+                    spanned,
+                    VmError::from(VmErrorKind::ConstNotSupported {
+                        actual: actual.type_info(),
+                    }),
+                ))
+            }
+        })
+    }
+
+    /// Evaluate to an ir value without converting it into a [`ConstValue`].
+    ///
+    /// The returned value is a shared handle, so any interior values it
+    /// references might alias variables in the scopes of the interpreter. It
+    /// should therefore not be held on to past the next call to [`reset`].
+    ///
+    /// [`reset`]: Self::reset
     pub(crate) fn eval_value(&mut self, ir: &ir::Ir, used: Used) -> compile::Result<Value> {
         match ir::eval_ir(ir, self, used) {
             Ok(ir_value) => Ok(ir_value),
             Err(outcome) => match outcome {
-                ir::EvalOutcome::Error(error) => Err(error),
+                ir::EvalOutcome::Error(error) => {
+                    self.visit_budget_exceeded(&error)?;
+                    Err(error)
+                }
                 ir::EvalOutcome::NotConst(span) => {
                     Err(compile::Error::new(span, IrErrorKind::NotConst))
                 }
                 ir::EvalOutcome::Break(span, _, _) => {
                     Err(compile::Error::new(span, IrErrorKind::BreakOutsideOfLoop))
                 }
+                ir::EvalOutcome::Continue(span, _) => Err(compile::Error::new(
+                    span,
+                    IrErrorKind::ContinueOutsideOfLoop,
+                )),
             },
         }
     }
 
+    /// Pass any explained steps to the visitor.
+    fn visit_explained(&mut self, span: &dyn Spanned) -> compile::Result<()> {
+        let Some(explain) = self.explain.take() else {
+            return Ok(());
+        };
+
+        self.q
+            .visitor
+            .visit_const_explained(self.source_id, self.q.pool.item(self.item), &explain.steps)
+            .with_span(span)?;
+
+        Ok(())
+    }
+
+    /// Notify the visitor if evaluation failed because the budget was
+    /// exceeded.
+    fn visit_budget_exceeded(&mut self, error: &compile::Error) -> compile::Result<()> {
+        if !self.budget.is_exceeded() {
+            return Ok(());
+        }
+
+        let span = error.span();
+
+        self.q
+            .visitor
+            .visit_budget_exceeded(
+                self.source_id,
+                self.q.pool.item(self.item),
+                span,
+                self.budget.steps(),
+            )
+            .with_span(span)?;
+
+        Ok(())
+    }
+
     /// Resolve the given constant value from the block scope.
     ///
     /// This looks up `const <ident> = <expr>` and evaluates them while caching
@@ -105,8 +290,20 @@ impl Interpreter<'_, '_> {
                 .pool
                 .alloc_item(base.extended(name.try_to_string()?)?)?;
 
-            if let Some(const_value) = self.q.consts.get(item) {
-                return Ok(const_value.as_value().with_span(span)?);
+            self.q.check_const_cycle(span, item)?;
+
+            // NB: constants are materialized into fresh values on every
+            // reference since values don't support copy-on-write, so sharing
+            // them would let mutations leak between uses.
+            if self.q.options.const_eval_no_cache {
+                if let Some(const_value) = self.q.recompute_const(item, used)? {
+                    self.q.insert_const_dependency(self.item, item)?;
+                    return Ok(const_value.as_value().with_span(span)?);
+                }
+            } else if let Some(const_value) = self.q.consts.get(item) {
+                let value = const_value.as_value().with_span(span)?;
+                self.q.insert_const_dependency(self.item, item)?;
+                return Ok(value);
             }
 
             if let Some(meta) = self.q.query_meta(span, item, used)? {
@@ -119,7 +316,12 @@ impl Interpreter<'_, '_> {
                             ));
                         };
 
-                        return Ok(const_value.as_value().with_span(span)?);
+                        let value = const_value.as_value().with_span(span)?;
+
+                        self.q
+                            .insert_const_dependency(self.item, meta.item_meta.item)?;
+
+                        return Ok(value);
                     }
                     _ => {
                         return Err(compile::Error::new(
@@ -165,6 +367,13 @@ impl Interpreter<'_, '_> {
         S: Copy + Spanned,
     {
         let span = Spanned::span(&spanned);
+
+        // NB: a constant function which is still being lowered can only be
+        // called if lowering it evaluates a constant which calls it back.
+        if let Some(item) = self.q.pending_const_fn_item(id) {
+            return Err(self.q.const_cycle_error(&span, item)?);
+        }
+
         let const_fn = self.q.const_fn_for(id).with_span(span)?;
 
         if const_fn.ir_fn.args.len() != args.len() {
@@ -177,25 +386,206 @@ impl Interpreter<'_, '_> {
             ));
         }
 
+        let item = const_fn.item_meta.item;
+
+        let key = self.const_fn_key(span, item, &args)?;
+
+        if let Some((key, const_args)) = &key {
+            if let Some(value) = self.q.get_const_fn_value(*key, const_args) {
+                return Ok(value.as_value().with_span(span)?);
+            }
+        }
+
+        let native_calls = self.q.inner.const_native_calls;
         let guard = self.scopes.isolate()?;
 
-        for (name, value) in const_fn.ir_fn.args.iter().zip(args) {
-            self.scopes.decl(name, value).with_span(span)?;
+        for (name, value) in const_fn.ir_fn.args.iter().zip(args.iter()) {
+            self.scopes.decl(name, value.clone()).with_span(span)?;
         }
 
-        let value = self.eval_value(&const_fn.ir_fn.ir, used)?;
+        // NB: the function is marked so that it shows up in the chain of
+        // constants which are being processed, but calls to it aren't checked
+        // for cycles since it's allowed to recurse.
+        self.budget.enter(span)?;
+        self.q.consts.mark(item)?;
+        let value = self.eval_value(&const_fn.ir_fn.ir, used);
+        self.q.consts.unmark();
+        self.budget.exit();
+
+        let value = value?;
         self.scopes.pop(guard).with_span(span)?;
+
+        // NB: calls which reach native functions or mutate their arguments
+        // aren't pure, so they're not memoized.
+        let Some((key, const_args)) = key else {
+            return Ok(value);
+        };
+
+        if self.q.inner.const_native_calls != native_calls {
+            return Ok(value);
+        }
+
+        match self.const_fn_key(span, item, &args)? {
+            Some((after, after_args))
+                if after == key
+                    && after_args
+                        .iter()
+                        .zip(&const_args)
+                        .all(|(a, b)| a.is_identical(b)) => {}
+            _ => return Ok(value),
+        }
+
+        if let Ok(const_value) = self.const_value(span, &value) {
+            self.q.insert_const_fn_value(key, const_args, const_value)?;
+        }
+
         Ok(value)
     }
+
+    /// Compute the key used to memoize a call to the constant function at the
+    /// given item, which is based on the exact content of its arguments, along
+    /// with the arguments converted into constant values.
+    ///
+    /// Returns `None` if the call can't be memoized, either because an
+    /// argument can't be converted into a constant value or because
+    /// memoization would hide something which is being observed, like the
+    /// steps taken when explaining a constant.
+    fn const_fn_key(
+        &self,
+        span: Span,
+        item: ItemId,
+        args: &[Value],
+    ) -> alloc::Result<Option<(Hash, Vec<ConstValue>)>> {
+        let options = self.q.options;
+
+        if self.explain.is_some() || options.const_eval_no_cache || options.const_graph {
+            return Ok(None);
+        }
+
+        let mut key = ParametersBuilder::new();
+        key.add(self.q.pool.item_type_hash(item));
+
+        let mut const_args = Vec::try_with_capacity(args.len())?;
+
+        for arg in args {
+            let Ok(arg) = self.const_value(span, arg) else {
+                return Ok(None);
+            };
+
+            key.add(arg.exact_hash());
+            const_args.try_push(arg)?;
+        }
+
+        Ok(Some((key.finish(), const_args)))
+    }
+
+    /// Call a native function which is safe to call in constant contexts.
+    ///
+    /// This fails if pure constants are required.
+    ///
+    /// Results are cached by the hash of the function and its arguments, where
+    /// floats are distinguished by their bit pattern.
+    pub(crate) fn call_native<S>(
+        &mut self,
+        spanned: S,
+        hash: Hash,
+        args: Vec<Value>,
+    ) -> compile::Result<Value>
+    where
+        S: Copy + Spanned,
+    {
+        let span = Spanned::span(&spanned);
+
+        if self.q.options.require_pure_consts {
+            return Err(self.q.impure_const_error(&span, hash)?);
+        }
+
+        let Some(handler) = self.q.context.lookup_const_function(hash) else {
+            return Err(compile::Error::new(
+                span,
+                IrErrorKind::Unsupported {
+                    reason: "Native function is not marked as safe to call in constant contexts",
+                },
+            ));
+        };
+
+        let mut key = ParametersBuilder::new();
+        key.add(hash);
+
+        let mut const_args = Vec::try_with_capacity(args.len())?;
+
+        for arg in args {
+            let arg = self.const_value(span, &arg)?;
+            key.add(arg.exact_hash());
+            const_args.try_push(arg)?;
+        }
+
+        let key = key.finish();
+        self.q.inner.const_native_calls = self.q.inner.const_native_calls.wrapping_add(1);
+
+        if let Some(value) = self.q.get_native_const_value(key, &const_args) {
+            return Ok(value.as_value().with_span(span)?);
+        }
+
+        let mut stack = Stack::new();
+
+        for arg in &const_args {
+            stack
+                .push(arg.as_value().with_span(span)?)
+                .with_span(span)?;
+        }
+
+        handler(&mut stack, const_args.len())
+            .into_result()
+            .with_span(span)?;
+
+        let value = stack.pop().map_err(VmError::from).with_span(span)?;
+
+        let value = self.const_value(span, &value)?;
+        let output = value.as_value().with_span(span)?;
+        self.q.insert_native_const_value(key, const_args, value)?;
+        Ok(output)
+    }
 }
 
 impl ir::Scopes {
-    /// Get the given target as mut.
-    pub(crate) fn get_target(&mut self, ir_target: &ir::IrTarget) -> compile::Result<Value> {
+    /// Get the value of the given target.
+    ///
+    /// Values are shared handles, so this doesn't copy the underlying data.
+    /// Mutating the returned value through [`Value::borrow_kind_mut`] is
+    /// visible through the target, which is what [`Scopes::set_target`] and
+    /// [`Scopes::mut_target`] rely on.
+    ///
+    /// At most `limit` levels of nested targets are resolved, after which an
+    /// error is raised instead of recursing any further.
+    ///
+    /// [`Scopes::set_target`]: ir::Scopes::set_target
+    /// [`Scopes::mut_target`]: ir::Scopes::mut_target
+    pub(crate) fn get_target(
+        &mut self,
+        ir_target: &ir::IrTarget,
+        limit: usize,
+    ) -> compile::Result<Value> {
+        self.get_target_at(ir_target, 0, limit)
+    }
+
+    fn get_target_at(
+        &mut self,
+        ir_target: &ir::IrTarget,
+        depth: usize,
+        limit: usize,
+    ) -> compile::Result<Value> {
+        if depth >= limit {
+            return Err(compile::Error::new(
+                ir_target,
+                IrErrorKind::RecursionLimit { limit },
+            ));
+        }
+
         match &ir_target.kind {
             ir::IrTargetKind::Name(name) => Ok(self.get_name(name, ir_target)?.try_clone()?),
             ir::IrTargetKind::Field(ir_target, field) => {
-                let value = self.get_target(ir_target)?;
+                let value = self.get_target_at(ir_target, depth + 1, limit)?;
 
                 match &*value.borrow_kind_ref().with_span(ir_target)? {
                     ValueKind::Object(object) => {
@@ -218,16 +608,20 @@ impl ir::Scopes {
                 ))
             }
             ir::IrTargetKind::Index(target, index) => {
-                let value = self.get_target(target)?;
+                let value = self.get_target_at(target, depth + 1, limit)?;
 
                 match &*value.borrow_kind_ref().with_span(ir_target)? {
                     ValueKind::Vec(vec) => {
-                        if let Some(value) = vec.get(*index).try_cloned()? {
+                        let value = vec_offset(*index, vec.len()).and_then(|n| vec.get(n));
+
+                        if let Some(value) = value.try_cloned()? {
                             return Ok(value);
                         }
                     }
                     ValueKind::Tuple(tuple) => {
-                        if let Some(value) = tuple.get(*index).try_cloned()? {
+                        let value = tuple_offset(*index).and_then(|n| tuple.get(n));
+
+                        if let Some(value) = value.try_cloned()? {
                             return Ok(value);
                         }
                     }
@@ -247,10 +641,15 @@ impl ir::Scopes {
     }
 
     /// Update the given target with the given constant value.
+    ///
+    /// See [`Scopes::get_target`] for how `limit` is used.
+    ///
+    /// [`Scopes::get_target`]: ir::Scopes::get_target
     pub(crate) fn set_target(
         &mut self,
         ir_target: &ir::IrTarget,
         value: Value,
+        limit: usize,
     ) -> compile::Result<()> {
         match &ir_target.kind {
             ir::IrTargetKind::Name(name) => {
@@ -258,7 +657,7 @@ impl ir::Scopes {
                 Ok(())
             }
             ir::IrTargetKind::Field(target, field) => {
-                let current = self.get_target(target)?;
+                let current = self.get_target(target, limit)?;
 
                 match &mut *current.borrow_kind_mut().with_span(ir_target)? {
                     ValueKind::Object(object) => {
@@ -275,17 +674,19 @@ impl ir::Scopes {
                 Ok(())
             }
             ir::IrTargetKind::Index(target, index) => {
-                let current = self.get_target(target)?;
+                let current = self.get_target(target, limit)?;
 
                 match &mut *current.borrow_kind_mut().with_span(ir_target)? {
                     ValueKind::Vec(vec) => {
-                        if let Some(current) = vec.get_mut(*index) {
+                        let offset = vec_offset(*index, vec.len());
+
+                        if let Some(current) = offset.and_then(|n| vec.get_mut(n)) {
                             *current = value;
                             return Ok(());
                         }
                     }
                     ValueKind::Tuple(tuple) => {
-                        if let Some(current) = tuple.get_mut(*index) {
+                        if let Some(current) = tuple_offset(*index).and_then(|n| tuple.get_mut(n)) {
                             *current = value;
                             return Ok(());
                         }
@@ -297,16 +698,24 @@ impl ir::Scopes {
                     }
                 };
 
-                Err(compile::Error::msg(ir_target, "missing index"))
+                Err(compile::Error::new(
+                    ir_target,
+                    IrErrorKind::MissingIndex { index: *index },
+                ))
             }
         }
     }
 
     /// Mutate the given target with the given constant value.
+    ///
+    /// See [`Scopes::get_target`] for how `limit` is used.
+    ///
+    /// [`Scopes::get_target`]: ir::Scopes::get_target
     pub(crate) fn mut_target(
         &mut self,
         ir_target: &ir::IrTarget,
         op: impl FnOnce(&mut Value) -> compile::Result<()>,
+        limit: usize,
     ) -> compile::Result<()> {
         match &ir_target.kind {
             ir::IrTargetKind::Name(name) => {
@@ -314,7 +723,7 @@ impl ir::Scopes {
                 op(value)
             }
             ir::IrTargetKind::Field(target, field) => {
-                let current = self.get_target(target)?;
+                let current = self.get_target(target, limit)?;
                 let mut kind = current.borrow_kind_mut().with_span(ir_target)?;
 
                 match &mut *kind {
@@ -336,12 +745,14 @@ impl ir::Scopes {
                 }
             }
             ir::IrTargetKind::Index(target, index) => {
-                let current = self.get_target(target)?;
+                let current = self.get_target(target, limit)?;
                 let mut kind = current.borrow_kind_mut().with_span(ir_target)?;
 
                 match &mut *kind {
                     ValueKind::Vec(vec) => {
-                        let value = vec.get_mut(*index).ok_or_else(|| {
+                        let offset = vec_offset(*index, vec.len());
+
+                        let value = offset.and_then(|n| vec.get_mut(n)).ok_or_else(|| {
                             compile::Error::new(
                                 ir_target,
                                 IrErrorKind::MissingIndex { index: *index },
@@ -351,12 +762,14 @@ impl ir::Scopes {
                         op(value)
                     }
                     ValueKind::Tuple(tuple) => {
-                        let value = tuple.get_mut(*index).ok_or_else(|| {
-                            compile::Error::new(
-                                ir_target,
-                                IrErrorKind::MissingIndex { index: *index },
-                            )
-                        })?;
+                        let value = tuple_offset(*index)
+                            .and_then(|n| tuple.get_mut(n))
+                            .ok_or_else(|| {
+                                compile::Error::new(
+                                    ir_target,
+                                    IrErrorKind::MissingIndex { index: *index },
+                                )
+                            })?;
 
                         op(value)
                     }
@@ -369,15 +782,123 @@ impl ir::Scopes {
     }
 }
 
-/// A budget dictating the number of evaluations the compiler is allowed to do.
+/// Resolve an index into a vector of the given length, where negative indexes
+/// count from the end.
+fn vec_offset(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        let n = usize::try_from(index.unsigned_abs()).ok()?;
+        len.checked_sub(n)
+    } else {
+        usize::try_from(index).ok()
+    }
+}
+
+/// Resolve an index into a tuple, which must not be negative.
+fn tuple_offset(index: i64) -> Option<usize> {
+    usize::try_from(index).ok()
+}
+
+/// Steps recorded while folding a constant.
+#[derive(Default)]
+pub(crate) struct Explain {
+    /// Steps which have completed.
+    steps: Vec<FoldStep>,
+    /// The indexes of the completed inputs to each step which is currently
+    /// being evaluated.
+    stack: Vec<Vec<usize>>,
+}
+
+impl Explain {
+    /// Start evaluating a step.
+    pub(crate) fn enter(&mut self) -> alloc::Result<()> {
+        self.stack.try_push(Vec::new())
+    }
+
+    /// Finish evaluating a step which produced the given output.
+    pub(crate) fn exit(
+        &mut self,
+        span: Span,
+        kind: &'static str,
+        output: Option<ConstValue>,
+    ) -> alloc::Result<()> {
+        let inputs = self.stack.pop().unwrap_or_default();
+        let index = self.steps.len();
+
+        self.steps.try_push(FoldStep {
+            span,
+            kind,
+            inputs,
+            output,
+        })?;
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.try_push(index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Abandon a step which didn't produce a value, like a `break` or an
+    /// error, passing its inputs on to the enclosing step.
+    pub(crate) fn abandon(&mut self) -> alloc::Result<()> {
+        let inputs = self.stack.pop().unwrap_or_default();
+
+        if let Some(parent) = self.stack.last_mut() {
+            for index in inputs {
+                parent.try_push(index)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A budget dictating the number of evaluations the compiler is allowed to do,
+/// and how deeply calls to constant functions are allowed to nest.
 pub(crate) struct Budget {
     budget: usize,
+    limit: usize,
+    exceeded: bool,
+    depth: usize,
+    max_depth: usize,
 }
 
 impl Budget {
-    /// Construct a new constant evaluation budget with the given constraint.
-    pub(crate) fn new(budget: usize) -> Self {
-        Self { budget }
+    /// Construct a new constant evaluation budget with the given constraints.
+    pub(crate) fn new(budget: usize, max_depth: usize) -> Self {
+        Self {
+            budget,
+            limit: budget,
+            exceeded: false,
+            depth: 0,
+            max_depth,
+        }
+    }
+
+    /// Enter a call to a constant function. Errors if calls are nested more
+    /// deeply than allowed.
+    ///
+    /// Every successful call must be paired with a call to [`Budget::exit`].
+    pub(crate) fn enter<S>(&mut self, spanned: S) -> compile::Result<()>
+    where
+        S: Spanned,
+    {
+        if self.depth >= self.max_depth {
+            return Err(compile::Error::new(
+                spanned,
+                IrErrorKind::RecursionLimit {
+                    limit: self.max_depth,
+                },
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Exit a call to a constant function.
+    pub(crate) fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
     }
 
     /// Take an item from the budget. Errors if the budget is exceeded.
@@ -385,11 +906,40 @@ impl Budget {
     where
         S: Spanned,
     {
-        if self.budget == 0 {
-            return Err(compile::Error::new(spanned, IrErrorKind::BudgetExceeded));
-        }
+        self.take_many(spanned, 1)
+    }
+
+    /// Take `count` items from the budget. Errors if the budget is exceeded.
+    pub(crate) fn take_many<S>(&mut self, spanned: S, count: usize) -> compile::Result<()>
+    where
+        S: Spanned,
+    {
+        let Some(budget) = self.budget.checked_sub(count) else {
+            self.exceeded = true;
+            return Err(compile::Error::new(
+                spanned,
+                IrErrorKind::BudgetExceeded { limit: self.limit },
+            ));
+        };
 
-        self.budget -= 1;
+        self.budget = budget;
         Ok(())
     }
+
+    /// Test if the budget has been exceeded.
+    pub(crate) fn is_exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    /// The number of steps which have been taken from the budget.
+    pub(crate) fn steps(&self) -> usize {
+        self.limit - self.budget
+    }
+
+    /// Restore the budget to its original limit.
+    pub(crate) fn reset(&mut self) {
+        self.budget = self.limit;
+        self.exceeded = false;
+        self.depth = 0;
+    }
 }
@@ -1,9 +1,9 @@
 use crate::alloc::prelude::*;
-use crate::alloc::{self, try_vec, Box, HashMap, Vec};
+use crate::alloc::{self, try_vec, Box, HashMap, String, Vec};
 use crate::ast::Spanned;
 use crate::compile::{self, ErrorKind};
 use crate::hir;
-use crate::runtime::Value;
+use crate::runtime::{ConstValue, Value};
 
 /// Error indicating that a local variable is missing.
 pub(crate) struct MissingLocal(pub(crate) Box<str>);
@@ -41,6 +41,29 @@ impl Scopes {
         Ok(())
     }
 
+    /// Declare the given constant values as locals in the scope.
+    pub(crate) fn decl_values(
+        &mut self,
+        values: &HashMap<String, ConstValue>,
+    ) -> Result<(), ErrorKind> {
+        for (name, value) in values {
+            let name = hir::OwnedName::Str(name.try_clone()?);
+            self.decl(&name, value.as_value()?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reset to a single empty scope, retaining allocated capacity.
+    pub(crate) fn reset(&mut self) {
+        self.scopes.truncate(1);
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.kind = ScopeKind::None;
+            scope.locals.clear();
+        }
+    }
+
     /// Try to get the value out from the scopes.
     pub(crate) fn try_get(&self, name: &hir::OwnedName) -> Option<&Value> {
         for scope in self.scopes.iter().rev() {
@@ -1,6 +1,7 @@
 use core::fmt;
 
 use ::rust_alloc::boxed::Box;
+use ::rust_alloc::vec::Vec;
 
 /// Error raised when trying to parse an invalid option.
 #[derive(Debug, Clone)]
@@ -39,6 +40,32 @@ pub struct Options {
     pub(crate) v2: bool,
     /// Build sources as function bodies.
     pub(crate) function_body: bool,
+    /// Record constants which can't be evaluated as warnings.
+    pub(crate) const_coverage: bool,
+    /// Emit the time taken to evaluate constants to tracing.
+    pub(crate) time_const_eval: bool,
+    /// Allow hashes to be computed in constant contexts.
+    pub(crate) const_hash: bool,
+    /// Record the steps taken to fold each constant.
+    pub(crate) explain_const: bool,
+    /// Record which constants each constant depends on.
+    pub(crate) const_graph: bool,
+    /// Evaluate constants again every time they're referenced instead of
+    /// using cached values.
+    pub(crate) const_eval_no_cache: bool,
+    /// Reject constants which call native functions.
+    pub(crate) require_pure_consts: bool,
+    /// Flags which are enabled for `#[when(..)]` attributes.
+    pub(crate) when_flags: Vec<Box<str>>,
+    /// The number of steps each constant evaluation is allowed to take.
+    pub(crate) const_eval_budget: usize,
+    /// The maximum length of collections constructed in constant contexts.
+    pub(crate) const_max_collection_len: usize,
+    /// The maximum depth of recursion when evaluating constants.
+    pub(crate) const_recursion_limit: usize,
+    /// The maximum number of bytes allocated by collections constructed in
+    /// constant contexts across a whole compilation.
+    pub(crate) const_total_alloc_limit: usize,
 }
 
 impl Options {
@@ -76,6 +103,72 @@ impl Options {
             Some("function-body") => {
                 self.function_body = it.next() == Some("true");
             }
+            Some("const-coverage") => {
+                self.const_coverage = it.next() == Some("true");
+            }
+            Some("time-const-eval") => {
+                self.time_const_eval = it.next() == Some("true");
+            }
+            Some("const-hash") => {
+                self.const_hash = it.next() == Some("true");
+            }
+            Some("explain-const") => {
+                self.explain_const = it.next() == Some("true");
+            }
+            Some("const-graph") => {
+                self.const_graph = it.next() == Some("true");
+            }
+            Some("const-eval-no-cache") => {
+                self.const_eval_no_cache = it.next() == Some("true");
+            }
+            Some("require-pure-consts") => {
+                self.require_pure_consts = it.next() == Some("true");
+            }
+            Some("const-eval-budget") => {
+                let Some(budget) = it.next().and_then(|budget| budget.parse().ok()) else {
+                    return Err(ParseOptionError {
+                        option: option.into(),
+                    });
+                };
+
+                self.const_eval_budget = budget;
+            }
+            Some("const-max-collection-len") => {
+                let Some(len) = it.next().and_then(|len| len.parse().ok()) else {
+                    return Err(ParseOptionError {
+                        option: option.into(),
+                    });
+                };
+
+                self.const_max_collection_len = len;
+            }
+            Some("const-recursion-limit") => {
+                let Some(limit) = it.next().and_then(|limit| limit.parse().ok()) else {
+                    return Err(ParseOptionError {
+                        option: option.into(),
+                    });
+                };
+
+                self.const_recursion_limit = limit;
+            }
+            Some("const-total-alloc-limit") => {
+                let Some(bytes) = it.next().and_then(|bytes| bytes.parse().ok()) else {
+                    return Err(ParseOptionError {
+                        option: option.into(),
+                    });
+                };
+
+                self.const_total_alloc_limit = bytes;
+            }
+            Some("when") => {
+                let Some(flag) = it.next() else {
+                    return Err(ParseOptionError {
+                        option: option.into(),
+                    });
+                };
+
+                self.when_flag(flag);
+            }
             _ => {
                 return Err(ParseOptionError {
                     option: option.into(),
@@ -117,6 +210,154 @@ impl Options {
     pub fn memoize_instance_fn(&mut self, enabled: bool) {
         self.memoize_instance_fn = enabled;
     }
+
+    /// Set if constants which can't be evaluated at compile time should be
+    /// recorded as warnings instead of failing compilation. Other errors, like
+    /// panics or exceeded limits, still fail compilation. This is useful for
+    /// reporting how much of a project can be evaluated at compile time.
+    /// Defaults to `false`.
+    pub fn const_coverage(&mut self, enabled: bool) {
+        self.const_coverage = enabled;
+    }
+
+    /// Set if the evaluation of each constant should be wrapped in a `tracing`
+    /// span, which records the time it took and the number of steps it used.
+    /// Defaults to `false`.
+    pub fn time_const_eval(&mut self, enabled: bool) {
+        self.time_const_eval = enabled;
+    }
+
+    /// Set if `hash` can be called in constant contexts.
+    ///
+    /// Hashes are seeded randomly for every process, so a hash computed while
+    /// compiling only agrees with the ones computed at runtime if the unit is
+    /// run by the same process which compiled it. Only enable this if that's
+    /// the case, and the unit isn't serialized or cached. Defaults to `false`.
+    pub fn const_hash(&mut self, enabled: bool) {
+        self.const_hash = enabled;
+    }
+
+    /// Set if the steps taken to fold each constant should be recorded and
+    /// passed to [`CompileVisitor::visit_const_explained`]. This has a cost,
+    /// since every intermediate value is converted into a constant value.
+    /// Defaults to `false`.
+    ///
+    /// [`CompileVisitor::visit_const_explained`]: crate::compile::CompileVisitor::visit_const_explained
+    pub fn explain_const(&mut self, enabled: bool) {
+        self.explain_const = enabled;
+    }
+
+    /// Set if a graph of the constants and constant functions which each
+    /// constant depends on should be recorded and passed to
+    /// [`CompileVisitor::visit_const_graph`] once compilation has finished.
+    /// Defaults to `false`.
+    ///
+    /// [`CompileVisitor::visit_const_graph`]: crate::compile::CompileVisitor::visit_const_graph
+    pub fn const_graph(&mut self, enabled: bool) {
+        self.const_graph = enabled;
+    }
+
+    /// Set if constants should be evaluated again every time they're
+    /// referenced, instead of reusing the value from when they were first
+    /// evaluated. Cycles are still detected.
+    ///
+    /// This is only intended as an aid when debugging constant evaluation,
+    /// since it can make compilation much slower. Defaults to `false`.
+    pub fn const_eval_no_cache(&mut self, enabled: bool) {
+        self.const_eval_no_cache = enabled;
+    }
+
+    /// Set if constants have to be computed purely in Rune, which means that
+    /// evaluating a constant fails if it calls a native function, even one
+    /// which is marked as safe to call in constant contexts. The error
+    /// includes the chain of constants and constant functions which led to
+    /// the call.
+    ///
+    /// This isn't affected by [`Options::const_coverage`], so an impure
+    /// constant always fails compilation. Defaults to `false`.
+    pub fn require_pure_consts(&mut self, enabled: bool) {
+        self.require_pure_consts = enabled;
+    }
+
+    /// Set the number of steps which evaluating a single constant, or a call
+    /// to a constant function from a macro, is allowed to take.
+    ///
+    /// Exceeding the budget results in a compile error which reports the
+    /// limit. Defaults to `1000000`.
+    pub fn const_eval_budget(&mut self, budget: usize) {
+        self.const_eval_budget = budget;
+    }
+
+    /// Set the maximum length of vectors, strings and objects which can be
+    /// constructed when evaluating constants. Strings are measured in bytes.
+    ///
+    /// Constructing a larger collection results in a compile error, which
+    /// prevents constants from allocating unbounded amounts of memory within
+    /// the evaluation budget. Defaults to `1048576`.
+    pub fn const_max_collection_len(&mut self, len: usize) {
+        self.const_max_collection_len = len;
+    }
+
+    /// Set the maximum depth of recursion when evaluating constants, such as
+    /// how deeply nested fields and indexes like `a.b[0].c` can be resolved
+    /// and how deeply calls to constant functions can be nested.
+    ///
+    /// Exceeding the limit results in a compile error instead of overflowing
+    /// the stack of the compiler. Defaults to `128`.
+    pub fn const_recursion_limit(&mut self, limit: usize) {
+        self.const_recursion_limit = limit;
+    }
+
+    /// Set the maximum number of bytes which collections constructed when
+    /// evaluating constants can allocate in total across a compilation.
+    ///
+    /// Unlike [`Options::const_max_collection_len`] this limits the sum of
+    /// every collection constructed, which protects against many constants
+    /// collectively exhausting memory. Exceeding the limit results in a compile
+    /// error. Defaults to `268435456`.
+    pub fn const_total_alloc_limit(&mut self, bytes: usize) {
+        self.const_total_alloc_limit = bytes;
+    }
+
+    /// Enable a flag which is tested by `#[when(..)]` attributes. Constants
+    /// marked with a flag which isn't enabled are skipped entirely.
+    ///
+    /// This can also be set with the `when=<flag>` option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Options, Sources};
+    ///
+    /// let mut options = Options::default();
+    /// options.when_flag("gpu");
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(rune::Source::memory(r#"
+    /// #[when("gpu")]
+    /// const BACKEND = "vulkan";
+    /// #[when("cpu")]
+    /// const BACKEND = "software";
+    /// "#)?)?;
+    ///
+    /// let consts = rune::prepare(&mut sources)
+    ///     .with_options(&options)
+    ///     .build_consts()?;
+    ///
+    /// assert_eq!(consts.len(), 1);
+    /// assert_eq!(consts[0].0.to_string(), "BACKEND");
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn when_flag(&mut self, flag: &str) {
+        if !self.is_when_flag(flag) {
+            self.when_flags.push(flag.into());
+        }
+    }
+
+    /// Test if the given `#[when(..)]` flag is enabled.
+    pub(crate) fn is_when_flag(&self, flag: &str) -> bool {
+        self.when_flags.iter().any(|f| f.as_ref() == flag)
+    }
 }
 
 impl Default for Options {
@@ -130,6 +371,18 @@ impl Default for Options {
             cfg_test: false,
             v2: false,
             function_body: false,
+            const_coverage: false,
+            time_const_eval: false,
+            const_hash: false,
+            explain_const: false,
+            const_graph: false,
+            const_eval_no_cache: false,
+            require_pure_consts: false,
+            when_flags: Vec::new(),
+            const_eval_budget: 1_000_000,
+            const_max_collection_len: 1 << 20,
+            const_recursion_limit: 128,
+            const_total_alloc_limit: 1 << 28,
         }
     }
 }
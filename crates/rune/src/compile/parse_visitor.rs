@@ -0,0 +1,33 @@
+use crate::ast::{self, Span};
+use crate::compile::MetaError;
+use crate::SourceId;
+
+/// A visitor that will be called for every top-level item as it is parsed.
+///
+/// Unlike a [CompileVisitor][crate::compile::CompileVisitor], this is called
+/// before anything about the item has been compiled, so it sees the raw shape
+/// of the syntax tree. Macro calls are for example visited before they are
+/// expanded.
+pub trait ParseVisitor {
+    /// Visit a top-level item which has been parsed from the given source.
+    fn visit_item(
+        &mut self,
+        _source_id: SourceId,
+        _item: &ast::Item,
+        _span: Span,
+    ) -> Result<(), MetaError> {
+        Ok(())
+    }
+}
+
+/// A [ParseVisitor] which does nothing.
+pub(crate) struct NoopParseVisitor(());
+
+impl NoopParseVisitor {
+    /// Construct a new noop parse visitor.
+    pub(crate) const fn new() -> Self {
+        Self(())
+    }
+}
+
+impl ParseVisitor for NoopParseVisitor {}
@@ -1,6 +1,9 @@
+#[cfg(feature = "std")]
+use crate::alloc::path::Component;
 use crate::alloc::path::Path;
 #[cfg(feature = "std")]
 use crate::alloc::prelude::*;
+use crate::alloc::Vec;
 use crate::ast::Spanned;
 use crate::compile::{self, Item};
 #[cfg(feature = "std")]
@@ -11,6 +14,19 @@ use crate::Source;
 pub trait SourceLoader {
     /// Load the given URL.
     fn load(&mut self, root: &Path, item: &Item, span: &dyn Spanned) -> compile::Result<Source>;
+
+    /// Load the contents of a file included through `include_str` or
+    /// `include_bytes` in a constant context, where `path` is relative to
+    /// the source file at `root`.
+    ///
+    /// Including files is denied unless this is implemented.
+    fn include(&mut self, root: &Path, path: &str, span: &dyn Spanned) -> compile::Result<Vec<u8>> {
+        let _ = (root, path);
+        Err(compile::Error::msg(
+            span,
+            "Including files is not supported",
+        ))
+    }
 }
 
 /// A source loader which does not support loading anything and will error.
@@ -93,4 +109,66 @@ cfg_std! {
             }
         }
     }
+
+    /// A filesystem-based source loader which also permits including files
+    /// through `include_str` and `include_bytes` in constant contexts.
+    ///
+    /// Included paths are resolved relative to the directory of the including
+    /// source, and must be relative paths which don't contain any `..` or root
+    /// components.
+    #[derive(Default)]
+    #[non_exhaustive]
+    pub struct FileIncludeSourceLoader {
+        base: FileSourceLoader,
+    }
+
+    impl FileIncludeSourceLoader {
+        /// Construct a new filesystem-based source loader which permits
+        /// includes.
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    impl SourceLoader for FileIncludeSourceLoader {
+        fn load(&mut self, root: &Path, item: &Item, span: &dyn Spanned) -> compile::Result<Source> {
+            self.base.load(root, item, span)
+        }
+
+        fn include(&mut self, root: &Path, path: &str, span: &dyn Spanned) -> compile::Result<Vec<u8>> {
+            let relative = Path::new(path);
+
+            let is_contained = relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(..) | Component::CurDir));
+
+            if relative.is_absolute() || !is_contained {
+                return Err(compile::Error::msg(
+                    span,
+                    "Included paths must be relative and can't contain `..` or root components",
+                ));
+            }
+
+            let mut base = root.try_to_owned()?;
+
+            if !base.pop() {
+                return Err(compile::Error::new(
+                    span,
+                    ErrorKind::UnsupportedModuleRoot {
+                        root: root.try_to_owned()?,
+                    },
+                ));
+            }
+
+            let path = base.join(relative);
+
+            match std::fs::read(&path) {
+                Ok(bytes) => Ok(Vec::try_from(bytes)?),
+                Err(error) => Err(compile::Error::new(
+                    span,
+                    ErrorKind::IncludeError { path, error },
+                )),
+            }
+        }
+    }
 }
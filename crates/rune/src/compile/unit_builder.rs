@@ -612,10 +612,16 @@ impl UnitBuilder {
                     ));
                 };
 
+                // Values of host types can only be used in constant contexts,
+                // and can't be stored in the unit.
+                if const_value.has_extension() {
+                    return Ok(());
+                }
+
                 let value = const_value.try_clone().with_span(span)?;
 
                 self.constants
-                    .try_insert(meta.hash, value)
+                    .try_insert(query.const_hash(meta.hash), value)
                     .with_span(span)?;
             }
             meta::Kind::Macro { .. } => (),
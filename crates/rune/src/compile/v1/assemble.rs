@@ -2,7 +2,7 @@ use core::mem::{replace, take};
 
 use crate as rune;
 use crate::alloc::prelude::*;
-use crate::alloc::{try_format, Vec};
+use crate::alloc::{try_format, String, Vec};
 use crate::ast::{self, Span, Spanned};
 use crate::compile::ir;
 use crate::compile::v1::{Layer, Loop, Loops, ScopeGuard, Scopes, Var};
@@ -132,8 +132,11 @@ impl<'a, 'hir, 'arena> Ctxt<'a, 'hir, 'arena> {
             ));
         }
 
+        // Warnings raised while compiling the arguments are recorded against
+        // the constant function, since the calling item isn't constant.
         let mut compiler = ir::Ctxt {
             source_id: self.source_id,
+            item: query_const_fn.item_meta.item,
             q: self.q.borrow(),
         };
 
@@ -145,10 +148,15 @@ impl<'a, 'hir, 'arena> Ctxt<'a, 'hir, 'arena> {
         }
 
         let mut interpreter = ir::Interpreter {
-            budget: ir::Budget::new(1_000_000),
+            budget: ir::Budget::new(
+                self.q.options.const_eval_budget,
+                self.q.options.const_recursion_limit,
+            ),
             scopes: ir::Scopes::new()?,
             module: from_module,
             item: from_item,
+            source_id: self.source_id,
+            explain: None,
             q: self.q.borrow(),
         };
 
@@ -159,6 +167,7 @@ impl<'a, 'hir, 'arena> Ctxt<'a, 'hir, 'arena> {
 
         interpreter.module = query_const_fn.item_meta.module;
         interpreter.item = query_const_fn.item_meta.item;
+        interpreter.source_id = query_const_fn.item_meta.location.source_id;
         let value = interpreter.eval_value(&query_const_fn.ir_fn.ir, Used::Used)?;
         Ok(crate::from_value(value).with_span(span)?)
     }
@@ -795,8 +804,16 @@ fn builtin_template<'hir>(
     }
 
     if template.from_literal && expansions == 0 {
+        let mut content = String::try_with_capacity(size_hint)?;
+
+        for hir in template.exprs {
+            if let hir::ExprKind::Lit(hir::Lit::Str(s)) = hir.kind {
+                content.try_push_str(s)?;
+            }
+        }
+
         cx.q.diagnostics
-            .template_without_expansions(cx.source_id, span, cx.context())?;
+            .template_without_expansions(cx.source_id, span, cx.context(), &content)?;
     }
 
     cx.asm.push(
@@ -875,6 +892,15 @@ fn const_<'hir>(
                 )?;
             }
         },
+        ConstValue::Result(result) => {
+            let (value, variant) = match result {
+                Ok(value) => (value, InstVariant::Ok),
+                Err(value) => (value, InstVariant::Err),
+            };
+
+            const_(cx, value, span, Needs::Value)?;
+            cx.asm.push(Inst::Variant { variant }, span)?;
+        }
         ConstValue::Vec(vec) => {
             for value in vec.iter() {
                 const_(cx, value, span, Needs::Value)?;
@@ -903,6 +929,15 @@ fn const_<'hir>(
 
             cx.asm.push(Inst::Object { slot }, span)?;
         }
+        ConstValue::Extension(extension) => {
+            return Err(compile::Error::msg(
+                span,
+                try_format!(
+                    "Constant values of type `{}` can only be used in constant contexts",
+                    extension.type_info()
+                ),
+            ));
+        }
     }
 
     Ok(())
@@ -971,6 +1006,12 @@ fn expr<'hir>(
                 "Path expression is not supported here",
             ))
         }
+        hir::ExprKind::ConstClosure(..) => {
+            return Err(compile::Error::msg(
+                span,
+                "Constant closures are only supported in constant contexts",
+            ))
+        }
     };
 
     Ok(asm)
@@ -1430,6 +1471,12 @@ fn expr_call<'hir>(
             let value = cx.call_const_fn(span, from_module, from_item, &const_fn, hir.args)?;
             const_(cx, &value, span, Needs::Value)?;
         }
+        hir::Call::ConstBuiltin { .. } => {
+            return Err(compile::Error::msg(
+                span,
+                "Built-in constant functions can only be called in constant contexts",
+            ));
+        }
     }
 
     if !needs.value() {
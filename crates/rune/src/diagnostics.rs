@@ -33,7 +33,7 @@
 pub use self::fatal::{FatalDiagnostic, FatalDiagnosticKind};
 mod fatal;
 
-pub use self::warning::{WarningDiagnostic, WarningDiagnosticKind};
+pub use self::warning::{Suggestion, WarningDiagnostic, WarningDiagnosticKind};
 mod warning;
 
 pub use self::runtime_warning::{RuntimeWarningDiagnostic, RuntimeWarningDiagnosticKind};
@@ -42,8 +42,10 @@ mod runtime_warning;
 use ::rust_alloc::boxed::Box;
 use rune_alloc::String;
 
-use crate::alloc::{self, Vec};
+use crate::alloc::prelude::*;
+use crate::alloc::{self, BTreeMap, Vec};
 use crate::ast::{Span, Spanned};
+use crate::compile::{Item, ItemBuf};
 use crate::{Hash, SourceId};
 
 cfg_emit! {
@@ -186,6 +188,109 @@ impl Diagnostics {
         self.diagnostics
     }
 
+    /// Group the warnings which were produced while compiling or evaluating
+    /// constants by the constant item they belong to.
+    ///
+    /// Warnings which aren't associated with a constant are not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Diagnostics, Sources};
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(rune::Source::memory(r#"
+    ///     const FOO = { 1; 2; 3 };
+    ///     const BAR = { 1; 2 };
+    ///     pub fn main() { (FOO, BAR) }
+    /// "#)?)?;
+    ///
+    /// let mut diagnostics = Diagnostics::new();
+    ///
+    /// let _ = rune::prepare(&mut sources)
+    ///     .with_diagnostics(&mut diagnostics)
+    ///     .build()?;
+    ///
+    /// let warnings = diagnostics.const_warnings()?;
+    /// let counts = warnings
+    ///     .iter()
+    ///     .map(|(item, warnings)| (item.to_string(), warnings.len()))
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert_eq!(counts, [("BAR".to_string(), 1), ("FOO".to_string(), 2)]);
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn const_warnings(&self) -> alloc::Result<BTreeMap<&Item, Vec<&WarningDiagnostic>>> {
+        let mut groups = BTreeMap::<_, Vec<_>>::new();
+
+        for diagnostic in &self.diagnostics {
+            let Diagnostic::Warning(warning) = diagnostic else {
+                continue;
+            };
+
+            let Some(item) = warning.const_item() else {
+                continue;
+            };
+
+            groups
+                .entry(item)
+                .or_try_insert_with(Vec::new)?
+                .try_push(warning)?;
+        }
+
+        Ok(groups)
+    }
+
+    /// Write all collected warnings to `out` as a JSON array.
+    ///
+    /// Each warning is an object with the fields `kind`, `source_id`, `span`
+    /// and `message`, where `span` is an object with the byte offsets `start`
+    /// and `end`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Diagnostics, Sources};
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(rune::Source::memory("pub fn main() { `Hello World` }")?)?;
+    ///
+    /// let mut diagnostics = Diagnostics::new();
+    ///
+    /// let _ = rune::prepare(&mut sources)
+    ///     .with_diagnostics(&mut diagnostics)
+    ///     .build()?;
+    ///
+    /// let mut out = Vec::new();
+    /// diagnostics.write_warnings_json(&mut out)?;
+    ///
+    /// let json = String::from_utf8(out)?;
+    /// assert!(json.starts_with(r#"[{"kind":"TemplateWithoutExpansions","#));
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(rune_docsrs, doc(cfg(feature = "json")))]
+    pub fn write_warnings_json<W>(&self, out: W) -> serde_json::Result<()>
+    where
+        W: std::io::Write,
+    {
+        struct Warnings<'a>(&'a [Diagnostic]);
+
+        impl serde::Serialize for Warnings<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_seq(self.0.iter().filter_map(|diagnostic| match diagnostic {
+                    Diagnostic::Warning(warning) => Some(warning),
+                    _ => None,
+                }))
+            }
+        }
+
+        serde_json::to_writer(out, &Warnings(&self.diagnostics))
+    }
+
     /// Report an internal error.
     ///
     /// This should be used for programming invariants of the compiler which are
@@ -241,12 +346,31 @@ impl Diagnostics {
         source_id: SourceId,
         span: &dyn Spanned,
         context: Option<Span>,
+        content: &str,
     ) -> alloc::Result<()> {
+        let mut literal = String::try_with_capacity(content.len() + 2)?;
+        literal.try_push('"')?;
+
+        for c in content.chars() {
+            match c {
+                '"' => literal.try_push_str("\\\"")?,
+                '\\' => literal.try_push_str("\\\\")?,
+                '\n' => literal.try_push_str("\\n")?,
+                '\r' => literal.try_push_str("\\r")?,
+                '\t' => literal.try_push_str("\\t")?,
+                '\0' => literal.try_push_str("\\0")?,
+                c => literal.try_push(c)?,
+            }
+        }
+
+        literal.try_push('"')?;
+
         self.warning(
             source_id,
             WarningDiagnosticKind::TemplateWithoutExpansions {
                 span: span.span(),
                 context,
+                literal,
             },
         )
     }
@@ -302,6 +426,68 @@ impl Diagnostics {
         )
     }
 
+    /// Indicate that a statement in a constant block produces a value which is
+    /// never used.
+    ///
+    /// Like `LEN;` in `const VALUE = { LEN; 1 };`.
+    pub(crate) fn const_statement_unused(
+        &mut self,
+        source_id: SourceId,
+        span: &dyn Spanned,
+        context: Option<Span>,
+        item: &Item,
+    ) -> alloc::Result<()> {
+        self.const_warning(
+            source_id,
+            item,
+            WarningDiagnosticKind::ConstStatementUnused {
+                span: span.span(),
+                context,
+            },
+        )
+    }
+
+    /// Indicate that a constant could not be evaluated at compile time.
+    pub(crate) fn not_const(
+        &mut self,
+        source_id: SourceId,
+        span: &dyn Spanned,
+        context: Option<Span>,
+        reason: String,
+        item: &Item,
+    ) -> alloc::Result<()> {
+        self.const_warning(
+            source_id,
+            item,
+            WarningDiagnosticKind::NotConst {
+                span: span.span(),
+                context,
+                reason,
+            },
+        )
+    }
+
+    /// Indicate that a constant match over an enum doesn't handle all of its
+    /// variants.
+    pub(crate) fn non_exhaustive_const_match(
+        &mut self,
+        source_id: SourceId,
+        span: &dyn Spanned,
+        context: Option<Span>,
+        missing: Vec<String>,
+        item: &Item,
+    ) -> alloc::Result<()> {
+        self.const_warning(
+            source_id,
+            item,
+            WarningDiagnosticKind::NonExhaustiveConstMatch {
+                span: span.span(),
+                missing,
+                context,
+            },
+        )
+    }
+
     /// Add a warning about using a deprecated function
     pub(crate) fn runtime_used_deprecated(&mut self, ip: usize, hash: Hash) -> alloc::Result<()> {
         self.runtime_warning(ip, RuntimeWarningDiagnosticKind::UsedDeprecated { hash })
@@ -312,6 +498,30 @@ impl Diagnostics {
     where
         WarningDiagnosticKind: From<T>,
     {
+        self.push_warning(source_id, None, kind.into())
+    }
+
+    /// Push a warning which was produced while compiling or evaluating the
+    /// given constant item.
+    fn const_warning(
+        &mut self,
+        source_id: SourceId,
+        item: &Item,
+        kind: WarningDiagnosticKind,
+    ) -> alloc::Result<()> {
+        if !self.mode.warnings() {
+            return Ok(());
+        }
+
+        self.push_warning(source_id, Some(item.try_to_owned()?), kind)
+    }
+
+    fn push_warning(
+        &mut self,
+        source_id: SourceId,
+        const_item: Option<ItemBuf>,
+        kind: WarningDiagnosticKind,
+    ) -> alloc::Result<()> {
         if !self.mode.warnings() {
             return Ok(());
         }
@@ -319,7 +529,8 @@ impl Diagnostics {
         self.diagnostics
             .try_push(Diagnostic::Warning(WarningDiagnostic {
                 source_id,
-                kind: kind.into(),
+                kind,
+                const_item,
             }))?;
 
         self.has_warning = true;
@@ -773,6 +773,15 @@ where
                     notes.push(note.into_std());
                 }
             }
+            ErrorKind::ExpectedMeta {
+                needed_at: Some(needed_at),
+                ..
+            } => {
+                labels.push(
+                    d::Label::secondary(this.source_id(), needed_at.range())
+                        .with_message("Expected because of this"),
+                );
+            }
             ErrorKind::VariableMoved { moved_at, .. } => {
                 labels.push(
                     d::Label::secondary(this.source_id(), moved_at.range())
@@ -1,8 +1,9 @@
 use core::fmt;
 
-use crate::alloc::String;
+use crate::alloc::{String, Vec};
 use crate::ast::Span;
 use crate::ast::Spanned;
+use crate::compile::{Item, ItemBuf};
 use crate::SourceId;
 
 /// Warning diagnostic emitted during compilation. Warning diagnostics indicates
@@ -13,6 +14,9 @@ pub struct WarningDiagnostic {
     pub(crate) source_id: SourceId,
     /// The kind of the warning.
     pub(crate) kind: WarningDiagnosticKind,
+    /// The constant item which was being compiled or evaluated when the
+    /// warning was produced, if any.
+    pub(crate) const_item: Option<ItemBuf>,
 }
 
 impl WarningDiagnostic {
@@ -21,6 +25,16 @@ impl WarningDiagnostic {
         self.source_id
     }
 
+    /// The constant item which was being compiled or evaluated when the
+    /// warning was produced, if any.
+    ///
+    /// See [`Diagnostics::const_warnings`] for grouping warnings by it.
+    ///
+    /// [`Diagnostics::const_warnings`]: crate::Diagnostics::const_warnings
+    pub fn const_item(&self) -> Option<&Item> {
+        self.const_item.as_deref()
+    }
+
     /// The kind of the warning.
     #[cfg(feature = "emit")]
     pub(crate) fn kind(&self) -> &WarningDiagnosticKind {
@@ -40,10 +54,71 @@ impl WarningDiagnostic {
             | WarningDiagnosticKind::RemoveTupleCallParams { context, .. }
             | WarningDiagnosticKind::NotUsed { context, .. }
             | WarningDiagnosticKind::UsedDeprecated { context, .. }
-            | WarningDiagnosticKind::TemplateWithoutExpansions { context, .. } => *context,
+            | WarningDiagnosticKind::TemplateWithoutExpansions { context, .. }
+            | WarningDiagnosticKind::ConstStatementUnused { context, .. }
+            | WarningDiagnosticKind::NotConst { context, .. }
+            | WarningDiagnosticKind::NonExhaustiveConstMatch { context, .. } => *context,
             WarningDiagnosticKind::UnnecessarySemiColon { .. } => None,
         }
     }
+
+    /// Get a machine-applicable suggestion for how to fix the warning, if one
+    /// is available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Diagnostics, Sources};
+    /// use rune::diagnostics::Diagnostic;
+    ///
+    /// let mut sources = Sources::new();
+    /// sources.insert(rune::Source::memory("pub fn main() { `Hello World` }")?)?;
+    ///
+    /// let mut diagnostics = Diagnostics::new();
+    ///
+    /// let _ = rune::prepare(&mut sources)
+    ///     .with_diagnostics(&mut diagnostics)
+    ///     .build()?;
+    ///
+    /// let Some(Diagnostic::Warning(warning)) = diagnostics.diagnostics().first() else {
+    ///     panic!("expected a warning");
+    /// };
+    ///
+    /// let suggestion = warning.suggestion().expect("expected a suggestion");
+    /// assert_eq!(suggestion.replacement, "\"Hello World\"");
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn suggestion(&self) -> Option<Suggestion<'_>> {
+        match &self.kind {
+            WarningDiagnosticKind::TemplateWithoutExpansions { span, literal, .. } => {
+                Some(Suggestion::new(*span, literal))
+            }
+            WarningDiagnosticKind::RemoveTupleCallParams { span, .. }
+            | WarningDiagnosticKind::UnnecessarySemiColon { span } => {
+                Some(Suggestion::new(*span, ""))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A machine-applicable suggestion for fixing a [WarningDiagnostic].
+///
+/// Applying the suggestion means replacing the source text covered by `span`
+/// with `replacement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Suggestion<'a> {
+    /// The span of source text to replace.
+    pub span: Span,
+    /// The text to replace the span with.
+    pub replacement: &'a str,
+}
+
+impl<'a> Suggestion<'a> {
+    fn new(span: Span, replacement: &'a str) -> Self {
+        Self { span, replacement }
+    }
 }
 
 impl Spanned for WarningDiagnostic {
@@ -56,6 +131,9 @@ impl Spanned for WarningDiagnostic {
             WarningDiagnosticKind::RemoveTupleCallParams { span, .. } => *span,
             WarningDiagnosticKind::UnnecessarySemiColon { span, .. } => *span,
             WarningDiagnosticKind::UsedDeprecated { span, .. } => *span,
+            WarningDiagnosticKind::ConstStatementUnused { span, .. } => *span,
+            WarningDiagnosticKind::NotConst { span, .. } => *span,
+            WarningDiagnosticKind::NonExhaustiveConstMatch { span, .. } => *span,
         }
     }
 }
@@ -67,6 +145,37 @@ impl fmt::Display for WarningDiagnostic {
     }
 }
 
+/// Serializes the warning with the stable fields `kind`, `source_id`, `span`
+/// and `message`, where `span` has the byte offsets `start` and `end`.
+#[cfg(feature = "json")]
+#[cfg_attr(rune_docsrs, doc(cfg(feature = "json")))]
+impl serde::Serialize for WarningDiagnostic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        struct Message<'a>(&'a WarningDiagnosticKind);
+
+        impl serde::Serialize for Message<'_> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.collect_str(self.0)
+            }
+        }
+
+        let mut s = serializer.serialize_struct("WarningDiagnostic", 4)?;
+        s.serialize_field("kind", self.kind.name())?;
+        s.serialize_field("source_id", &self.source_id.into_index())?;
+        s.serialize_field("span", &self.span().range())?;
+        s.serialize_field("message", &Message(&self.kind))?;
+        s.end()
+    }
+}
+
 cfg_std! {
     impl std::error::Error for WarningDiagnostic {
         #[inline]
@@ -102,6 +211,8 @@ pub enum WarningDiagnosticKind {
         span: Span,
         /// The context in which it is used.
         context: Option<Span>,
+        /// The template written as a normal string literal.
+        literal: String,
     },
     /// Suggestion that call parameters could be removed.
     RemoveTupleCallParams {
@@ -125,6 +236,53 @@ pub enum WarningDiagnosticKind {
         /// The defined deprecation message
         message: String,
     },
+    /// A statement in a constant block produces a value which is never used.
+    ConstStatementUnused {
+        /// The span of the statement.
+        span: Span,
+        /// The context in which it is used.
+        context: Option<Span>,
+    },
+    /// A constant could not be evaluated at compile time.
+    ///
+    /// This is only emitted if const coverage is enabled.
+    NotConst {
+        /// The span which could not be evaluated.
+        span: Span,
+        /// The constant being evaluated.
+        context: Option<Span>,
+        /// The reason the constant could not be evaluated.
+        reason: String,
+    },
+    /// A constant match over an enum doesn't handle all of its variants and
+    /// has no wildcard branch.
+    NonExhaustiveConstMatch {
+        /// The span of the expression being matched over.
+        span: Span,
+        /// The names of the variants which aren't handled.
+        missing: Vec<String>,
+        /// The match expression.
+        context: Option<Span>,
+    },
+}
+
+impl WarningDiagnosticKind {
+    /// The name of the warning kind, which is the same as the name of its
+    /// variant.
+    #[cfg(feature = "json")]
+    fn name(&self) -> &'static str {
+        match self {
+            WarningDiagnosticKind::NotUsed { .. } => "NotUsed",
+            WarningDiagnosticKind::LetPatternMightPanic { .. } => "LetPatternMightPanic",
+            WarningDiagnosticKind::TemplateWithoutExpansions { .. } => "TemplateWithoutExpansions",
+            WarningDiagnosticKind::RemoveTupleCallParams { .. } => "RemoveTupleCallParams",
+            WarningDiagnosticKind::UnnecessarySemiColon { .. } => "UnnecessarySemiColon",
+            WarningDiagnosticKind::UsedDeprecated { .. } => "UsedDeprecated",
+            WarningDiagnosticKind::ConstStatementUnused { .. } => "ConstStatementUnused",
+            WarningDiagnosticKind::NotConst { .. } => "NotConst",
+            WarningDiagnosticKind::NonExhaustiveConstMatch { .. } => "NonExhaustiveConstMatch",
+        }
+    }
 }
 
 impl fmt::Display for WarningDiagnosticKind {
@@ -147,6 +305,27 @@ impl fmt::Display for WarningDiagnosticKind {
             WarningDiagnosticKind::UsedDeprecated { .. } => {
                 write!(f, "Used deprecated function")
             }
+            WarningDiagnosticKind::ConstStatementUnused { .. } => {
+                write!(f, "Value of constant statement is not used")
+            }
+            WarningDiagnosticKind::NotConst { reason, .. } => {
+                write!(f, "Constant could not be evaluated: {reason}")
+            }
+            WarningDiagnosticKind::NonExhaustiveConstMatch { missing, .. } => {
+                write!(f, "Constant match is missing variants: ")?;
+
+                let mut it = missing.iter().peekable();
+
+                while let Some(name) = it.next() {
+                    write!(f, "`{name}`")?;
+
+                    if it.peek().is_some() {
+                        write!(f, ", ")?;
+                    }
+                }
+
+                Ok(())
+            }
         }
     }
 }
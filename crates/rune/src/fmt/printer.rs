@@ -219,6 +219,7 @@ impl<'a> Printer<'a> {
             impl_,
             path,
             open,
+            consts,
             functions,
             close,
         } = item;
@@ -236,8 +237,27 @@ impl<'a> Printer<'a> {
 
         self.writer.indent();
 
-        for function in functions {
-            self.visit_fn(function, None)?;
+        // Constants and functions are stored separately, so interleave them
+        // back into the order they were declared in.
+        let mut consts = consts.iter().peekable();
+        let mut functions = functions.iter().peekable();
+
+        loop {
+            let is_const = match (consts.peek(), functions.peek()) {
+                (Some((c, _)), Some(f)) => c.span().start < f.span().start,
+                (Some(..), None) => true,
+                (None, Some(..)) => false,
+                (None, None) => break,
+            };
+
+            if is_const {
+                if let Some((c, semi)) = consts.next() {
+                    self.visit_const(c, Some(*semi))?;
+                }
+            } else if let Some(function) = functions.next() {
+                self.visit_fn(function, None)?;
+            }
+
             self.writer.newline()?;
         }
 
@@ -241,6 +241,7 @@ pub(crate) enum ExprKind<'hir> {
     Try(&'hir Expr<'hir>),
     Select(&'hir ExprSelect<'hir>),
     CallClosure(&'hir ExprCallClosure<'hir>),
+    ConstClosure(&'hir ExprClosure<'hir>),
     Lit(Lit<'hir>),
     Object(&'hir ExprObject<'hir>),
     Tuple(&'hir ExprSeq<'hir>),
@@ -436,6 +437,11 @@ pub(crate) enum Call<'hir> {
         /// The identifier of the constant function.
         id: NonZeroId,
     },
+    /// A call to a function which is built into constant evaluation.
+    ConstBuiltin {
+        /// Hash of the name of the function being called.
+        hash: Hash,
+    },
 }
 
 /// A function call `<expr>(<args>)`.
@@ -766,4 +772,22 @@ pub(crate) struct Local<'hir> {
     pub(crate) pat: Pat<'hir>,
     /// The expression the binding is assigned to.
     pub(crate) expr: Expr<'hir>,
+    /// The type ascribed to the binding, like `let x: i64 = ..`.
+    ///
+    /// This is only supported in constant contexts.
+    pub(crate) ty: Option<LocalType<'hir>>,
+}
+
+/// A type ascribed to a local variable declaration.
+#[derive(Debug, TryClone, Clone, Copy, Spanned)]
+#[try_clone(copy)]
+#[non_exhaustive]
+pub(crate) struct LocalType<'hir> {
+    /// The span of the ascribed type.
+    #[rune(span)]
+    pub(crate) span: Span,
+    /// The hash of the ascribed type.
+    pub(crate) hash: Hash,
+    /// The name of the ascribed type.
+    pub(crate) name: &'hir str,
 }
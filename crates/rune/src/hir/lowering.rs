@@ -1,14 +1,16 @@
 use core::cell::Cell;
+use core::iter;
 use core::ops::Neg;
 
 use num::ToPrimitive;
 
 use crate::alloc::prelude::*;
 use crate::alloc::try_format;
-use crate::alloc::{self, Box, HashMap, HashSet};
+use crate::alloc::{self, Box, HashMap, HashSet, String};
 use crate::ast::{self, Spanned};
+use crate::compile::ir;
 use crate::compile::meta;
-use crate::compile::{self, DynLocation, ErrorKind, Item, ItemId, WithSpan};
+use crate::compile::{self, ComponentRef, DynLocation, ErrorKind, Item, ItemId, WithSpan};
 use crate::hash::{Hash, ParametersBuilder};
 use crate::hir;
 use crate::indexing;
@@ -20,11 +22,29 @@ use crate::SourceId;
 
 use rune_macros::instrument;
 
-#[derive(Default, Clone, Copy)]
+#[derive(Clone, Copy)]
 enum Needs {
-    #[default]
-    Value,
-    Type,
+    /// A value is needed, with the span that established the need if any.
+    Value(Option<ast::Span>),
+    /// A type is needed, with the span that established the need.
+    Type(ast::Span),
+}
+
+impl Needs {
+    /// The span that established the need, if any.
+    fn span(self) -> Option<ast::Span> {
+        match self {
+            Needs::Value(span) => span,
+            Needs::Type(span) => Some(span),
+        }
+    }
+}
+
+impl Default for Needs {
+    #[inline]
+    fn default() -> Self {
+        Needs::Value(None)
+    }
 }
 
 pub(crate) struct Ctxt<'hir, 'a, 'arena> {
@@ -51,6 +71,17 @@ impl<'hir, 'a, 'arena> Ctxt<'hir, 'a, 'arena> {
         output
     }
 
+    #[inline(always)]
+    fn with_needs<F, O>(&mut self, needs: Needs, f: F) -> O
+    where
+        F: FnOnce(&mut Self) -> O,
+    {
+        let needs = self.needs.replace(needs);
+        let output = f(self);
+        self.needs.set(needs);
+        output
+    }
+
     /// Construct a new context for used when constants are built separately
     /// through the query system.
     pub(crate) fn with_query(
@@ -89,6 +120,35 @@ impl<'hir, 'a, 'arena> Ctxt<'hir, 'a, 'arena> {
         })
     }
 
+    /// Define locals which are provided by the caller when evaluating a
+    /// constant expression.
+    pub(crate) fn define_locals<'n, I>(
+        &mut self,
+        span: &dyn Spanned,
+        names: I,
+    ) -> compile::Result<()>
+    where
+        I: IntoIterator<Item = &'n str>,
+    {
+        for name in names {
+            let name = match self.arena.alloc_str(name) {
+                Ok(name) => name,
+                Err(e) => {
+                    return Err(compile::Error::new(
+                        span,
+                        ErrorKind::ArenaAllocError {
+                            requested: e.requested,
+                        },
+                    ))
+                }
+            };
+
+            self.scopes.define(hir::Name::Str(name), span)?;
+        }
+
+        Ok(())
+    }
+
     #[allow(unused)]
     #[instrument(span = ast)]
     pub(crate) fn try_lookup_meta(
@@ -303,6 +363,32 @@ fn expr_call_closure<'hir>(
     })))
 }
 
+/// Lower a closure in a constant context.
+///
+/// Constant closures are never built into functions, instead they are called
+/// in place by the built-in methods which accept them. Captured variables are
+/// therefore resolved from the enclosing constant scope when called.
+#[instrument(span = ast)]
+fn expr_const_closure<'hir>(
+    cx: &mut Ctxt<'hir, '_, '_>,
+    ast: &ast::ExprClosure,
+) -> compile::Result<hir::ExprKind<'hir>> {
+    alloc_with!(cx, ast);
+
+    cx.scopes.push()?;
+
+    let args = iter!(ast.args.as_slice(), |(ast, _)| fn_arg(cx, ast)?);
+    let body = expr(cx, &ast.body)?;
+
+    cx.scopes.pop().with_span(ast)?;
+
+    Ok(hir::ExprKind::ConstClosure(alloc!(hir::ExprClosure {
+        args,
+        body,
+        captures: &[],
+    })))
+}
+
 #[instrument(span = ast)]
 pub(crate) fn block<'hir>(
     cx: &mut Ctxt<'hir, '_, '_>,
@@ -506,6 +592,12 @@ pub(crate) fn expr<'hir>(
     cx: &mut Ctxt<'hir, '_, '_>,
     ast: &ast::Expr,
 ) -> compile::Result<hir::Expr<'hir>> {
+    // NB: the span which established the need for a value only applies to a
+    // path which is the immediate expression, not to paths nested in it.
+    if let (Needs::Value(Some(..)), false) = (cx.needs.get(), matches!(ast, ast::Expr::Path(..))) {
+        return cx.with_needs(Needs::Value(None), |cx| expr(cx, ast));
+    }
+
     alloc_with!(cx, ast);
 
     let in_path = cx.in_path.take();
@@ -578,10 +670,14 @@ pub(crate) fn expr<'hir>(
                 drop: iter!(layer.into_drop_order()),
             }))
         }
-        ast::Expr::Let(ast) => hir::ExprKind::Let(alloc!(hir::ExprLet {
-            pat: pat(cx, &ast.pat)?,
-            expr: expr(cx, &ast.expr)?,
-        })),
+        ast::Expr::Let(ast) => {
+            let needs = Needs::Value(Some(ast.let_token.span()));
+
+            hir::ExprKind::Let(alloc!(hir::ExprLet {
+                pat: pat(cx, &ast.pat)?,
+                expr: cx.with_needs(needs, |cx| expr(cx, &ast.expr))?,
+            }))
+        }
         ast::Expr::If(ast) => hir::ExprKind::If(alloc!(expr_if(cx, ast)?)),
         ast::Expr::Match(ast) => hir::ExprKind::Match(alloc!(hir::ExprMatch {
             expr: expr(cx, &ast.expr)?,
@@ -614,15 +710,14 @@ pub(crate) fn expr<'hir>(
         }
         ast::Expr::Binary(ast) => {
             let rhs_needs = match &ast.op {
-                ast::BinOp::As(..) | ast::BinOp::Is(..) | ast::BinOp::IsNot(..) => Needs::Type,
-                _ => Needs::Value,
+                ast::BinOp::As(..) | ast::BinOp::Is(..) | ast::BinOp::IsNot(..) => {
+                    Needs::Type(ast.op.span())
+                }
+                _ => Needs::Value(Some(ast.op.span())),
             };
 
             let lhs = expr(cx, &ast.lhs)?;
-
-            let needs = cx.needs.replace(rhs_needs);
-            let rhs = expr(cx, &ast.rhs)?;
-            cx.needs.set(needs);
+            let rhs = cx.with_needs(rhs_needs, |cx| expr(cx, &ast.rhs))?;
 
             hir::ExprKind::Binary(alloc!(hir::ExprBinary {
                 lhs,
@@ -639,7 +734,13 @@ pub(crate) fn expr<'hir>(
         ast::Expr::Break(ast) => hir::ExprKind::Break(alloc!(expr_break(cx, ast)?)),
         ast::Expr::Continue(ast) => hir::ExprKind::Continue(alloc!(expr_continue(cx, ast)?)),
         ast::Expr::Yield(ast) => hir::ExprKind::Yield(option!(&ast.expr, |ast| expr(cx, ast)?)),
-        ast::Expr::Return(ast) => hir::ExprKind::Return(option!(&ast.expr, |ast| expr(cx, ast)?)),
+        ast::Expr::Return(ast) => {
+            let needs = Needs::Value(Some(ast.return_token.span()));
+
+            hir::ExprKind::Return(option!(&ast.expr, |ast| {
+                cx.with_needs(needs, |cx| expr(cx, ast))?
+            }))
+        }
         ast::Expr::Await(ast) => hir::ExprKind::Await(alloc!(expr(cx, &ast.expr)?)),
         ast::Expr::Try(ast) => hir::ExprKind::Try(alloc!(expr(cx, &ast.expr)?)),
         ast::Expr::Select(ast) => hir::ExprKind::Select(alloc!(hir::ExprSelect {
@@ -666,6 +767,7 @@ pub(crate) fn expr<'hir>(
                 }
             })
         })),
+        ast::Expr::Closure(ast) if cx.const_eval => expr_const_closure(cx, ast)?,
         ast::Expr::Closure(ast) => expr_call_closure(cx, ast)?,
         ast::Expr::Lit(ast) => hir::ExprKind::Lit(lit(cx, &ast.lit)?),
         ast::Expr::Object(ast) => expr_object(cx, ast)?,
@@ -1143,15 +1245,76 @@ fn fn_arg<'hir>(
 
 /// Lower an assignment.
 fn local<'hir>(cx: &mut Ctxt<'hir, '_, '_>, ast: &ast::Local) -> compile::Result<hir::Local<'hir>> {
+    alloc_with!(cx, ast);
+
     // Note: expression needs to be assembled before pattern, otherwise the
     // expression will see declarations in the pattern.
-    let expr = expr(cx, &ast.expr)?;
+    let expr = cx.with_needs(Needs::Value(Some(ast.let_token.span())), |cx| {
+        expr(cx, &ast.expr)
+    })?;
+
+    // In constant contexts `let <ident>: <type> = ..` ascribes a type to the
+    // binding, which is otherwise parsed as an unsupported binding pattern.
+    if cx.const_eval {
+        if let ast::Pat::Binding(binding) = &ast.pat {
+            if let (ast::ObjectKey::Path(path), ast::Pat::Path(ty)) = (&binding.key, &*binding.pat)
+            {
+                if let Some(ident) = path.try_as_ident() {
+                    let ty = local_type(cx, &ty.path)?;
+                    let name = alloc_str!(ident.resolve(resolve_context!(cx.q))?);
+                    cx.scopes.define(hir::Name::Str(name), ident)?;
+
+                    let pat = hir::Pat {
+                        span: path.span(),
+                        kind: hir::PatKind::Path(alloc!(hir::PatPathKind::Ident(name))),
+                    };
+
+                    return Ok(hir::Local {
+                        span: ast.span(),
+                        pat,
+                        expr,
+                        ty: Some(ty),
+                    });
+                }
+            }
+        }
+    }
+
     let pat = pat(cx, &ast.pat)?;
 
     Ok(hir::Local {
         span: ast.span(),
         pat,
         expr,
+        ty: None,
+    })
+}
+
+/// Resolve the type ascribed to a local variable.
+fn local_type<'hir>(
+    cx: &mut Ctxt<'hir, '_, '_>,
+    ast: &ast::Path,
+) -> compile::Result<hir::LocalType<'hir>> {
+    alloc_with!(cx, ast);
+
+    let named = cx.q.convert_path(ast)?;
+    let parameters = generics_parameters(cx, &named)?;
+    let meta = cx.lookup_meta(ast, named.item, parameters)?;
+
+    let Some(hash) = meta.type_hash_of() else {
+        return Err(compile::Error::expected_meta(
+            ast,
+            meta.info(cx.q.pool)?,
+            "a type",
+        ));
+    };
+
+    let name = try_format!("{}", cx.q.pool.item(meta.item_meta.item));
+
+    Ok(hir::LocalType {
+        span: ast.span(),
+        hash,
+        name: alloc_str!(name.as_str()),
     })
 }
 
@@ -1438,7 +1601,7 @@ pub(crate) fn expr_path<'hir>(
         return Ok(hir::ExprKind::Variable(hir::Name::SelfValue));
     }
 
-    if let Needs::Value = cx.needs.get() {
+    if let Needs::Value(..) = cx.needs.get() {
         if let Some(name) = ast.try_as_ident() {
             let name = alloc_str!(name.resolve(resolve_context!(cx.q))?);
 
@@ -1461,7 +1624,15 @@ pub(crate) fn expr_path<'hir>(
         return expr_path_meta(cx, &meta, ast);
     }
 
-    if let (Needs::Value, Some(local)) = (cx.needs.get(), ast.try_as_ident()) {
+    // Constants and constant functions which are being processed can't be
+    // looked up, so report the cycle instead of a missing item.
+    cx.q.check_const_cycle(ast, named.item)?;
+
+    if cx.const_eval {
+        check_numeric_limit(cx, ast, named.item)?;
+    }
+
+    if let (Needs::Value(..), Some(local)) = (cx.needs.get(), ast.try_as_ident()) {
         let local = local.resolve(resolve_context!(cx.q))?;
 
         // light heuristics, treat it as a type error in case the first
@@ -1490,6 +1661,50 @@ pub(crate) fn expr_path<'hir>(
     Err(compile::Error::new(ast, kind))
 }
 
+/// Numeric limits which are available as constants on primitive numeric types.
+const NUMERIC_LIMITS: &[&str] = &["MAX", "MIN", "BITS"];
+
+/// Report a clear error if a numeric limit like `MAX` is referenced on a type
+/// which isn't a primitive numeric type, since it would otherwise be reported
+/// as a missing item.
+fn check_numeric_limit(
+    cx: &mut Ctxt<'_, '_, '_>,
+    span: &dyn Spanned,
+    item: ItemId,
+) -> compile::Result<()> {
+    let item = cx.q.pool.item(item);
+
+    let (Some(parent), Some(ComponentRef::Str(name))) = (item.parent(), item.last()) else {
+        return Ok(());
+    };
+
+    let Some(name) = NUMERIC_LIMITS.iter().copied().find(|limit| *limit == name) else {
+        return Ok(());
+    };
+
+    let parent = parent.try_to_owned()?;
+    let parent = cx.q.pool.alloc_item(parent)?;
+
+    let Some(meta) = cx.try_lookup_meta(span, parent, &GenericsParameters::default())? else {
+        return Ok(());
+    };
+
+    if !matches!(
+        meta.kind,
+        meta::Kind::Struct { .. } | meta::Kind::Type { .. } | meta::Kind::Enum { .. }
+    ) {
+        return Ok(());
+    }
+
+    Err(compile::Error::msg(
+        span,
+        try_format!(
+            "Numeric limit `{name}` is only available on primitive numeric types, like `i64::{name}`, but `{}` is not one",
+            cx.q.pool.item(meta.item_meta.item)
+        ),
+    ))
+}
+
 /// Compile an item.
 #[instrument(span = span)]
 fn expr_path_meta<'hir>(
@@ -1499,7 +1714,9 @@ fn expr_path_meta<'hir>(
 ) -> compile::Result<hir::ExprKind<'hir>> {
     alloc_with!(cx, span);
 
-    if let Needs::Value = cx.needs.get() {
+    let needs = cx.needs.get();
+
+    if let Needs::Value(..) = needs {
         match &meta.kind {
             meta::Kind::Struct {
                 fields: meta::Fields::Empty,
@@ -1536,18 +1753,20 @@ fn expr_path_meta<'hir>(
             meta::Kind::Struct { .. } | meta::Kind::Type { .. } | meta::Kind::Enum { .. } => {
                 Ok(hir::ExprKind::Type(Type::new(meta.hash)))
             }
-            _ => Err(compile::Error::expected_meta(
+            _ => Err(compile::Error::expected_meta_needed_at(
                 span,
                 meta.info(cx.q.pool)?,
                 "something that can be used as a value",
+                needs.span(),
             )),
         }
     } else {
         let Some(type_hash) = meta.type_hash_of() else {
-            return Err(compile::Error::expected_meta(
+            return Err(compile::Error::expected_meta_needed_at(
                 span,
                 meta.info(cx.q.pool)?,
                 "something that has a type",
+                needs.span(),
             ));
         };
 
@@ -1754,7 +1973,27 @@ fn expr_call<'hir>(
                 let named = cx.q.convert_path(path)?;
                 let parameters = generics_parameters(cx, &named)?;
 
-                let meta = cx.lookup_meta(path, named.item, parameters)?;
+                let meta = match cx.try_lookup_meta(path, named.item, &parameters)? {
+                    Some(meta) => meta,
+                    None => {
+                        if let Some(id) = cx.q.pending_const_fn(named.item) {
+                            let from = cx.q.item_for(ast.id).with_span(ast)?;
+
+                            break 'ok hir::Call::ConstFn {
+                                from_module: from.module,
+                                from_item: from.item,
+                                id,
+                            };
+                        }
+
+                        if let Some(hash) = const_builtin(cx, path)? {
+                            break 'ok hir::Call::ConstBuiltin { hash };
+                        }
+
+                        cx.lookup_meta(path, named.item, parameters)?
+                    }
+                };
+
                 debug_assert_eq!(meta.item_meta.item, named.item);
 
                 match &meta.kind {
@@ -1846,7 +2085,21 @@ fn expr_call<'hir>(
                     }
                     hir::ExprField::IdentGenerics(ident, hash) => {
                         cx.q.unit.insert_debug_ident(ident)?;
-                        Hash::ident(ident).with_function_parameters(hash)
+
+                        // Built-in methods in constant contexts are untyped,
+                        // so generic parameters are ignored unless they pick
+                        // a specialized method.
+                        if let Some(hash) =
+                            ir::builtins::generic_method(ident, hash).filter(|_| cx.const_eval)
+                        {
+                            hash
+                        } else if cx.const_eval
+                            && ir::builtins::method(Hash::ident(ident)).is_some()
+                        {
+                            Hash::ident(ident)
+                        } else {
+                            Hash::ident(ident).with_function_parameters(hash)
+                        }
                     }
                 };
 
@@ -1867,6 +2120,45 @@ fn expr_call<'hir>(
     })
 }
 
+/// Resolve a path to a function which is built into constant evaluation.
+///
+/// These are only available in constant contexts, and only if the path isn't
+/// shadowed by an item. Paths with multiple segments like `Object::from` are
+/// matched by their full name.
+fn const_builtin(cx: &mut Ctxt<'_, '_, '_>, path: &ast::Path) -> compile::Result<Option<Hash>> {
+    if !cx.const_eval {
+        return Ok(None);
+    }
+
+    if path.global.is_some() || path.trailing.is_some() {
+        return Ok(None);
+    }
+
+    let mut name = String::new();
+
+    let segments = iter::once(&path.first).chain(path.rest.iter().map(|(_, segment)| segment));
+
+    for (index, segment) in segments.enumerate() {
+        let Some(ident) = segment.try_as_ident() else {
+            return Ok(None);
+        };
+
+        if index > 0 {
+            name.try_push_str("::")?;
+        }
+
+        name.try_push_str(ident.resolve(resolve_context!(cx.q))?)?;
+    }
+
+    let hash = Hash::ident(&name);
+
+    if ir::builtins::function(hash).is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(hash))
+}
+
 #[instrument(span = ast)]
 fn expr_field_access<'hir>(
     cx: &mut Ctxt<'hir, '_, '_>,
@@ -15,7 +15,7 @@ use crate::macros::MacroCompiler;
 use crate::parse::{NonZeroId, Parse, Parser, Resolve};
 use crate::query::{
     BuiltInFile, BuiltInFormat, BuiltInLine, BuiltInMacro, BuiltInTemplate, ItemImplEntry, Query,
-    QueryImplFn,
+    QueryImplConst, QueryImplFn,
 };
 use crate::runtime::format;
 use crate::runtime::Call;
@@ -86,6 +86,23 @@ impl<'a, 'arena> Indexer<'a, 'arena> {
         Ok(())
     }
 
+    /// Notify the visitor that an attribute on the given item is about to be
+    /// rejected.
+    fn reject_attribute(
+        &mut self,
+        item: &dyn Spanned,
+        attr: &ast::Attribute,
+    ) -> compile::Result<()> {
+        let name = attr.path.resolve(resolve_context!(self.q))?;
+
+        self.q
+            .visitor
+            .visit_rejected_attribute(self.source_id, item, attr, &name)
+            .with_span(attr)?;
+
+        Ok(())
+    }
+
     /// Leave the last macro context.
     fn leave_macro(&mut self) {
         self.macro_depth = self.macro_depth.wrapping_sub(1);
@@ -620,6 +637,8 @@ pub(crate) fn file(idx: &mut Indexer<'_, '_>, ast: &mut ast::File) -> compile::R
 
             if idx.try_expand_internal_macro(&mut p, &mut macro_call)? {
                 if let Some(attr) = p.remaining(&macro_call.attributes).next() {
+                    idx.reject_attribute(&macro_call, attr)?;
+
                     return Err(compile::Error::msg(
                         attr,
                         "Attributes on macros are not supported",
@@ -631,6 +650,8 @@ pub(crate) fn file(idx: &mut Indexer<'_, '_>, ast: &mut ast::File) -> compile::R
                     .try_push((ast::Item::MacroCall(macro_call), semi))?;
             } else {
                 if let Some(attr) = p.remaining(&macro_call.attributes).next() {
+                    idx.reject_attribute(&macro_call, attr)?;
+
                     return Err(compile::Error::msg(
                         attr,
                         "Attributes on macros are not supported",
@@ -639,7 +660,20 @@ pub(crate) fn file(idx: &mut Indexer<'_, '_>, ast: &mut ast::File) -> compile::R
 
                 let file = idx.expand_macro::<ast::File>(&mut macro_call)?;
 
-                for (item, semi) in file.items.into_iter().rev() {
+                let mut items = Vec::try_with_capacity(file.items.len())?;
+                let mut semis = Vec::try_with_capacity(file.items.len())?;
+
+                for (item, semi) in file.items {
+                    items.try_push(item)?;
+                    semis.try_push(semi)?;
+                }
+
+                idx.q
+                    .visitor
+                    .visit_macro_expansion(idx.source_id, macro_call.span(), &items)
+                    .with_span(&macro_call)?;
+
+                for (item, semi) in items.into_iter().zip(semis).rev() {
                     match item {
                         item @ ast::Item::MacroCall(_) => {
                             queue.try_push_back((depth.wrapping_add(1), item, Vec::new(), semi))?;
@@ -803,6 +837,8 @@ pub(crate) fn item_fn_immediate(
     };
 
     if let Some(attrs) = p.remaining(&ast.attributes).next() {
+        idx.reject_attribute(&ast, attrs)?;
+
         return Err(compile::Error::msg(
             attrs,
             "Attributes on functions are not supported",
@@ -1344,6 +1380,8 @@ fn item_enum(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemEnum) -> compile::Resu
     let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &ast.attributes)?;
 
     if let Some(first) = p.remaining(&ast.attributes).next() {
+        idx.reject_attribute(&ast, first)?;
+
         return Err(compile::Error::msg(
             first,
             "Attributes on enums are not supported",
@@ -1371,6 +1409,8 @@ fn item_enum(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemEnum) -> compile::Resu
         let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &variant.attributes)?;
 
         if let Some(first) = p.remaining(&variant.attributes).next() {
+            idx.reject_attribute(&variant, first)?;
+
             return Err(compile::Error::msg(
                 first,
                 "Attributes on variants are not supported",
@@ -1391,20 +1431,20 @@ fn item_enum(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemEnum) -> compile::Resu
 
         variant.id.set(item_meta.id);
 
-        let cx = resolve_context!(idx.q);
-
         for (field, _) in variant.body.fields() {
             let mut p = attrs::Parser::new(&field.attributes)?;
-            let docs = Doc::collect_from(cx, &mut p, &field.attributes)?;
+            let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &field.attributes)?;
 
             if let Some(first) = p.remaining(&field.attributes).next() {
+                idx.reject_attribute(field, first)?;
+
                 return Err(compile::Error::msg(
                     first,
                     "Attributes on variant fields are not supported",
                 ));
             }
 
-            let name = field.name.resolve(cx)?;
+            let name = field.name.resolve(resolve_context!(idx.q))?;
 
             for doc in docs {
                 idx.q
@@ -1414,7 +1454,7 @@ fn item_enum(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemEnum) -> compile::Resu
                         idx.q.pool.item(item_meta.item),
                         idx.q.pool.item_type_hash(item_meta.item),
                         name,
-                        doc.doc_string.resolve(cx)?.as_ref(),
+                        doc.doc_string.resolve(resolve_context!(idx.q))?.as_ref(),
                     )
                     .with_span(doc)?;
             }
@@ -1438,6 +1478,8 @@ fn item_struct(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemStruct) -> compile::
     let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &ast.attributes)?;
 
     if let Some(first) = p.remaining(&ast.attributes).next() {
+        idx.reject_attribute(&ast, first)?;
+
         return Err(compile::Error::msg(
             first,
             "Attributes on structs are not supported",
@@ -1458,20 +1500,20 @@ fn item_struct(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemStruct) -> compile::
     )?;
     ast.id.set(item_meta.id);
 
-    let cx = resolve_context!(idx.q);
-
     for (field, _) in ast.body.fields() {
         let mut p = attrs::Parser::new(&field.attributes)?;
-        let docs = Doc::collect_from(cx, &mut p, &field.attributes)?;
+        let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &field.attributes)?;
 
         if let Some(first) = p.remaining(&field.attributes).next() {
+            idx.reject_attribute(field, first)?;
+
             return Err(compile::Error::msg(
                 first,
                 "Attributes on fields are not supported",
             ));
         }
 
-        let name = field.name.resolve(cx)?;
+        let name = field.name.resolve(resolve_context!(idx.q))?;
 
         for doc in docs {
             idx.q
@@ -1481,7 +1523,7 @@ fn item_struct(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemStruct) -> compile::
                     idx.q.pool.item(item_meta.item),
                     idx.q.pool.item_type_hash(item_meta.item),
                     name,
-                    doc.doc_string.resolve(cx)?.as_ref(),
+                    doc.doc_string.resolve(resolve_context!(idx.q))?.as_ref(),
                 )
                 .with_span(doc)?;
         }
@@ -1503,6 +1545,8 @@ fn item_struct(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemStruct) -> compile::
 #[instrument(span = ast)]
 fn item_impl(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemImpl) -> compile::Result<()> {
     if let Some(first) = ast.attributes.first() {
+        idx.reject_attribute(&ast, first)?;
+
         return Err(compile::Error::msg(
             first,
             "Attributes on impl blocks are not supported",
@@ -1529,6 +1573,19 @@ fn item_impl(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemImpl) -> compile::Resu
         item_fn(idx, i)?;
     }
 
+    // Constants are indexed once the path of the impl has been resolved, since
+    // they are registered under the item being implemented.
+    for (i, _) in ast.consts.drain(..) {
+        idx.q
+            .inner
+            .impl_consts
+            .entry(id)
+            .or_try_default()?
+            .try_push(QueryImplConst {
+                ast: Box::try_new(i)?,
+            })?;
+    }
+
     idx.item = idx_item;
     Ok(())
 }
@@ -1540,6 +1597,8 @@ fn item_mod(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemMod) -> compile::Result
     let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &ast.attributes)?;
 
     if let Some(first) = p.remaining(&ast.attributes).next() {
+        idx.reject_attribute(&ast, first)?;
+
         return Err(compile::Error::msg(
             first,
             "Attributes on modules are not supported",
@@ -1584,18 +1643,37 @@ fn item_mod(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemMod) -> compile::Result
 }
 
 #[instrument(span = ast)]
-fn item_const(idx: &mut Indexer<'_, '_>, mut ast: ast::ItemConst) -> compile::Result<()> {
+pub(crate) fn item_const(
+    idx: &mut Indexer<'_, '_>,
+    mut ast: ast::ItemConst,
+) -> compile::Result<()> {
     let mut p = attrs::Parser::new(&ast.attributes)?;
 
     let docs = Doc::collect_from(resolve_context!(idx.q), &mut p, &ast.attributes)?;
 
+    let enabled = match p.try_parse::<attrs::When>(resolve_context!(idx.q), &ast.attributes)? {
+        Some((_, when)) => {
+            let flag = when.flag.resolve(resolve_context!(idx.q))?;
+            idx.q.options.is_when_flag(flag.as_ref())
+        }
+        None => true,
+    };
+
     if let Some(first) = p.remaining(&ast.attributes).next() {
+        idx.reject_attribute(&ast, first)?;
+
         return Err(compile::Error::msg(
             first,
             "Attributes on constants are not supported",
         ));
     }
 
+    // Constants which are disabled through `#[when(..)]` are skipped entirely,
+    // so they're neither indexed nor evaluated.
+    if !enabled {
+        return Ok(());
+    }
+
     let name = ast.name.resolve(resolve_context!(idx.q))?;
     let guard = idx.items.push_name(name.as_ref())?;
     let idx_item = idx.item.replace();
@@ -1654,6 +1732,8 @@ fn item(idx: &mut Indexer<'_, '_>, ast: ast::Item) -> compile::Result<()> {
                 .with_span(&macro_call)?;
 
             if let Some(span) = macro_call.attributes.first() {
+                idx.reject_attribute(&macro_call, span)?;
+
                 return Err(compile::Error::msg(
                     span,
                     "Attributes on macros are not supported",
@@ -1663,6 +1743,8 @@ fn item(idx: &mut Indexer<'_, '_>, ast: ast::Item) -> compile::Result<()> {
         // NB: imports are ignored during indexing.
         ast::Item::Use(item_use) => {
             if let Some(span) = item_use.attributes.first() {
+                idx.reject_attribute(&item_use, span)?;
+
                 return Err(compile::Error::msg(
                     span,
                     "Attributes on uses are not supported",
@@ -2,14 +2,14 @@
 
 use core::fmt;
 
-use crate::alloc;
+use crate::alloc::{self, HashMap, String, Vec};
 use crate::ast;
 use crate::ast::Span;
 use crate::compile::{self, ErrorKind, ItemMeta};
 use crate::indexing::Indexer;
 use crate::macros::{IntoLit, ToTokens, TokenStream};
 use crate::parse::{Parse, Resolve};
-use crate::runtime::Value;
+use crate::runtime::{ConstValue, Value};
 use crate::{Source, SourceId};
 
 cfg_std! {
@@ -33,7 +33,9 @@ cfg_std! {
         F: FnOnce(&mut MacroContext<'_, '_, '_>) -> crate::support::Result<O>,
     {
         use crate::support::Context as _;
-        use crate::compile::{Item, NoopCompileVisitor, NoopSourceLoader, Pool, Prelude, UnitBuilder};
+        use crate::compile::{
+            Item, NoopCompileVisitor, NoopParseVisitor, NoopSourceLoader, Pool, Prelude, UnitBuilder,
+        };
         use crate::hir;
         use crate::indexing::{IndexItem, Items, Scopes};
         use crate::macros::Storage;
@@ -50,6 +52,7 @@ cfg_std! {
         let mut sources = Sources::default();
         let mut pool = Pool::new().context("Failed to allocate pool")?;
         let mut visitor = NoopCompileVisitor::new();
+        let mut parse_visitor = NoopParseVisitor::new();
         let mut diagnostics = Diagnostics::default();
         let mut source_loader = NoopSourceLoader::default();
         let options = Options::default();
@@ -65,9 +68,11 @@ cfg_std! {
             &mut sources,
             &mut pool,
             &mut visitor,
+            &mut parse_visitor,
             &mut diagnostics,
             &mut source_loader,
             &options,
+            None,
             &gen,
             &context,
             &mut inner,
@@ -123,6 +128,10 @@ pub struct MacroContext<'a, 'b, 'arena> {
 impl<'a, 'b, 'arena> MacroContext<'a, 'b, 'arena> {
     /// Evaluate the given target as a constant expression.
     ///
+    /// The value is returned as it was produced, without being converted into
+    /// a [`ConstValue`]. So values which can't be stored in constants, like
+    /// iterators, can be inspected as well.
+    ///
     /// # Panics
     ///
     /// This will panic if it's called outside of a macro context.
@@ -152,6 +161,60 @@ impl<'a, 'b, 'arena> MacroContext<'a, 'b, 'arena> {
         target.eval(self)
     }
 
+    /// Evaluate the given target as a constant expression once for each set
+    /// of bindings, where every binding is available as a local variable.
+    ///
+    /// The expression is only compiled once, and the same interpreter is reset
+    /// and reused for each evaluation.
+    ///
+    /// # Panics
+    ///
+    /// This will panic if it's called outside of a macro context.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use rune::support::*;
+    /// use rune::alloc::{HashMap, String};
+    /// use rune::ast;
+    /// use rune::macros::{self, quote};
+    /// use rune::parse::Parser;
+    /// use rune::runtime::ConstValue;
+    ///
+    /// macros::test(|cx| {
+    ///     let stream = quote!(a + b).into_token_stream(cx)?;
+    ///
+    ///     let mut p = Parser::from_token_stream(&stream, cx.input_span());
+    ///     let expr = p.parse_all::<ast::Expr>()?;
+    ///
+    ///     let mut bindings = Vec::new();
+    ///
+    ///     for (a, b) in [(1, 2), (3, 4)] {
+    ///         let mut values = HashMap::new();
+    ///         values.try_insert(String::try_from("a")?, ConstValue::Integer(a))?;
+    ///         values.try_insert(String::try_from("b")?, ConstValue::Integer(b))?;
+    ///         bindings.push(values);
+    ///     }
+    ///
+    ///     let values = cx.eval_each(&expr, &bindings)?;
+    ///     let sums = values
+    ///         .iter()
+    ///         .map(|value| value.try_as_integer::<u32>().context("Expected integer"))
+    ///         .collect::<Result<Vec<_>>>()?;
+    ///
+    ///     assert_eq!(sums, [3, 7]);
+    ///     Ok(())
+    /// })?;
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn eval_each(
+        &mut self,
+        target: &ast::Expr,
+        bindings: &[HashMap<String, ConstValue>],
+    ) -> compile::Result<Vec<Value>> {
+        target.eval_each(self, bindings)
+    }
+
     /// Construct a new literal from within a macro context.
     ///
     /// # Examples
@@ -17,8 +17,8 @@ use crate::alloc::prelude::*;
 use crate::alloc::{self, Box, Vec};
 use crate::compile::{meta, ContextError, Docs, Item, ItemBuf};
 use crate::runtime::{
-    AttributeMacroHandler, ConstValue, FullTypeOf, FunctionHandler, MacroHandler, MaybeTypeOf,
-    StaticType, TypeCheck, TypeInfo, TypeOf,
+    const_kind_handler, AttributeMacroHandler, ConstKind, ConstKindHandler, ConstValue, FullTypeOf,
+    FunctionHandler, MacroHandler, MaybeTypeOf, StaticType, TypeCheck, TypeInfo, TypeOf,
 };
 use crate::Hash;
 
@@ -112,6 +112,8 @@ pub(crate) struct ModuleType {
     pub(crate) spec: Option<TypeSpecification>,
     /// Handler to use if this type can be constructed through a regular function call.
     pub(crate) constructor: Option<Arc<FunctionHandler>>,
+    /// Handler to use if values of this type can be used in constant contexts.
+    pub(crate) const_kind: Option<ConstKindHandler>,
 }
 
 /// The kind of the variant.
@@ -207,6 +209,7 @@ pub(crate) struct ModuleItem {
 #[derive(TryClone)]
 pub(crate) struct ModuleFunction {
     pub(crate) handler: Arc<FunctionHandler>,
+    pub(crate) const_fn: bool,
     #[cfg(feature = "doc")]
     pub(crate) is_async: bool,
     #[cfg(feature = "doc")]
@@ -318,6 +321,7 @@ impl fmt::Debug for ItemMut<'_> {
 /// * [`Module::function_meta`].
 pub struct ItemFnMut<'a> {
     docs: &'a mut Docs,
+    const_fn: &'a mut bool,
     #[cfg(feature = "doc")]
     deprecated: &'a mut Option<Box<str>>,
     #[cfg(feature = "doc")]
@@ -353,6 +357,16 @@ impl ItemFnMut<'_> {
         self
     }
 
+    /// Mark the given item as safe to call in constant contexts.
+    ///
+    /// The function will be called with the evaluated arguments during
+    /// constant evaluation, and its result cached, so it must be pure and only
+    /// accept and return values which can be used as constants.
+    pub fn const_fn(self, const_fn: bool) -> Self {
+        *self.const_fn = const_fn;
+        self
+    }
+
     /// Mark the given item as deprecated.
     pub fn deprecated<S>(
         self,
@@ -636,6 +650,7 @@ where
     deprecated: &'a mut Option<Box<str>>,
     spec: &'a mut Option<TypeSpecification>,
     constructor: &'a mut Option<Arc<FunctionHandler>>,
+    const_kind: &'a mut Option<ConstKindHandler>,
     item: &'a Item,
     _marker: PhantomData<T>,
 }
@@ -740,6 +755,23 @@ where
         Ok(self)
     }
 
+    /// Allow values of the current type to be used in constant contexts.
+    ///
+    /// Values of the type returned from native functions marked with
+    /// [`ItemFnMut::const_fn`] can then be stored in constants, and binary
+    /// operations on them are folded through [`ConstKind::fold`].
+    ///
+    /// Such values can only be used in constant contexts. Constants holding
+    /// them are not stored in the compiled unit, and referencing them from
+    /// runtime code is a compile error.
+    pub fn const_kind(self) -> Result<Self, ContextError>
+    where
+        T: ConstKind,
+    {
+        *self.const_kind = Some(const_kind_handler::<T>);
+        Ok(self)
+    }
+
     fn make_struct(self, fields: Fields) -> Result<Self, ContextError> {
         let old = self.spec.replace(TypeSpecification::Struct(fields));
 
@@ -560,6 +560,7 @@ impl Module {
             type_info,
             spec: None,
             constructor: None,
+            const_kind: None,
         })?;
 
         T::install_with(self)?;
@@ -572,6 +573,7 @@ impl Module {
             deprecated: &mut ty.common.deprecated,
             spec: &mut ty.spec,
             constructor: &mut ty.constructor,
+            const_kind: &mut ty.const_kind,
             item: &ty.item,
             _marker: PhantomData,
         })
@@ -599,6 +601,7 @@ impl Module {
             deprecated: &mut ty.common.deprecated,
             spec: &mut ty.spec,
             constructor: &mut ty.constructor,
+            const_kind: &mut ty.const_kind,
             item: &ty.item,
             _marker: PhantomData,
         })
@@ -1739,6 +1742,7 @@ impl Module {
             common: ModuleItemCommon { docs, deprecated },
             kind: ModuleItemKind::Function(ModuleFunction {
                 handler: data.handler,
+                const_fn: false,
                 #[cfg(feature = "doc")]
                 is_async: data.is_async,
                 #[cfg(feature = "doc")]
@@ -1752,7 +1756,6 @@ impl Module {
 
         let last = self.items.last_mut().unwrap();
 
-        #[cfg(feature = "doc")]
         let last_fn = match &mut last.kind {
             ModuleItemKind::Function(f) => f,
             _ => unreachable!(),
@@ -1760,6 +1763,7 @@ impl Module {
 
         Ok(ItemFnMut {
             docs: &mut last.common.docs,
+            const_fn: &mut last_fn.const_fn,
             #[cfg(feature = "doc")]
             deprecated: &mut last.common.deprecated,
             #[cfg(feature = "doc")]
@@ -1789,6 +1793,7 @@ impl Module {
             common: ModuleItemCommon { docs, deprecated },
             kind: ModuleAssociatedKind::Function(ModuleFunction {
                 handler: data.handler,
+                const_fn: false,
                 #[cfg(feature = "doc")]
                 is_async: data.is_async,
                 #[cfg(feature = "doc")]
@@ -1802,7 +1807,6 @@ impl Module {
 
         let last = self.associated.last_mut().unwrap();
 
-        #[cfg(feature = "doc")]
         let last_fn = match &mut last.kind {
             ModuleAssociatedKind::Function(f) => f,
             _ => unreachable!(),
@@ -1810,6 +1814,7 @@ impl Module {
 
         Ok(ItemFnMut {
             docs: &mut last.common.docs,
+            const_fn: &mut last_fn.const_fn,
             #[cfg(feature = "doc")]
             deprecated: &mut last.common.deprecated,
             #[cfg(feature = "doc")]
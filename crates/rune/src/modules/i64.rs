@@ -35,6 +35,8 @@ pub fn module() -> Result<Module, ContextError> {
     module.function_meta(wrapping_div)?;
     module.function_meta(wrapping_mul)?;
     module.function_meta(wrapping_rem)?;
+    module.function_meta(wrapping_shl)?;
+    module.function_meta(wrapping_shr)?;
 
     module.function_meta(saturating_add)?;
     module.function_meta(saturating_sub)?;
@@ -78,6 +80,16 @@ pub fn module() -> Result<Module, ContextError> {
         "```",
     ])?;
 
+    module.constant("BITS", i64::BITS).build()?.docs([
+        "The size of this integer type in bits.",
+        "",
+        "# Examples",
+        "",
+        "```rune",
+        "assert_eq!(i64::BITS, 64);",
+        "```",
+    ])?;
+
     Ok(module)
 }
 
@@ -374,6 +386,48 @@ fn wrapping_rem(this: i64, rhs: i64) -> VmResult<i64> {
     VmResult::Ok(i64::wrapping_rem(this, rhs))
 }
 
+/// Panic-free bitwise shift-left; yields `self << mask(rhs)`, where `mask`
+/// removes any high-order bits of `rhs` that would cause the shift to exceed
+/// the bitwidth of the type.
+///
+/// This means that the shift amount is taken modulo 64, so shifting by 64 is
+/// the same as not shifting at all.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rune
+/// assert_eq!((-1).wrapping_shl(7), -128);
+/// assert_eq!((-1).wrapping_shl(64), -1);
+/// ```
+#[rune::function(instance)]
+#[inline]
+fn wrapping_shl(this: i64, rhs: i64) -> i64 {
+    i64::wrapping_shl(this, rhs as u32)
+}
+
+/// Panic-free bitwise shift-right; yields `self >> mask(rhs)`, where `mask`
+/// removes any high-order bits of `rhs` that would cause the shift to exceed
+/// the bitwidth of the type.
+///
+/// This means that the shift amount is taken modulo 64, and since the shift
+/// is arithmetic the sign of `self` is preserved.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```rune
+/// assert_eq!((-128).wrapping_shr(7), -1);
+/// assert_eq!((-128).wrapping_shr(64), -128);
+/// ```
+#[rune::function(instance)]
+#[inline]
+fn wrapping_shr(this: i64, rhs: i64) -> i64 {
+    i64::wrapping_shr(this, rhs as u32)
+}
+
 /// Saturating integer addition. Computes `self + rhs`, saturating at the
 /// numeric bounds instead of overflowing.
 ///
@@ -7,8 +7,9 @@ use rune_alloc::hash_map::RandomState;
 
 use crate as rune;
 use crate::runtime::{
-    ControlFlow, EnvProtocolCaller, Function, Generator, GeneratorState, Hasher, Iterator, Range,
-    RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive, Value, Vm, VmResult,
+    ControlFlow, EnvProtocolCaller, Function, Generator, GeneratorState, Hasher, Iterator,
+    ProtocolCaller, Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive, Value,
+    Vm, VmResult,
 };
 use crate::{ContextError, Module};
 
@@ -231,15 +232,17 @@ fn cmp(lhs: Value, rhs: Value) -> VmResult<Ordering> {
 /// ```
 #[rune::function]
 fn hash(value: Value) -> VmResult<i64> {
+    hash_with(&value, &mut EnvProtocolCaller)
+}
+
+/// Hash the given value with the given protocol caller.
+///
+/// This is shared with constant evaluation, so that hashes computed in
+/// constant contexts agree with the ones computed by [`hash`] at runtime.
+pub(crate) fn hash_with(value: &Value, caller: &mut impl ProtocolCaller) -> VmResult<i64> {
     let state = STATE.get_or_init(RandomState::new);
     let mut hasher = Hasher::new_with(state);
-
-    vm_try!(Value::hash_with(
-        &value,
-        &mut hasher,
-        &mut EnvProtocolCaller
-    ));
-
+    vm_try!(Value::hash_with(value, &mut hasher, caller));
     VmResult::Ok(hasher.finish() as i64)
 }
 
@@ -6,7 +6,7 @@ mod query;
 use core::fmt;
 use core::num::NonZeroUsize;
 
-pub(crate) use self::query::{MissingId, Query, QueryInner};
+pub(crate) use self::query::{ConstPath, MissingId, Query, QueryInner};
 
 use crate as rune;
 use crate::alloc::path::PathBuf;
@@ -153,6 +153,12 @@ pub(crate) struct QueryImplFn {
     pub(crate) ast: Box<ast::ItemFn>,
 }
 
+/// An associated constant in an implementation.
+pub(crate) struct QueryImplConst {
+    /// Ast for declaration.
+    pub(crate) ast: Box<ast::ItemConst>,
+}
+
 pub(crate) struct ItemImplEntry {
     /// Non-expanded ast of the path.
     pub(crate) path: Box<ast::Path>,
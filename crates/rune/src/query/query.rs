@@ -14,9 +14,10 @@ use crate::compile::context::ContextMeta;
 use crate::compile::ir;
 use crate::compile::meta::{self, FieldMeta};
 use crate::compile::{
-    self, CompileVisitor, ComponentRef, Doc, DynLocation, ErrorKind, ImportStep, IntoComponent,
-    Item, ItemBuf, ItemId, ItemMeta, Located, Location, MetaError, ModId, ModMeta, Names, Pool,
-    Prelude, SourceLoader, SourceMeta, UnitBuilder, Visibility, WithSpan,
+    self, CompileVisitor, ComponentRef, ConstErrorKind, ConstGraph, Doc, DynLocation, ErrorKind,
+    ImportStep, IntoComponent, IrErrorKind, Item, ItemBuf, ItemId, ItemMeta, Located, Location,
+    MetaError, ModId, ModMeta, Names, ParseVisitor, Pool, Prelude, SourceLoader, SourceMeta,
+    UnitBuilder, Visibility, WithSpan,
 };
 use crate::hir;
 use crate::indexing::{self, FunctionAst, Indexed, Items};
@@ -24,7 +25,7 @@ use crate::macros::Storage;
 use crate::parse::{Id, NonZeroId, Opaque, Resolve, ResolveContext};
 use crate::query::{
     Build, BuildEntry, BuiltInMacro, ConstFn, GenericsParameters, ItemImplEntry, Named,
-    QueryImplFn, QueryPath, Used,
+    QueryImplConst, QueryImplFn, QueryPath, Used,
 };
 #[cfg(feature = "doc")]
 use crate::runtime::Call;
@@ -70,12 +71,35 @@ pub(crate) struct QueryInner<'arena> {
     indexed: BTreeMap<ItemId, Vec<indexing::Entry>>,
     /// Compiled constant functions.
     const_fns: HashMap<NonZeroId, Rc<ConstFn<'arena>>>,
+    /// Identifiers of constant functions which are currently being lowered,
+    /// so that they can call themselves.
+    pending_const_fns: HashMap<ItemId, NonZeroId>,
     /// Indexed constant values.
     constants: HashMap<Hash, ConstValue>,
+    /// Rewritten paths of constant items, keyed by the hash of their original
+    /// path.
+    const_paths: HashMap<Hash, ItemBuf>,
+    /// Original paths of constant items, keyed by their rewritten path.
+    const_path_items: HashMap<ItemBuf, ItemBuf>,
+    /// Cached results of native functions called in constant contexts, keyed
+    /// by the function and the content of its arguments. The arguments are
+    /// stored alongside the result since keys might collide.
+    native_constants: HashMap<Hash, (Vec<ConstValue>, ConstValue)>,
+    /// Memoized results of pure calls to constant functions, keyed by the
+    /// function and the content of its arguments. The arguments are stored
+    /// alongside the result since keys might collide.
+    const_fn_results: HashMap<Hash, (Vec<ConstValue>, ConstValue)>,
+    /// The intermediate representation of evaluated constants, kept so that
+    /// they can be evaluated again if caching is disabled.
+    const_irs: HashMap<ItemId, (ItemMeta, ir::Ir)>,
     /// Query paths.
     pub(crate) query_paths: HashMap<NonZeroId, QueryPath>,
     /// Functions associated with impl blocks.
     pub(crate) impl_functions: HashMap<NonZeroId, Vec<QueryImplFn>>,
+    /// Associated constants in impl blocks.
+    pub(crate) impl_consts: HashMap<NonZeroId, Vec<QueryImplConst>>,
+    /// Dependencies between constants, if enabled.
+    pub(crate) const_graph: ConstGraph,
     /// Queue of impl items to process.
     pub(crate) impl_item_queue: VecDeque<ItemImplEntry>,
     /// The result of internally resolved macros.
@@ -90,6 +114,11 @@ pub(crate) struct QueryInner<'arena> {
     names: Names,
     /// Recorded captures.
     captures: HashMap<Hash, Vec<hir::OwnedName>>,
+    /// The number of bytes allocated by constant evaluation so far.
+    pub(crate) const_alloc: usize,
+    /// The number of native functions called by constant evaluation so far,
+    /// which tells if a call to a constant function is pure.
+    pub(crate) const_native_calls: usize,
 }
 
 impl QueryInner<'_> {
@@ -97,8 +126,20 @@ impl QueryInner<'_> {
     pub(crate) fn get_const_value(&self, hash: Hash) -> Option<&ConstValue> {
         self.constants.get(&hash)
     }
+
+    /// Get the hash a constant item is exposed under, which differs from its
+    /// own hash if its path has been rewritten.
+    pub(crate) fn const_hash(&self, hash: Hash) -> Hash {
+        match self.const_paths.get(&hash) {
+            Some(item) => Hash::type_hash(item),
+            None => hash,
+        }
+    }
 }
 
+/// A function which rewrites the path of a constant item.
+pub(crate) type ConstPath<'a> = &'a dyn Fn(&Item) -> alloc::Result<ItemBuf>;
+
 /// Query system of the rune compiler.
 ///
 /// The basic mode of operation here is that you ask for an item, and the query
@@ -125,12 +166,16 @@ pub(crate) struct Query<'a, 'arena> {
     pub(crate) pool: &'a mut Pool,
     /// Visitor for the compiler meta.
     pub(crate) visitor: &'a mut dyn CompileVisitor,
+    /// Visitor for parsed items.
+    pub(crate) parse_visitor: &'a mut dyn ParseVisitor,
     /// Compilation warnings.
     pub(crate) diagnostics: &'a mut Diagnostics,
     /// Source loader.
     pub(crate) source_loader: &'a mut dyn SourceLoader,
     /// Build options.
     pub(crate) options: &'a Options,
+    /// Rewrites the paths of constant items.
+    const_path: Option<ConstPath<'a>>,
     /// Shared id generator.
     pub(crate) gen: &'a Gen,
     /// Native context.
@@ -150,9 +195,11 @@ impl<'a, 'arena> Query<'a, 'arena> {
         sources: &'a mut Sources,
         pool: &'a mut Pool,
         visitor: &'a mut dyn CompileVisitor,
+        parse_visitor: &'a mut dyn ParseVisitor,
         diagnostics: &'a mut Diagnostics,
         source_loader: &'a mut dyn SourceLoader,
         options: &'a Options,
+        const_path: Option<ConstPath<'a>>,
         gen: &'a Gen,
         context: &'a Context,
         inner: &'a mut QueryInner<'arena>,
@@ -166,9 +213,11 @@ impl<'a, 'arena> Query<'a, 'arena> {
             sources,
             pool,
             visitor,
+            parse_visitor,
             diagnostics,
             source_loader,
             options,
+            const_path,
             gen,
             context,
             inner,
@@ -186,9 +235,11 @@ impl<'a, 'arena> Query<'a, 'arena> {
             pool: self.pool,
             sources: self.sources,
             visitor: self.visitor,
+            parse_visitor: self.parse_visitor,
             diagnostics: self.diagnostics,
             source_loader: self.source_loader,
             options: self.options,
+            const_path: self.const_path,
             gen: self.gen,
             context: self.context,
             inner: self.inner,
@@ -341,6 +392,18 @@ impl<'a, 'arena> Query<'a, 'arena> {
                     .with_span(location.as_spanned())?;
                 return Ok(Some(meta));
             }
+        } else if let (1, [Some(..), None]) = (parameters.trailing, parameters.parameters) {
+            // Constant functions are dynamically typed, so function parameters
+            // don't affect which instance is selected.
+            if let Some(meta) = self.query_meta(location.as_spanned(), item, Default::default())? {
+                if let meta::Kind::ConstFn { .. } = meta.kind {
+                    tracing::trace!("found const fn in query: {:?}", meta);
+                    self.visitor
+                        .visit_meta(location, meta.as_meta_ref(self.pool))
+                        .with_span(location.as_spanned())?;
+                    return Ok(Some(meta));
+                }
+            }
         }
 
         let Some(metas) = self.context.lookup_meta(self.pool.item(item)) else {
@@ -522,7 +585,22 @@ impl<'a, 'arena> Query<'a, 'arena> {
 
     /// Insert the given compile meta.
     pub(crate) fn insert_meta(&mut self, meta: meta::Meta) -> Result<&ItemMeta, MetaError> {
-        self.visitor.register_meta(meta.as_meta_ref(self.pool))?;
+        self.rewrite_const_path(&meta)?;
+
+        let meta_ref = meta.as_meta_ref(self.pool);
+
+        match (&meta.kind, self.inner.const_paths.get(&meta.hash)) {
+            (meta::Kind::Const, Some(item)) => {
+                self.visitor.register_meta(meta::MetaRef {
+                    hash: Hash::type_hash(item),
+                    item,
+                    ..meta_ref
+                })?;
+            }
+            _ => {
+                self.visitor.register_meta(meta_ref)?;
+            }
+        }
 
         let meta = match self
             .inner
@@ -544,6 +622,45 @@ impl<'a, 'arena> Query<'a, 'arena> {
         Ok(&meta.item_meta)
     }
 
+    /// Rewrite the path of the given constant item if a rewrite is in use.
+    ///
+    /// The rewritten path is used wherever the constant is exposed, which is
+    /// an error if another constant was already rewritten to the same path.
+    fn rewrite_const_path(&mut self, meta: &meta::Meta) -> Result<(), MetaError> {
+        let (Some(const_path), meta::Kind::Const) = (self.const_path, &meta.kind) else {
+            return Ok(());
+        };
+
+        if self.inner.const_paths.contains_key(&meta.hash) {
+            return Ok(());
+        }
+
+        let current = self.pool.item(meta.item_meta.item);
+        let item = const_path(current)?;
+
+        if let Some(existing) = self.inner.const_path_items.get(&item) {
+            return Err(MetaError::new(
+                compile::error::MetaErrorKind::ConstPathConflict {
+                    item,
+                    current: current.try_to_owned()?,
+                    existing: existing.try_clone()?,
+                },
+            ));
+        }
+
+        self.inner
+            .const_path_items
+            .try_insert(item.try_clone()?, current.try_to_owned()?)?;
+        self.inner.const_paths.try_insert(meta.hash, item)?;
+        Ok(())
+    }
+
+    /// Insert the given meta into the unit being built.
+    fn insert_unit_meta(&mut self, span: &dyn Spanned, meta: &meta::Meta) -> compile::Result<()> {
+        self.rewrite_const_path(meta).with_span(span)?;
+        self.unit.insert_meta(span, meta, self.pool, self.inner)
+    }
+
     /// Insert a new item with the given newly allocated identifier and complete
     /// `Item`.
     fn insert_new_item_with(
@@ -647,6 +764,21 @@ impl<'a, 'arena> Query<'a, 'arena> {
         }
     }
 
+    /// Get the identifier of the constant function at the given item if it's
+    /// currently being lowered.
+    pub(crate) fn pending_const_fn(&self, item: ItemId) -> Option<NonZeroId> {
+        self.inner.pending_const_fns.get(&item).copied()
+    }
+
+    /// Get the item of the constant function with the given identifier if it's
+    /// currently being lowered.
+    pub(crate) fn pending_const_fn_item(&self, id: NonZeroId) -> Option<ItemId> {
+        self.inner
+            .pending_const_fns
+            .iter()
+            .find_map(|(item, pending)| (*pending == id).then_some(*item))
+    }
+
     /// Index the given entry. It is not allowed to overwrite other entries.
     #[tracing::instrument(skip_all)]
     pub(crate) fn index(&mut self, entry: indexing::Entry) -> compile::Result<()> {
@@ -814,7 +946,7 @@ impl<'a, 'arena> Query<'a, 'arena> {
             parameters: Hash::EMPTY,
         };
 
-        self.unit.insert_meta(span, &meta, self.pool, self.inner)?;
+        self.insert_unit_meta(span, &meta)?;
         self.insert_meta(meta).with_span(span)?;
         Ok(())
     }
@@ -850,11 +982,93 @@ impl<'a, 'arena> Query<'a, 'arena> {
         Ok(false)
     }
 
+    /// Collect the values of all constant items which have been evaluated,
+    /// ordered by item.
+    pub(crate) fn const_values(
+        &self,
+        output: &mut Vec<(ItemBuf, ConstValue)>,
+    ) -> alloc::Result<()> {
+        for meta in self.inner.meta.values() {
+            if !matches!(meta.kind, meta::Kind::Const) {
+                continue;
+            }
+
+            let Some(value) = self.inner.constants.get(&meta.hash) else {
+                continue;
+            };
+
+            let item = match self.inner.const_paths.get(&meta.hash) {
+                Some(item) => item.try_clone()?,
+                None => self.pool.item(meta.item_meta.item).try_to_owned()?,
+            };
+
+            output.try_push((item, value.try_clone()?))?;
+        }
+
+        output.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        Ok(())
+    }
+
     /// Explicitly look for meta with the given item and hash.
     pub(crate) fn get_meta(&self, item: ItemId, hash: Hash) -> Option<&meta::Meta> {
         self.inner.meta.get(&(item, hash))
     }
 
+    /// Get the hashes and items of every variant of the enum with the given
+    /// type hash, or `None` if the enum isn't defined in a script.
+    pub(crate) fn enum_variants(
+        &mut self,
+        span: &dyn Spanned,
+        enum_hash: Hash,
+    ) -> compile::Result<Option<Vec<(Hash, ItemId)>>> {
+        let enum_item = self.inner.meta.values().find_map(|meta| match meta.kind {
+            meta::Kind::Enum { .. } if meta.hash == enum_hash => Some(meta.item_meta.item),
+            _ => None,
+        });
+
+        let Some(enum_item) = enum_item else {
+            return Ok(None);
+        };
+
+        let name = self.pool.item(enum_item).try_to_owned()?;
+
+        let components = self
+            .iter_components(&name)?
+            .map(|c| c.into_component())
+            .try_collect::<Result<Vec<_>, _>>()??;
+
+        let mut variants = Vec::new();
+
+        for c in components {
+            let item = self.pool.alloc_item(name.extended(c)?)?;
+
+            // NB: children of the enum also include associated items, which
+            // are skipped.
+            let Some(meta) = self.query_meta(span, item, Used::Unused)? else {
+                continue;
+            };
+
+            if let meta::Kind::Variant {
+                enum_hash: hash,
+                index,
+                ..
+            } = meta.kind
+            {
+                if hash == enum_hash {
+                    variants.try_push((index, meta.hash, item))?;
+                }
+            }
+        }
+
+        variants.sort_by_key(|&(index, ..)| index);
+        Ok(Some(
+            variants
+                .into_iter()
+                .map(|(_, hash, item)| (hash, item))
+                .try_collect()?,
+        ))
+    }
+
     /// Query for the given meta by looking up the reverse of the specified
     /// item.
     #[tracing::instrument(skip(self, span, item), fields(item = ?self.pool.item(item)))]
@@ -888,7 +1102,7 @@ impl<'a, 'arena> Query<'a, 'arena> {
 
         if let Some(entry) = self.remove_indexed(span, item)? {
             let meta = self.build_indexed_entry(span, entry, used)?;
-            self.unit.insert_meta(span, &meta, self.pool, self.inner)?;
+            self.insert_unit_meta(span, &meta)?;
             self.insert_meta(meta.try_clone()?).with_span(span)?;
             tracing::trace!(item = ?item, meta = ?meta, "build");
             return Ok(Some(meta));
@@ -1402,34 +1616,9 @@ impl<'a, 'arena> Query<'a, 'arena> {
                 kind
             }
             Indexed::ConstExpr(c) => {
-                let ir = {
-                    let arena = crate::hir::Arena::new();
-                    let mut hir_ctx = crate::hir::lowering::Ctxt::with_const(
-                        &arena,
-                        self.borrow(),
-                        item_meta.location.source_id,
-                    )?;
-                    let hir = crate::hir::lowering::expr(&mut hir_ctx, &c.ast)?;
-
-                    let mut cx = ir::Ctxt {
-                        source_id: item_meta.location.source_id,
-                        q: self.borrow(),
-                    };
-                    ir::compiler::expr(&hir, &mut cx)?
-                };
-
-                let mut const_compiler = ir::Interpreter {
-                    budget: ir::Budget::new(1_000_000),
-                    scopes: ir::Scopes::new()?,
-                    module: item_meta.module,
-                    item: item_meta.item,
-                    q: self.borrow(),
-                };
-
-                let const_value = const_compiler.eval_const(&ir, used)?;
-
-                let hash = self.pool.item_type_hash(item_meta.item);
-                self.inner.constants.try_insert(hash, const_value)?;
+                let result = self
+                    .with_const_mark(&item_meta, |q| q.eval_const_expr(&item_meta, &c.ast, used));
+                self.insert_const(item_meta, result)?;
 
                 if used.is_unused() {
                     self.inner.queue.try_push_back(BuildEntry {
@@ -1441,34 +1630,9 @@ impl<'a, 'arena> Query<'a, 'arena> {
                 meta::Kind::Const
             }
             Indexed::ConstBlock(c) => {
-                let ir = {
-                    let arena = crate::hir::Arena::new();
-                    let mut hir_ctx = crate::hir::lowering::Ctxt::with_const(
-                        &arena,
-                        self.borrow(),
-                        item_meta.location.source_id,
-                    )?;
-                    let hir = crate::hir::lowering::block(&mut hir_ctx, &c.ast)?;
-
-                    let mut cx = ir::Ctxt {
-                        source_id: item_meta.location.source_id,
-                        q: self.borrow(),
-                    };
-                    ir::Ir::new(item_meta.location.span, ir::compiler::block(&hir, &mut cx)?)
-                };
-
-                let mut const_compiler = ir::Interpreter {
-                    budget: ir::Budget::new(1_000_000),
-                    scopes: ir::Scopes::new()?,
-                    module: item_meta.module,
-                    item: item_meta.item,
-                    q: self.borrow(),
-                };
-
-                let const_value = const_compiler.eval_const(&ir, used)?;
-
-                let hash = self.pool.item_type_hash(item_meta.item);
-                self.inner.constants.try_insert(hash, const_value)?;
+                let result = self
+                    .with_const_mark(&item_meta, |q| q.eval_const_block(&item_meta, &c.ast, used));
+                self.insert_const(item_meta, result)?;
 
                 if used.is_unused() {
                     self.inner.queue.try_push_back(BuildEntry {
@@ -1480,23 +1644,34 @@ impl<'a, 'arena> Query<'a, 'arena> {
                 meta::Kind::Const
             }
             Indexed::ConstFn(c) => {
-                let (ir_fn, hir) = {
+                // NB: the identifier is allocated up front so that the
+                // function can call itself, directly or through other constant
+                // functions, while it's being lowered.
+                let id = self.gen.next();
+
+                self.inner
+                    .pending_const_fns
+                    .try_insert(item_meta.item, id)?;
+
+                let result = self.with_const_mark(&item_meta, |q| {
                     // TODO: avoid this arena?
                     let mut cx = crate::hir::lowering::Ctxt::with_const(
-                        self.const_arena,
-                        self.borrow(),
+                        q.const_arena,
+                        q.borrow(),
                         item_meta.location.source_id,
                     )?;
                     let hir = crate::hir::lowering::item_fn(&mut cx, &c.item_fn)?;
 
                     let mut cx = ir::Ctxt {
                         source_id: item_meta.location.source_id,
-                        q: self.borrow(),
+                        item: item_meta.item,
+                        q: q.borrow(),
                     };
-                    (ir::IrFn::compile_ast(&hir, &mut cx)?, hir)
-                };
+                    Ok((ir::IrFn::compile_ast(&hir, &mut cx)?, hir))
+                });
 
-                let id = self.gen.next();
+                self.inner.pending_const_fns.remove(&item_meta.item);
+                let (ir_fn, hir) = result?;
 
                 self.inner.const_fns.try_insert(
                     id,
@@ -1548,6 +1723,207 @@ impl<'a, 'arena> Query<'a, 'arena> {
         })
     }
 
+    /// Process the constant or constant function at the given item while it's
+    /// marked as being processed, so that cycles through it can be reported.
+    fn with_const_mark<T>(
+        &mut self,
+        item_meta: &ItemMeta,
+        f: impl FnOnce(&mut Self) -> compile::Result<T>,
+    ) -> compile::Result<T> {
+        self.check_const_cycle(&item_meta.location.span, item_meta.item)?;
+        self.consts.mark(item_meta.item)?;
+        let result = f(self);
+        self.consts.unmark();
+        result
+    }
+
+    /// Evaluate the constant at the given item again, if it has been
+    /// evaluated before.
+    ///
+    /// This is used instead of cached values if
+    /// [`Options::const_eval_no_cache`] is enabled.
+    ///
+    /// [`Options::const_eval_no_cache`]: crate::Options::const_eval_no_cache
+    pub(crate) fn recompute_const(
+        &mut self,
+        item: ItemId,
+        used: Used,
+    ) -> compile::Result<Option<ConstValue>> {
+        let Some((item_meta, ir)) = self.inner.const_irs.get(&item) else {
+            return Ok(None);
+        };
+
+        let item_meta = *item_meta;
+        let ir = ir.try_clone()?;
+        let value = self.with_const_mark(&item_meta, |q| q.eval_const_ir(&item_meta, &ir, used))?;
+        Ok(Some(value))
+    }
+
+    /// Evaluate a constant expression.
+    fn eval_const_expr(
+        &mut self,
+        item_meta: &ItemMeta,
+        ast: &ast::Expr,
+        used: Used,
+    ) -> compile::Result<ConstValue> {
+        let ir = {
+            let arena = crate::hir::Arena::new();
+            let mut hir_ctx = crate::hir::lowering::Ctxt::with_const(
+                &arena,
+                self.borrow(),
+                item_meta.location.source_id,
+            )?;
+            let hir = crate::hir::lowering::expr(&mut hir_ctx, ast)?;
+
+            let mut cx = ir::Ctxt {
+                source_id: item_meta.location.source_id,
+                item: item_meta.item,
+                q: self.borrow(),
+            };
+            ir::compiler::expr(&hir, &mut cx)?
+        };
+
+        self.eval_const_ir(item_meta, &ir, used)
+    }
+
+    /// Evaluate a constant block.
+    fn eval_const_block(
+        &mut self,
+        item_meta: &ItemMeta,
+        ast: &ast::Block,
+        used: Used,
+    ) -> compile::Result<ConstValue> {
+        let ir = {
+            let arena = crate::hir::Arena::new();
+            let mut hir_ctx = crate::hir::lowering::Ctxt::with_const(
+                &arena,
+                self.borrow(),
+                item_meta.location.source_id,
+            )?;
+            let hir = crate::hir::lowering::block(&mut hir_ctx, ast)?;
+
+            let mut cx = ir::Ctxt {
+                source_id: item_meta.location.source_id,
+                item: item_meta.item,
+                q: self.borrow(),
+            };
+            ir::Ir::new(item_meta.location.span, ir::compiler::block(&hir, &mut cx)?)
+        };
+
+        self.eval_const_ir(item_meta, &ir, used)
+    }
+
+    /// Evaluate the intermediate representation of a constant.
+    fn eval_const_ir(
+        &mut self,
+        item_meta: &ItemMeta,
+        ir: &ir::Ir,
+        used: Used,
+    ) -> compile::Result<ConstValue> {
+        if self.options.const_eval_no_cache && !self.inner.const_irs.contains_key(&item_meta.item) {
+            self.inner
+                .const_irs
+                .try_insert(item_meta.item, (*item_meta, ir.try_clone()?))?;
+        }
+
+        if self.options.time_const_eval {
+            return self.eval_const_ir_timed(item_meta, ir, used);
+        }
+
+        self.const_interpreter(item_meta)?.eval_const(ir, used)
+    }
+
+    /// Evaluate the intermediate representation of a constant in a tracing
+    /// span which records how long it took and how many steps it used.
+    #[cold]
+    fn eval_const_ir_timed(
+        &mut self,
+        item_meta: &ItemMeta,
+        ir: &ir::Ir,
+        used: Used,
+    ) -> compile::Result<ConstValue> {
+        let span = tracing::info_span!(
+            "const_eval",
+            item = %self.pool.item(item_meta.item),
+            steps = tracing::field::Empty,
+            elapsed = tracing::field::Empty,
+        );
+
+        let _enter = span.enter();
+
+        #[cfg(feature = "std")]
+        let start = std::time::Instant::now();
+
+        let mut interpreter = self.const_interpreter(item_meta)?;
+        let result = interpreter.eval_const(ir, used);
+
+        #[cfg(feature = "std")]
+        span.record("elapsed", tracing::field::debug(start.elapsed()));
+        span.record("steps", interpreter.budget.steps());
+        result
+    }
+
+    /// Construct an interpreter for evaluating the given constant item.
+    fn const_interpreter(
+        &mut self,
+        item_meta: &ItemMeta,
+    ) -> alloc::Result<ir::Interpreter<'_, 'arena>> {
+        Ok(ir::Interpreter {
+            budget: ir::Budget::new(
+                self.options.const_eval_budget,
+                self.options.const_recursion_limit,
+            ),
+            scopes: ir::Scopes::new()?,
+            module: item_meta.module,
+            item: item_meta.item,
+            source_id: item_meta.location.source_id,
+            explain: if self.options.explain_const {
+                Some(ir::Explain::default())
+            } else {
+                None
+            },
+            q: self.borrow(),
+        })
+    }
+
+    /// Insert the result of evaluating a constant.
+    ///
+    /// If const coverage is enabled, a constant which fails to evaluate is
+    /// recorded as a warning instead of failing compilation, unless it failed
+    /// because pure constants are required.
+    fn insert_const(
+        &mut self,
+        item_meta: ItemMeta,
+        result: compile::Result<ConstValue>,
+    ) -> compile::Result<()> {
+        let const_value = match result {
+            Ok(const_value) => const_value,
+            Err(error)
+                if self.options.const_coverage
+                    && error.const_error_kind() == Some(ConstErrorKind::NotConst) =>
+            {
+                self.diagnostics.not_const(
+                    item_meta.location.source_id,
+                    &error,
+                    Some(item_meta.location.span),
+                    try_format!("{error}"),
+                    self.pool.item(item_meta.item),
+                )?;
+
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+
+        if self.options.const_graph {
+            self.const_graph_node(item_meta.item)?;
+        }
+
+        let hash = self.pool.item_type_hash(item_meta.item);
+        self.inner.constants.try_insert(hash, const_value)?;
+        Ok(())
+    }
+
     /// Insert the given name into the unit.
     fn insert_name(&mut self, item: ItemId) -> alloc::Result<()> {
         let item = self.pool.item(item);
@@ -1569,7 +1945,7 @@ impl<'a, 'arena> Query<'a, 'arena> {
         let entry = indexing::Entry { item_meta, indexed };
 
         let meta = self.build_indexed_entry(span, entry, used)?;
-        self.unit.insert_meta(span, &meta, self.pool, self.inner)?;
+        self.insert_unit_meta(span, &meta)?;
         self.insert_meta(meta).with_span(span)?;
         Ok(())
     }
@@ -1781,6 +2157,173 @@ impl<'a, 'arena> Query<'a, 'arena> {
         self.context.get_const_value(hash)
     }
 
+    /// Record a node for the given constant item in the constant graph.
+    fn const_graph_node(&mut self, item: ItemId) -> alloc::Result<usize> {
+        let hash = self.pool.item_type_hash(item);
+        self.inner
+            .const_graph
+            .insert_node(hash, self.pool.item(item))
+    }
+
+    /// Record that the constant or constant function `from` depends on the
+    /// constant or constant function `to`, if the constant graph is enabled.
+    pub(crate) fn insert_const_dependency(
+        &mut self,
+        from: ItemId,
+        to: ItemId,
+    ) -> alloc::Result<()> {
+        if !self.options.const_graph {
+            return Ok(());
+        }
+
+        let from = self.const_graph_node(from)?;
+        let to = self.const_graph_node(to)?;
+        self.inner.const_graph.insert_edge(from, to)
+    }
+
+    /// Record that the constant or constant function `from` depends on the
+    /// constant with the given hash, if the constant graph is enabled.
+    ///
+    /// Constants which aren't items in the current compilation, like the ones
+    /// provided by the context, aren't recorded.
+    pub(crate) fn insert_const_dependency_hash(
+        &mut self,
+        from: ItemId,
+        to: Hash,
+    ) -> alloc::Result<()> {
+        if !self.options.const_graph {
+            return Ok(());
+        }
+
+        let Some(to) = self.inner.const_graph.node(to) else {
+            return Ok(());
+        };
+
+        let from = self.const_graph_node(from)?;
+        self.inner.const_graph.insert_edge(from, to)
+    }
+
+    /// Check that the given item isn't a constant or constant function which
+    /// is currently being processed, since using it would form a cycle.
+    pub(crate) fn check_const_cycle(
+        &self,
+        span: &dyn Spanned,
+        item: ItemId,
+    ) -> compile::Result<()> {
+        if self.consts.cycle(item).is_none() {
+            return Ok(());
+        }
+
+        Err(self.const_cycle_error(span, item)?)
+    }
+
+    /// Construct an error for the cycle which is closed by processing the given
+    /// constant or constant function.
+    pub(crate) fn const_cycle_error(
+        &self,
+        span: &dyn Spanned,
+        item: ItemId,
+    ) -> compile::Result<compile::Error> {
+        let cycle = self.consts.cycle(item).unwrap_or_default();
+        let mut path = Vec::try_with_capacity(cycle.len() + 1)?;
+
+        for &item in cycle.iter().chain([item].iter()) {
+            path.try_push(self.pool.item(item).try_to_owned()?)?;
+        }
+
+        Ok(compile::Error::new(
+            span,
+            IrErrorKind::ConstCycle {
+                path: path.try_into_boxed_slice()?,
+            },
+        ))
+    }
+
+    /// Construct an error for a call to the native function with the given
+    /// hash when pure constants are required.
+    pub(crate) fn impure_const_error(
+        &self,
+        span: &dyn Spanned,
+        hash: Hash,
+    ) -> compile::Result<compile::Error> {
+        let processing = self.consts.processing();
+        let mut path = Vec::try_with_capacity(processing.len())?;
+
+        for &item in processing {
+            path.try_push(self.pool.item(item).try_to_owned()?)?;
+        }
+
+        let item = self
+            .context
+            .lookup_meta_by_hash(hash)
+            .find_map(|meta| meta.item.as_deref());
+
+        let function = match item {
+            Some(item) => try_format!("{item}"),
+            None => try_format!("{hash}"),
+        };
+
+        Ok(compile::Error::new(
+            span,
+            IrErrorKind::ImpureConst {
+                path: path.try_into_boxed_slice()?,
+                function: function.try_into_boxed_str()?,
+            },
+        ))
+    }
+
+    /// Get the cached result of a native function called in a constant
+    /// context with the given arguments.
+    pub(crate) fn get_native_const_value(
+        &self,
+        key: Hash,
+        args: &[ConstValue],
+    ) -> Option<&ConstValue> {
+        let (cached, value) = self.inner.native_constants.get(&key)?;
+
+        if cached.len() != args.len() || !cached.iter().zip(args).all(|(a, b)| a.is_identical(b)) {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    /// Cache the result of a native function called in a constant context with
+    /// the given arguments.
+    pub(crate) fn insert_native_const_value(
+        &mut self,
+        key: Hash,
+        args: Vec<ConstValue>,
+        value: ConstValue,
+    ) -> alloc::Result<()> {
+        self.inner.native_constants.try_insert(key, (args, value))?;
+        Ok(())
+    }
+
+    /// Get the memoized result of a pure call to a constant function with the
+    /// given arguments.
+    pub(crate) fn get_const_fn_value(&self, key: Hash, args: &[ConstValue]) -> Option<&ConstValue> {
+        let (memo, value) = self.inner.const_fn_results.get(&key)?;
+
+        if memo.len() != args.len() || !memo.iter().zip(args).all(|(a, b)| a.is_identical(b)) {
+            return None;
+        }
+
+        Some(value)
+    }
+
+    /// Memoize the result of a pure call to a constant function with the given
+    /// arguments.
+    pub(crate) fn insert_const_fn_value(
+        &mut self,
+        key: Hash,
+        args: Vec<ConstValue>,
+        value: ConstValue,
+    ) -> alloc::Result<()> {
+        self.inner.const_fn_results.try_insert(key, (args, value))?;
+        Ok(())
+    }
+
     /// Insert captures.
     pub(crate) fn insert_captures<'hir, C>(&mut self, hash: Hash, captures: C) -> alloc::Result<()>
     where
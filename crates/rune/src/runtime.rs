@@ -24,8 +24,12 @@ pub use self::bytes::Bytes;
 mod call;
 pub use self::call::Call;
 
+mod const_diff;
+pub use self::const_diff::{diff_consts, ConstDiff};
+
 mod const_value;
-pub use self::const_value::ConstValue;
+pub(crate) use self::const_value::{const_kind_handler, ConstKindHandler};
+pub use self::const_value::{ConstExtension, ConstKind, ConstOp, ConstValue};
 
 pub mod debug;
 pub use self::debug::{DebugInfo, DebugInst};
@@ -82,7 +86,9 @@ mod protocol;
 pub use self::protocol::Protocol;
 
 mod protocol_caller;
-pub(crate) use self::protocol_caller::{EnvProtocolCaller, ProtocolCaller};
+pub(crate) use self::protocol_caller::{
+    EnvProtocolCaller, ProtocolCaller, UnsupportedProtocolCaller,
+};
 
 mod range_from;
 pub use self::range_from::RangeFrom;
@@ -0,0 +1,90 @@
+use crate::alloc::prelude::*;
+use crate::alloc::{self, BTreeMap, Vec};
+use crate::compile::ItemBuf;
+use crate::runtime::ConstValue;
+
+/// The difference between two sets of constants, as produced by
+/// [`diff_consts`].
+///
+/// Every list of items is sorted, so the difference is deterministic
+/// regardless of the order in which constants were produced.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ConstDiff {
+    /// Items which only exist in the new set of constants.
+    pub added: Vec<ItemBuf>,
+    /// Items which only exist in the old set of constants.
+    pub removed: Vec<ItemBuf>,
+    /// Items which exist in both sets of constants, but whose values differ.
+    pub changed: Vec<ItemBuf>,
+}
+
+impl ConstDiff {
+    /// Test if the two sets of constants are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare two sets of constants, such as the ones produced by
+/// [`Build::build_consts`] for two different compilations.
+///
+/// Values are compared through their [`ConstValue::content_hash`], so the
+/// fields of objects are compared independently of their order.
+///
+/// [`Build::build_consts`]: crate::Build::build_consts
+///
+/// # Examples
+///
+/// ```
+/// use rune::Sources;
+/// use rune::runtime::diff_consts;
+///
+/// let mut sources = Sources::new();
+/// sources.insert(rune::Source::memory("const A = 1; const B = 2;")?)?;
+/// let old = rune::prepare(&mut sources).build_consts()?;
+///
+/// let mut sources = Sources::new();
+/// sources.insert(rune::Source::memory("const A = 1; const B = 3; const C = 4;")?)?;
+/// let new = rune::prepare(&mut sources).build_consts()?;
+///
+/// let diff = diff_consts(&old, &new)?;
+/// assert_eq!(diff.added.len(), 1);
+/// assert_eq!(diff.added[0].to_string(), "C");
+/// assert!(diff.removed.is_empty());
+/// assert_eq!(diff.changed.len(), 1);
+/// assert_eq!(diff.changed[0].to_string(), "B");
+/// # Ok::<_, rune::support::Error>(())
+/// ```
+pub fn diff_consts(
+    old: &[(ItemBuf, ConstValue)],
+    new: &[(ItemBuf, ConstValue)],
+) -> alloc::Result<ConstDiff> {
+    let mut old_hashes = BTreeMap::new();
+
+    for (item, value) in old {
+        old_hashes.try_insert(item, value.content_hash())?;
+    }
+
+    let mut diff = ConstDiff::default();
+
+    for (item, value) in new {
+        match old_hashes.remove(item) {
+            Some(hash) if hash != value.content_hash() => {
+                diff.changed.try_push(item.try_clone()?)?;
+            }
+            Some(..) => {}
+            None => {
+                diff.added.try_push(item.try_clone()?)?;
+            }
+        }
+    }
+
+    for (item, _) in old_hashes {
+        diff.removed.try_push(item.try_clone()?)?;
+    }
+
+    diff.added.sort_unstable();
+    diff.changed.sort_unstable();
+    Ok(diff)
+}
@@ -1,11 +1,18 @@
+use core::any;
+use core::fmt;
+
+use ::rust_alloc::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 
 use crate::alloc::prelude::*;
 use crate::alloc::{self, Box, HashMap, String, Vec};
+use crate::hash::{Hash, ParametersBuilder};
 use crate::runtime::{
-    self, Bytes, FromValue, Object, OwnedTuple, ToValue, TypeInfo, Value, ValueKind, VmErrorKind,
-    VmResult,
+    self, AnyObj, Bytes, FromValue, Object, OwnedTuple, ToValue, TypeInfo, TypeOf, Value,
+    ValueKind, VmErrorKind, VmResult,
 };
+use crate::Any;
 
 /// A constant value.
 #[derive(Debug, Deserialize, Serialize)]
@@ -34,6 +41,17 @@ pub enum ConstValue {
     Object(HashMap<String, ConstValue>),
     /// An option.
     Option(Option<Box<ConstValue>>),
+    /// A result.
+    Result(Result<Box<ConstValue>, Box<ConstValue>>),
+    /// A value of a host type which has been registered as a constant kind
+    /// through [`TypeMut::const_kind`].
+    ///
+    /// These can only be used in constant contexts, so they are never stored in
+    /// a unit and can't be serialized.
+    ///
+    /// [`TypeMut::const_kind`]: crate::module::TypeMut::const_kind
+    #[serde(skip)]
+    Extension(ConstExtension),
 }
 
 impl ConstValue {
@@ -56,6 +74,10 @@ impl ConstValue {
                 Some(some) => Some(some.as_value()?),
                 None => None,
             })?,
+            Self::Result(result) => Value::try_from(match result {
+                Ok(ok) => Ok(ok.as_value()?),
+                Err(err) => Err(err.as_value()?),
+            })?,
             Self::Vec(vec) => {
                 let mut v = runtime::Vec::with_capacity(vec.len())?;
 
@@ -84,6 +106,7 @@ impl ConstValue {
 
                 Value::try_from(o)?
             }
+            Self::Extension(extension) => extension.value.to_value()?,
         })
     }
 
@@ -110,6 +133,210 @@ impl ConstValue {
             Self::Tuple(..) => TypeInfo::StaticType(crate::runtime::static_type::TUPLE_TYPE),
             Self::Object(..) => TypeInfo::StaticType(crate::runtime::static_type::OBJECT_TYPE),
             Self::Option(..) => TypeInfo::StaticType(crate::runtime::static_type::OPTION_TYPE),
+            Self::Result(..) => TypeInfo::StaticType(crate::runtime::static_type::RESULT_TYPE),
+            Self::Extension(extension) => extension.type_info(),
+        }
+    }
+
+    /// Get a human-readable name for the kind of the value, like `"integer"`
+    /// or `"object"`, suitable for use in diagnostics.
+    ///
+    /// Values of host types registered as constant kinds are all named
+    /// `"extension"`, use [`ConstValue::type_info`] to tell them apart.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::EmptyTuple => "unit",
+            Self::Byte(..) => "byte",
+            Self::Char(..) => "char",
+            Self::Bool(..) => "bool",
+            Self::Integer(..) => "integer",
+            Self::Float(..) => "float",
+            Self::String(..) => "string",
+            Self::Bytes(..) => "bytes",
+            Self::Vec(..) => "vector",
+            Self::Tuple(..) => "tuple",
+            Self::Object(..) => "object",
+            Self::Option(..) => "option",
+            Self::Result(..) => "result",
+            Self::Extension(..) => "extension",
+        }
+    }
+
+    /// Calculate a structural hash of the constant value.
+    ///
+    /// The hash only depends on the contents of the value, so it is stable
+    /// across runs and values which compare equal hash the same. The fields of
+    /// an object are hashed independently of their order.
+    pub fn content_hash(&self) -> Hash {
+        let mut builder = ParametersBuilder::new();
+        self.hash_into(&mut builder, false);
+        builder.finish()
+    }
+
+    /// Calculate a structural hash of the constant value where floats are
+    /// hashed by their bit pattern.
+    ///
+    /// Unlike [`ConstValue::content_hash`], this distinguishes between values
+    /// like `0.0` and `-0.0` which compare equal but behave differently.
+    pub(crate) fn exact_hash(&self) -> Hash {
+        let mut builder = ParametersBuilder::new();
+        self.hash_into(&mut builder, true);
+        builder.finish()
+    }
+
+    /// Test if the constant value is identical to another, which is the case if
+    /// they can be used interchangeably.
+    ///
+    /// Floats are compared by their bit pattern, and values of host types are
+    /// never identical since they can't be compared.
+    pub(crate) fn is_identical(&self, other: &ConstValue) -> bool {
+        match (self, other) {
+            (Self::EmptyTuple, Self::EmptyTuple) => true,
+            (Self::Byte(a), Self::Byte(b)) => a == b,
+            (Self::Char(a), Self::Char(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Integer(a), Self::Integer(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a.to_bits() == b.to_bits(),
+            (Self::String(a), Self::String(b)) => a.as_str() == b.as_str(),
+            (Self::Bytes(a), Self::Bytes(b)) => a.as_slice() == b.as_slice(),
+            (Self::Vec(a), Self::Vec(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.is_identical(b))
+            }
+            (Self::Tuple(a), Self::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.is_identical(b))
+            }
+            (Self::Object(a), Self::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| a.is_identical(b)))
+            }
+            (Self::Option(a), Self::Option(b)) => match (a, b) {
+                (Some(a), Some(b)) => a.is_identical(b),
+                (None, None) => true,
+                _ => false,
+            },
+            (Self::Result(a), Self::Result(b)) => match (a, b) {
+                (Ok(a), Ok(b)) | (Err(a), Err(b)) => a.is_identical(b),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Test if the constant value contains a value of a host type, which can
+    /// only be used in constant contexts.
+    pub(crate) fn has_extension(&self) -> bool {
+        match self {
+            Self::Extension(..) => true,
+            Self::Vec(values) => values.iter().any(Self::has_extension),
+            Self::Tuple(values) => values.iter().any(Self::has_extension),
+            Self::Object(object) => object.values().any(Self::has_extension),
+            Self::Option(Some(value)) => value.has_extension(),
+            Self::Result(Ok(value) | Err(value)) => value.has_extension(),
+            _ => false,
+        }
+    }
+
+    fn hash_into(&self, builder: &mut ParametersBuilder, exact: bool) {
+        match self {
+            Self::EmptyTuple => {
+                builder.add(0u8);
+            }
+            Self::Byte(b) => {
+                builder.add(1u8);
+                builder.add(*b);
+            }
+            Self::Char(c) => {
+                builder.add(2u8);
+                builder.add(*c);
+            }
+            Self::Bool(b) => {
+                builder.add(3u8);
+                builder.add(*b);
+            }
+            Self::Integer(n) => {
+                builder.add(4u8);
+                builder.add(*n);
+            }
+            Self::Float(n) => {
+                builder.add(5u8);
+                // Positive and negative zero compare equal.
+                let n = if !exact && *n == 0.0 { 0.0 } else { *n };
+                builder.add(n.to_bits());
+            }
+            Self::String(string) => {
+                builder.add(6u8);
+                builder.add(string.as_str());
+            }
+            Self::Bytes(bytes) => {
+                builder.add(7u8);
+                builder.add(bytes.as_slice());
+            }
+            Self::Vec(vec) => {
+                builder.add(8u8);
+                builder.add(vec.len() as u64);
+
+                for value in vec {
+                    value.hash_into(builder, exact);
+                }
+            }
+            Self::Tuple(tuple) => {
+                builder.add(9u8);
+                builder.add(tuple.len() as u64);
+
+                for value in tuple.iter() {
+                    value.hash_into(builder, exact);
+                }
+            }
+            Self::Object(object) => {
+                builder.add(10u8);
+                builder.add(object.len() as u64);
+
+                // Entries are combined with a commutative operation so that
+                // the hash doesn't depend on iteration order.
+                let mut entries = 0u64;
+
+                for (key, value) in object {
+                    let mut entry = ParametersBuilder::new();
+                    entry.add(key.as_str());
+                    value.hash_into(&mut entry, exact);
+                    entries = entries.wrapping_add(entry.finish().into_inner());
+                }
+
+                builder.add(entries);
+            }
+            Self::Option(option) => {
+                builder.add(11u8);
+
+                match option {
+                    Some(some) => {
+                        builder.add(true);
+                        some.hash_into(builder, exact);
+                    }
+                    None => {
+                        builder.add(false);
+                    }
+                }
+            }
+            Self::Extension(extension) => {
+                builder.add(12u8);
+                builder.add(extension.type_hash());
+                builder.add(extension.value.content_hash());
+            }
+            Self::Result(result) => {
+                builder.add(13u8);
+
+                match result {
+                    Ok(ok) => {
+                        builder.add(true);
+                        ok.hash_into(builder, exact);
+                    }
+                    Err(err) => {
+                        builder.add(false);
+                        err.hash_into(builder, exact);
+                    }
+                }
+            }
         }
     }
 }
@@ -129,6 +356,11 @@ impl TryClone for ConstValue {
             ConstValue::Tuple(value) => ConstValue::Tuple(value.try_clone()?),
             ConstValue::Object(value) => ConstValue::Object(value.try_clone()?),
             ConstValue::Option(value) => ConstValue::Option(value.try_clone()?),
+            ConstValue::Result(value) => ConstValue::Result(match value {
+                Ok(ok) => Ok(ok.try_clone()?),
+                Err(err) => Err(err.try_clone()?),
+            }),
+            ConstValue::Extension(value) => ConstValue::Extension(value.clone()),
         })
     }
 }
@@ -147,6 +379,10 @@ impl FromValue for ConstValue {
                 Some(some) => Some(vm_try!(Box::try_new(vm_try!(Self::from_value(some))))),
                 None => None,
             }),
+            ValueKind::Result(result) => Self::Result(match result {
+                Ok(ok) => Ok(vm_try!(Box::try_new(vm_try!(Self::from_value(ok))))),
+                Err(err) => Err(vm_try!(Box::try_new(vm_try!(Self::from_value(err))))),
+            }),
             ValueKind::Bytes(b) => Self::Bytes(b),
             ValueKind::Vec(vec) => {
                 let mut const_vec = vm_try!(Vec::try_with_capacity(vec.len()));
@@ -189,3 +425,161 @@ impl ToValue for ConstValue {
         VmResult::Ok(vm_try!(ConstValue::as_value(&self)))
     }
 }
+
+/// A binary operation which can be folded by a [`ConstKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConstOp {
+    /// Addition, `a + b`.
+    Add,
+    /// Subtraction, `a - b`.
+    Sub,
+    /// Multiplication, `a * b`.
+    Mul,
+    /// Division, `a / b`.
+    Div,
+    /// Less than, `a < b`.
+    Lt,
+    /// Less than or equal, `a <= b`.
+    Lte,
+    /// Equality, `a == b`.
+    Eq,
+    /// Greater than, `a > b`.
+    Gt,
+    /// Greater than or equal, `a >= b`.
+    Gte,
+}
+
+/// A host type which can be used as a value in constant contexts.
+///
+/// Once registered through [`TypeMut::const_kind`], values of the type which
+/// are produced by native functions marked as constant can be stored in
+/// constants, and binary operations where the value is on the left-hand side
+/// are folded through [`ConstKind::fold`].
+///
+/// Such values can only be used in constant contexts. Constants holding them
+/// are not stored in the compiled unit, and referencing them from runtime code
+/// is a compile error.
+///
+/// [`TypeMut::const_kind`]: crate::module::TypeMut::const_kind
+pub trait ConstKind: Any + TypeOf + Clone + fmt::Debug + Send + Sync {
+    /// Calculate a hash of the contents of the value.
+    ///
+    /// This is used to cache calls to constant native functions, so values
+    /// which are equal must hash the same.
+    fn content_hash(&self) -> Hash;
+
+    /// Fold the binary operation `op` where this value is the left-hand side
+    /// and `rhs` is the right-hand side.
+    ///
+    /// Returning `None` indicates that the operation is not supported in
+    /// constant contexts, which is what the default implementation does.
+    fn fold(&self, op: ConstOp, rhs: &ConstValue) -> VmResult<Option<ConstValue>> {
+        _ = (op, rhs);
+        VmResult::Ok(None)
+    }
+}
+
+/// Object safe wrapper around [`ConstKind`].
+trait DynConstKind: fmt::Debug + Send + Sync {
+    fn type_hash(&self) -> Hash;
+
+    fn type_info(&self) -> TypeInfo;
+
+    fn content_hash(&self) -> Hash;
+
+    fn fold(&self, op: ConstOp, rhs: &ConstValue) -> VmResult<Option<ConstValue>>;
+
+    fn to_value(&self) -> alloc::Result<Value>;
+
+    fn as_any(&self) -> &dyn any::Any;
+}
+
+impl<T> DynConstKind for T
+where
+    T: ConstKind,
+{
+    fn type_hash(&self) -> Hash {
+        <T as TypeOf>::type_hash()
+    }
+
+    fn type_info(&self) -> TypeInfo {
+        <T as TypeOf>::type_info()
+    }
+
+    fn content_hash(&self) -> Hash {
+        ConstKind::content_hash(self)
+    }
+
+    fn fold(&self, op: ConstOp, rhs: &ConstValue) -> VmResult<Option<ConstValue>> {
+        ConstKind::fold(self, op, rhs)
+    }
+
+    fn to_value(&self) -> alloc::Result<Value> {
+        Value::try_from(AnyObj::new(self.clone())?)
+    }
+
+    fn as_any(&self) -> &dyn any::Any {
+        self
+    }
+}
+
+/// A constant value of a host type implementing [`ConstKind`].
+#[derive(Clone)]
+pub struct ConstExtension {
+    value: Arc<dyn DynConstKind>,
+}
+
+impl ConstExtension {
+    /// Construct a new constant value of a host type.
+    pub fn new<T>(value: T) -> Self
+    where
+        T: ConstKind,
+    {
+        Self {
+            value: Arc::new(value),
+        }
+    }
+
+    /// Get the type hash of the wrapped value.
+    pub fn type_hash(&self) -> Hash {
+        self.value.type_hash()
+    }
+
+    /// Get the type information of the wrapped value.
+    pub fn type_info(&self) -> TypeInfo {
+        self.value.type_info()
+    }
+
+    /// Try to access the wrapped value as the given type.
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: ConstKind,
+    {
+        self.value.as_any().downcast_ref()
+    }
+
+    /// Fold the binary operation `op` with this value as the left-hand side.
+    pub(crate) fn fold(&self, op: ConstOp, rhs: &ConstValue) -> VmResult<Option<ConstValue>> {
+        self.value.fold(op, rhs)
+    }
+}
+
+impl fmt::Debug for ConstExtension {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.value, f)
+    }
+}
+
+/// Handler which converts a host value into a constant value.
+pub(crate) type ConstKindHandler = fn(&AnyObj) -> Option<ConstExtension>;
+
+/// Convert a host value of type `T` into a constant value.
+pub(crate) fn const_kind_handler<T>(any: &AnyObj) -> Option<ConstExtension>
+where
+    T: ConstKind,
+{
+    let value = any.downcast_borrow_ref::<T>().ok()?;
+    Some(ConstExtension::new(value.clone()))
+}
@@ -98,6 +98,36 @@ impl ProtocolCaller for EnvProtocolCaller {
     }
 }
 
+/// A protocol caller which treats every protocol as unsupported.
+///
+/// This is used where no virtual machine is available, like when evaluating
+/// constants, so that only built-in implementations of protocols are used.
+pub(crate) struct UnsupportedProtocolCaller;
+
+impl ProtocolCaller for UnsupportedProtocolCaller {
+    fn call_protocol_fn<A>(&mut self, protocol: Protocol, target: Value, _: A) -> VmResult<Value>
+    where
+        A: GuardedArgs,
+    {
+        VmResult::err(VmErrorKind::MissingProtocolFunction {
+            protocol,
+            instance: vm_try!(target.type_info()),
+        })
+    }
+
+    fn try_call_protocol_fn<A>(
+        &mut self,
+        _: Protocol,
+        target: Value,
+        _: A,
+    ) -> VmResult<CallResult<Value>>
+    where
+        A: GuardedArgs,
+    {
+        VmResult::Ok(CallResult::Unsupported(target))
+    }
+}
+
 impl ProtocolCaller for Vm {
     fn call_protocol_fn<A>(&mut self, protocol: Protocol, target: Value, args: A) -> VmResult<Value>
     where
@@ -49,10 +49,13 @@ pub(crate) static BYTE_TYPE: &StaticType = &StaticType {
 
 impl_static_type!(u8 => BYTE_TYPE);
 
+/// Hash for `::std::bool`.
+pub(crate) const BOOL_TYPE_HASH: Hash = ::rune_macros::hash!(::std::bool);
+
 /// The specialized type information for a bool type.
 pub(crate) static BOOL_TYPE: &StaticType = &StaticType {
     name: RawStr::from_str("bool"),
-    hash: ::rune_macros::hash!(::std::bool),
+    hash: BOOL_TYPE_HASH,
 };
 
 impl_static_type!(bool => BOOL_TYPE);
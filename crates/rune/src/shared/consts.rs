@@ -3,7 +3,7 @@
 //! This maps the item of a global constant to its value. It's also used to
 //! detect resolution cycles during constant evaluation.
 
-use crate::alloc::{self, HashMap, HashSet};
+use crate::alloc::{self, HashMap, Vec};
 use crate::compile::ItemId;
 use crate::runtime::ConstValue;
 
@@ -12,17 +12,40 @@ use crate::runtime::ConstValue;
 pub(crate) struct Consts {
     /// Const expression that have been resolved.
     resolved: HashMap<ItemId, ConstValue>,
-    /// Constant expressions being processed.
-    processing: HashSet<ItemId>,
+    /// Stack of constants and constant functions being processed, in the
+    /// order in which they started being processed.
+    processing: Vec<ItemId>,
 }
 
 impl Consts {
-    /// Mark that the given constant is being processed.
+    /// Mark that the given constant or constant function is being processed.
     ///
-    /// Returns `true` if the given constant hasn't been marked yet. This is
-    /// used to detect cycles during processing.
-    pub(crate) fn mark(&mut self, item: ItemId) -> alloc::Result<bool> {
-        self.processing.try_insert(item)
+    /// Every mark must be followed by a call to [`Consts::unmark`] once
+    /// processing is done. Use [`Consts::cycle`] to check if marking the item
+    /// would form a cycle.
+    pub(crate) fn mark(&mut self, item: ItemId) -> alloc::Result<()> {
+        self.processing.try_push(item)
+    }
+
+    /// Unmark the item which was most recently marked as being processed.
+    pub(crate) fn unmark(&mut self) {
+        self.processing.pop();
+    }
+
+    /// Get the stack of constants and constant functions being processed,
+    /// starting with the one which started being processed first.
+    pub(crate) fn processing(&self) -> &[ItemId] {
+        &self.processing
+    }
+
+    /// Get the chain of items which forms a cycle if the given item is
+    /// processed, starting with the item itself.
+    pub(crate) fn cycle(&self, item: ItemId) -> Option<&[ItemId]> {
+        let start = self
+            .processing
+            .iter()
+            .position(|current| *current == item)?;
+        Some(&self.processing[start..])
     }
 
     /// Get the value for the constant at the given item, if present.
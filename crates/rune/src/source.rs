@@ -7,11 +7,9 @@
 //!
 //! [`Sources`]: crate::sources::Sources
 
-#[cfg(feature = "emit")]
 use core::cmp;
 use core::fmt;
 use core::iter;
-#[cfg(feature = "emit")]
 use core::ops::Range;
 use core::slice;
 
@@ -22,7 +20,6 @@ use crate::alloc::path::Path;
 use crate::alloc::prelude::*;
 use crate::alloc::{self, Box};
 
-#[cfg(feature = "emit")]
 use crate::ast::Span;
 
 /// Error raised when constructing a source.
@@ -246,7 +243,6 @@ impl Source {
     }
 
     /// Get the range corresponding to the given line index.
-    #[cfg(feature = "emit")]
     pub(crate) fn line_range(&self, line_index: usize) -> Option<Range<usize>> {
         let line_start = self.line_start(line_index)?;
         let next_line_start = self.line_start(line_index.saturating_add(1))?;
@@ -259,6 +255,45 @@ impl Source {
         self.line_starts.len()
     }
 
+    /// Resolve the line and column positions of the given span, together with
+    /// the text of the line that it starts on.
+    ///
+    /// Spans which cross multiple lines resolve to the line that they start
+    /// on, with the end position on a later line. Returns `None` if the span is
+    /// out of bounds or doesn't fall on character boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::Source;
+    /// use rune::ast::Span;
+    ///
+    /// let source = Source::memory("const A = 1;\nconst B = A\n    + 2;")?;
+    /// let location = source.location(Span::new(23, 32)).expect("valid span");
+    ///
+    /// assert_eq!((location.line, location.column), (1, 10));
+    /// assert_eq!((location.end_line, location.end_column), (2, 7));
+    /// assert_eq!(location.text, "const B = A");
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn location(&self, span: Span) -> Option<SourceLocation<'_>> {
+        let range = span.range();
+        self.source.get(range.clone())?;
+
+        let (line, column) = self.pos_to_utf8_linecol(range.start);
+        let (end_line, end_column) = self.pos_to_utf8_linecol(range.end);
+        let text = self.source.get(self.line_range(line)?)?;
+        let text = text.trim_end_matches(['\n', '\r']);
+
+        Some(SourceLocation {
+            line,
+            column,
+            end_line,
+            end_column,
+            text,
+        })
+    }
+
     /// Access the line number of content that starts with the given span.
     #[cfg(feature = "emit")]
     pub(crate) fn line(&self, span: Span) -> Option<(usize, usize, [&str; 3])> {
@@ -295,7 +330,6 @@ impl Source {
         (line, offset, rest)
     }
 
-    #[cfg(feature = "emit")]
     fn line_start(&self, line_index: usize) -> Option<usize> {
         match line_index.cmp(&self.line_starts.len()) {
             cmp::Ordering::Less => self.line_starts.get(line_index).copied(),
@@ -309,6 +343,26 @@ impl Source {
     }
 }
 
+/// The resolved location of a span in a [`Source`].
+///
+/// Lines and columns are zero-based, and columns are counted in characters.
+///
+/// See [`Source::location`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SourceLocation<'a> {
+    /// The line the span starts on.
+    pub line: usize,
+    /// The column the span starts on.
+    pub column: usize,
+    /// The line the span ends on.
+    pub end_line: usize,
+    /// The column the span ends on.
+    pub end_column: usize,
+    /// The text of the line the span starts on, without its line ending.
+    pub text: &'a str,
+}
+
 impl fmt::Debug for Source {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Source")
@@ -6,7 +6,7 @@ use crate::alloc::path::Path;
 use crate::alloc::prelude::*;
 use crate::alloc::{self, Vec};
 use crate::ast::Span;
-use crate::source::Source;
+use crate::source::{Source, SourceLocation};
 #[cfg(feature = "codespan-reporting")]
 use codespan_reporting::files;
 
@@ -91,6 +91,29 @@ impl Sources {
         self.sources.get(id.into_index())
     }
 
+    /// Resolve the line and column positions of the given span in the source
+    /// with the given id.
+    ///
+    /// See [`Source::location`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rune::{Sources, Source};
+    /// use rune::ast::Span;
+    ///
+    /// let mut sources = Sources::new();
+    /// let id = sources.insert(Source::new("<memory>", "pub fn main() {\n    10\n}")?)?;
+    ///
+    /// let location = sources.location(id, Span::new(20, 22)).expect("valid span");
+    /// assert_eq!((location.line, location.column), (1, 4));
+    /// assert_eq!(location.text, "    10");
+    /// # Ok::<_, rune::support::Error>(())
+    /// ```
+    pub fn location(&self, id: SourceId, span: Span) -> Option<SourceLocation<'_>> {
+        self.sources.get(id.into_index())?.location(span)
+    }
+
     /// Fetch name for the given source id.
     pub(crate) fn name(&self, id: SourceId) -> Option<&str> {
         let source = self.sources.get(id.into_index())?;
@@ -44,9 +44,12 @@ use ::rust_alloc::sync::Arc;
 use anyhow::{Context as _, Error, Result};
 
 use crate::alloc;
-use crate::compile::{IntoComponent, ItemBuf};
+use crate::compile::{self, IntoComponent, ItemBuf};
+use crate::diagnostics::{Diagnostic, FatalDiagnosticKind};
 use crate::runtime::{Args, VmError};
-use crate::{termcolor, BuildError, Context, Diagnostics, FromValue, Source, Sources, Unit, Vm};
+use crate::{
+    termcolor, BuildError, Context, Diagnostics, FromValue, Options, Source, Sources, Unit, Vm,
+};
 
 /// An error that can be raised during testing.
 #[derive(Debug)]
@@ -303,6 +306,37 @@ macro_rules! assert_parse {
     }};
 }
 
+/// Compile the given source with a custom context and options, expecting it to
+/// fail, and return the first compile error raised.
+#[doc(hidden)]
+pub fn compile_error(source: &str, context: &Context, options: &Options) -> compile::Error {
+    let mut sources = sources(source);
+    let mut diagnostics = Diagnostics::new();
+
+    let result = crate::prepare(&mut sources)
+        .with_context(context)
+        .with_options(options)
+        .with_diagnostics(&mut diagnostics)
+        .build();
+
+    assert!(result.is_err(), "expected compilation to fail");
+    first_compile_error(diagnostics)
+}
+
+/// Get the first compile error out of the given diagnostics.
+#[doc(hidden)]
+pub fn first_compile_error(diagnostics: Diagnostics) -> compile::Error {
+    let Some(Diagnostic::Fatal(error)) = diagnostics.into_diagnostics().into_iter().next() else {
+        panic!("expected fatal diagnostic");
+    };
+
+    let FatalDiagnosticKind::CompileError(error) = error.into_kind() else {
+        panic!("expected compile error");
+    };
+
+    error
+}
+
 /// Assert that the given rune program raises a query error.
 macro_rules! assert_errors {
     ($source:expr, $span:pat, $($pat:pat $(=> $cond:expr)?),+ $(,)?) => {{
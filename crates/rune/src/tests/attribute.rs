@@ -210,3 +210,44 @@ fn deny_expr_attributes() {
         }
     };
 }
+
+#[test]
+fn visit_rejected_attributes() -> Result<()> {
+    use crate::SourceId;
+    use ast::Spanned;
+
+    #[derive(Default)]
+    struct RejectedVisitor {
+        rejected: Vec<(ast::Span, ast::Span, String)>,
+    }
+
+    impl compile::CompileVisitor for RejectedVisitor {
+        fn visit_rejected_attribute(
+            &mut self,
+            _: SourceId,
+            item_span: &dyn Spanned,
+            attr_span: &dyn Spanned,
+            name: &str,
+        ) -> Result<(), compile::MetaError> {
+            self.rejected
+                .push((item_span.span(), attr_span.span(), name.to_owned()));
+            Ok(())
+        }
+    }
+
+    let mut sources = crate::tests::sources("#[struct_attribute] struct Struct {}");
+    let mut diagnostics = Diagnostics::new();
+    let mut visitor = RejectedVisitor::default();
+
+    let result = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .with_visitor(&mut visitor)?
+        .build();
+
+    assert!(result.is_err());
+    assert_eq!(
+        visitor.rejected,
+        [(span!(0, 36), span!(0, 19), String::from("struct_attribute"))]
+    );
+    Ok(())
+}
@@ -6,8 +6,33 @@ use ErrorKind::*;
 fn test_use_variant_as_type() {
     assert_errors! {
         r#"pub fn main() { Err(0) is Err }"#,
-        span!(26, 29), ExpectedMeta { meta, .. } => {
+        span!(26, 29), ExpectedMeta { meta, needed_at, .. } => {
             assert_eq!(meta.to_string(), "variant ::std::result::Result::Err");
+            assert_eq!(needed_at, Some(span!(23, 25)));
+        }
+    };
+}
+
+#[test]
+fn test_value_needed_at() {
+    assert_errors! {
+        r#"mod m {} pub fn main() { let x = m; }"#,
+        span!(33, 34), ExpectedMeta { needed_at, .. } => {
+            assert_eq!(needed_at, Some(span!(25, 28)));
+        }
+    };
+
+    assert_errors! {
+        r#"mod m {} pub fn main() { return m; }"#,
+        span!(32, 33), ExpectedMeta { needed_at, .. } => {
+            assert_eq!(needed_at, Some(span!(25, 31)));
+        }
+    };
+
+    assert_errors! {
+        r#"mod m {} fn f(x) { x } pub fn main() { let x = f(m); }"#,
+        span!(49, 50), ExpectedMeta { needed_at, .. } => {
+            assert_eq!(needed_at, None);
         }
     };
 }
@@ -76,3 +101,47 @@ fn test_bad_struct_declaration() {
         span!(20, 23), UnsupportedArgumentCount { expected: 0, actual: 1, .. }
     };
 }
+
+#[test]
+fn visit_parsed_items() -> Result<()> {
+    use crate::SourceId;
+
+    #[derive(Default)]
+    struct Outline {
+        items: Vec<(ast::Span, bool)>,
+    }
+
+    impl compile::ParseVisitor for Outline {
+        fn visit_item(
+            &mut self,
+            _: SourceId,
+            item: &ast::Item,
+            span: ast::Span,
+        ) -> Result<(), compile::MetaError> {
+            self.items
+                .push((span, matches!(item, ast::Item::MacroCall(..))));
+            Ok(())
+        }
+    }
+
+    let mut sources = crate::tests::sources("fn a() {} struct B {} missing! {}");
+    let mut diagnostics = Diagnostics::new();
+    let mut outline = Outline::default();
+
+    let result = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .with_parse_visitor(&mut outline)
+        .build();
+
+    // The macro call is visited even though it can't be expanded.
+    assert!(result.is_err());
+    assert_eq!(
+        outline.items,
+        [
+            (span!(0, 9), false),
+            (span!(10, 21), false),
+            (span!(22, 33), true)
+        ]
+    );
+    Ok(())
+}
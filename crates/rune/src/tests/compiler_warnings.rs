@@ -25,3 +25,170 @@ fn test_remove_variant_parens() {
         span!(20, 22), RemoveTupleCallParams { variant: span!(16, 20), .. }
     };
 }
+
+#[test]
+fn test_const_statement_unused() {
+    assert_warnings! {
+        r#"const VALUE = { LEN; 1 }; const LEN = 2; pub fn main() { VALUE }"#,
+        span!(16, 19), ConstStatementUnused { context: Some(span!(14, 24)), .. }
+    };
+}
+
+#[test]
+fn test_const_fn_argument_warnings() -> Result<()> {
+    let mut diagnostics = Diagnostics::new();
+    let _ = crate::tests::compile_helper(
+        r#"const fn id(x) { x } pub fn main() { id({ 1; 2 }) }"#,
+        &mut diagnostics,
+    )?;
+
+    let groups = diagnostics.const_warnings()?;
+    let items = groups
+        .keys()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(items, ["id"]);
+    Ok(())
+}
+
+#[test]
+fn test_non_exhaustive_const_match() -> Result<()> {
+    assert_warnings! {
+        r#"enum Color { Red, Green, Blue } const NAME = match 1 { Color::Red => "red", _ if true => "other" }; pub fn main() { NAME }"#,
+        span!(51, 52), NonExhaustiveConstMatch { missing, context: Some(span!(45, 98)), .. } => {
+            let missing = missing.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+            assert_eq!(missing, ["Green", "Blue"]);
+        }
+    };
+
+    let mut diagnostics = Diagnostics::new();
+    let _ = crate::tests::compile_helper(
+        r#"enum Color { Red, Green } const NAME = match 1 { Color::Red => "red", Color::Green => "green", _ => "other" }; pub fn main() { NAME }"#,
+        &mut diagnostics,
+    )?;
+    assert!(!diagnostics.has_warning());
+    Ok(())
+}
+
+#[test]
+fn test_const_coverage() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.const_coverage(true);
+
+    let mut sources =
+        crate::tests::sources("const A = [1].unknown(); const B = 2; pub fn main() { B }");
+    let mut diagnostics = Diagnostics::new();
+
+    let _ = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .with_options(&options)
+        .build()?;
+
+    {
+        let groups = diagnostics.const_warnings()?;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups.keys().next().map(|item| item.to_string()).as_deref(),
+            Some("A")
+        );
+    }
+
+    let not_const = diagnostics
+        .into_diagnostics()
+        .into_iter()
+        .filter_map(|diagnostic| match diagnostic {
+            diagnostics::Diagnostic::Warning(warning) => match warning.into_kind() {
+                NotConst { span, reason, .. } => Some((span, reason)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(not_const.len(), 1);
+    assert_eq!(not_const[0].0, span!(10, 23));
+    assert_eq!(
+        not_const[0].1.as_str(),
+        "Method not supported in constant contexts"
+    );
+
+    // Only constants which aren't constant are downgraded, other errors are
+    // still reported.
+    let error = crate::tests::compile_error(
+        r#"const A = panic("bad"); const B = 2; pub fn main() { B }"#,
+        &Context::with_default_modules()?,
+        &options,
+    );
+
+    assert_eq!(
+        error.const_error_kind(),
+        Some(crate::compile::ConstErrorKind::Panic)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_warning_suggestions() -> Result<()> {
+    let mut sources =
+        crate::tests::sources(r#"pub fn main() { let a = `Say "hi"\n`; let b = None(); (a, b) };"#);
+    let mut diagnostics = Diagnostics::new();
+
+    let _ = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .build()?;
+
+    let suggestions = diagnostics
+        .diagnostics()
+        .iter()
+        .filter_map(|diagnostic| match diagnostic {
+            diagnostics::Diagnostic::Warning(warning) => warning.suggestion(),
+            _ => None,
+        })
+        .map(|s| (s.span, s.replacement))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        suggestions,
+        [
+            (span!(62, 63), ""),
+            (span!(24, 36), r#""Say \"hi\"\n""#),
+            (span!(50, 52), ""),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn test_warnings_json() -> Result<()> {
+    let mut sources = crate::tests::sources(r#"pub fn main() { let a = `Hello`; a };"#);
+    let mut diagnostics = Diagnostics::new();
+
+    let _ = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .build()?;
+
+    let mut out = Vec::new();
+    diagnostics.write_warnings_json(&mut out)?;
+    let json: serde_json::Value = serde_json::from_slice(&out)?;
+
+    assert_eq!(
+        json,
+        serde_json::json!([
+            {
+                "kind": "UnnecessarySemiColon",
+                "source_id": 0,
+                "span": { "start": 36, "end": 37 },
+                "message": "Unnecessary semicolon",
+            },
+            {
+                "kind": "TemplateWithoutExpansions",
+                "source_id": 0,
+                "span": { "start": 24, "end": 31 },
+                "message": "Using a template string without expansions, like `Hello World`",
+            },
+        ])
+    );
+
+    Ok(())
+}
@@ -160,3 +160,72 @@ fn attribute_imports_builtin() -> Result<()> {
     assert_eq!(output, 42);
     Ok(())
 }
+
+#[test]
+fn visit_macro_expansion() -> Result<()> {
+    use crate::SourceId;
+
+    #[derive(Default)]
+    struct ExpansionVisitor {
+        expansions: Vec<(SourceId, ast::Span, Vec<&'static str>)>,
+    }
+
+    impl compile::CompileVisitor for ExpansionVisitor {
+        fn visit_macro_expansion(
+            &mut self,
+            source_id: SourceId,
+            call_span: ast::Span,
+            expanded: &[ast::Item],
+        ) -> Result<(), compile::MetaError> {
+            let kinds = expanded.iter().map(ast::Item::kind_name).collect();
+            self.expansions.push((source_id, call_span, kinds));
+            Ok(())
+        }
+    }
+
+    let mut m = Module::default();
+
+    m.macro_(["generate"], |cx, _| {
+        let stream = quote!(
+            const ONE = 1;
+            fn two() {
+                2
+            }
+        );
+
+        Ok(stream.into_token_stream(cx)?)
+    })?;
+
+    let mut context = Context::with_default_modules()?;
+    context.install(m)?;
+
+    let mut sources = sources! {
+        entry => {
+            generate!();
+
+            pub fn main() {
+                ONE + two()
+            }
+        }
+    };
+
+    let mut visitor = ExpansionVisitor::default();
+
+    let unit = prepare(&mut sources)
+        .with_context(&context)
+        .with_visitor(&mut visitor)?
+        .build()?;
+
+    let mut vm = Vm::new(Arc::new(context.runtime()?), Arc::new(unit));
+    let output: u32 = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(output, 3);
+
+    let [(source_id, span, kinds)] = &visitor.expansions[..] else {
+        panic!("expected one expansion, got {:?}", visitor.expansions);
+    };
+
+    assert_eq!(kinds, &["const", "fn"]);
+    let source = sources.get(*source_id).expect("missing source");
+    assert_eq!(source.get(span.range()), Some("generate!()"));
+    Ok(())
+}
@@ -1,5 +1,7 @@
 prelude!();
 
+use crate::ast::Spanned;
+
 macro_rules! test_op {
     ($ty:ty => $lhs:literal $op:tt $rhs:literal = $result:literal) => {{
         let program = format!(
@@ -264,3 +266,2528 @@ fn test_const_block() {
 
     assert_eq!(result, "Hello World");
 }
+
+#[test]
+fn test_const_set_operations() {
+    let out: Vec<i64> = rune!(const VALUE = [1, 2, 2, 3].union([3, 4, 1]); pub fn main() { VALUE });
+    assert_eq!(out, [1, 2, 3, 4]);
+
+    let out: Vec<i64> =
+        rune!(const VALUE = [3, 1, 2, 1].intersection([1, 3]); pub fn main() { VALUE });
+    assert_eq!(out, [3, 1]);
+
+    let out: Vec<i64> = rune!(const VALUE = [1, 2, 2, 3].difference([1]); pub fn main() { VALUE });
+    assert_eq!(out, [2, 3]);
+}
+
+#[test]
+fn test_const_repeat() {
+    let out: String = rune!(const VALUE = "-".repeat(4); pub fn main() { VALUE });
+    assert_eq!(out, "----");
+
+    let out: String = rune!(const VALUE = "ab".repeat(0); pub fn main() { VALUE });
+    assert_eq!(out, "");
+
+    assert_errors! {
+        r#"const VALUE = "-".repeat(-1); pub fn main() { VALUE }"#,
+        span!(25, 27), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Count must not be negative");
+        }
+    };
+}
+
+#[test]
+fn test_const_sum_product() {
+    let out: i64 = rune!(const SIZES = [1, 2, 3, 4]; const VALUE = SIZES.iter().sum(); pub fn main() { VALUE });
+    assert_eq!(out, 10);
+
+    let out: i64 = rune!(const VALUE = [1, 2, 3, 4].iter().product(); pub fn main() { VALUE });
+    assert_eq!(out, 24);
+
+    let out: f64 = rune!(const VALUE = [1.5, 2.5].iter().sum(); pub fn main() { VALUE });
+    assert_eq!(out, 4.0);
+
+    let out: i64 = rune!(const VALUE = [].iter().product(); pub fn main() { VALUE });
+    assert_eq!(out, 1);
+
+    assert_errors! {
+        r#"const VALUE = [9223372036854775807, 1].iter().sum(); pub fn main() { VALUE }"#,
+        span!(14, 51), ErrorKind::IrError(compile::IrErrorKind::IntegerOverflow)
+    };
+}
+
+#[test]
+fn test_const_fn_generics() {
+    let result: i64 = rune! {
+        const VALUE = identity::<i64>(5);
+        const fn identity(x) { x }
+
+        pub fn main() {
+            VALUE + identity::<i64>(1)
+        }
+    };
+
+    assert_eq!(result, 6);
+
+    assert_errors! {
+        r#"fn identity(x) { x } const VALUE = identity::<i64>(5); pub fn main() { VALUE }"#,
+        span!(35, 50), ErrorKind::MissingItemParameters { .. }
+    };
+}
+
+#[test]
+fn test_const_bits() {
+    let out: i64 =
+        rune!(const REG = 0b0001; const VALUE = set_bit(REG, 4); pub fn main() { VALUE });
+    assert_eq!(out, 0b10001);
+
+    let out: i64 = rune!(const VALUE = clear_bit(0b1011, 1); pub fn main() { VALUE });
+    assert_eq!(out, 0b1001);
+
+    let out: i64 = rune!(const VALUE = toggle_bit(toggle_bit(0, 63), 0); pub fn main() { VALUE });
+    assert_eq!(out, i64::MIN + 1);
+
+    let out: bool = rune!(const VALUE = test_bit(0b100, 2); pub fn main() { VALUE });
+    assert!(out);
+
+    assert_errors! {
+        r#"const VALUE = set_bit(1, 64); pub fn main() { VALUE }"#,
+        span!(25, 27), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Bit index 64 is out of range 0 to 63");
+        }
+    };
+}
+
+#[test]
+fn test_const_budget_exceeded_visitor() -> Result<()> {
+    use crate::SourceId;
+
+    #[derive(Default)]
+    struct BudgetVisitor {
+        exceeded: Vec<(String, usize)>,
+    }
+
+    impl compile::CompileVisitor for BudgetVisitor {
+        fn visit_budget_exceeded(
+            &mut self,
+            _: SourceId,
+            item: &Item,
+            _: ast::Span,
+            steps: usize,
+        ) -> Result<(), compile::MetaError> {
+            self.exceeded.push((item.to_string(), steps));
+            Ok(())
+        }
+    }
+
+    let mut sources = crate::tests::sources(
+        "const VALUE = { let n = 0; loop { n += 1; } }; pub fn main() { VALUE }",
+    );
+    let mut diagnostics = Diagnostics::new();
+    let mut visitor = BudgetVisitor::default();
+
+    let result = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .with_visitor(&mut visitor)?
+        .build();
+
+    assert!(result.is_err());
+    assert_eq!(visitor.exceeded, [(String::from("VALUE"), 1_000_000)]);
+    Ok(())
+}
+
+#[test]
+fn test_const_join() {
+    let out: String =
+        rune!(const ROW = ["a", "b", "c"]; const CSV = ROW.join(","); pub fn main() { CSV });
+    assert_eq!(out, "a,b,c");
+
+    let out: String = rune!(const VALUE = [].join(", "); pub fn main() { VALUE });
+    assert_eq!(out, "");
+
+    assert_errors! {
+        r#"const VALUE = ["a"].join(1); pub fn main() { VALUE }"#,
+        span!(25, 26), ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_value_content_hash() -> Result<()> {
+    use crate::alloc::{try_vec, HashMap};
+    use crate::runtime::ConstValue;
+
+    let mut a = HashMap::new();
+    a.try_insert("a".try_to_owned()?, ConstValue::Integer(1))?;
+    a.try_insert("b".try_to_owned()?, ConstValue::Float(-0.0))?;
+
+    let mut b = HashMap::new();
+    b.try_insert("b".try_to_owned()?, ConstValue::Float(0.0))?;
+    b.try_insert("a".try_to_owned()?, ConstValue::Integer(1))?;
+
+    let a = ConstValue::Object(a);
+    let b = ConstValue::Object(b);
+    assert_eq!(a.content_hash(), b.content_hash());
+
+    let vec = ConstValue::Vec(try_vec![ConstValue::Integer(1), ConstValue::Integer(2)]);
+    let tuple = ConstValue::Tuple(
+        try_vec![ConstValue::Integer(1), ConstValue::Integer(2)].try_into_boxed_slice()?,
+    );
+    assert_ne!(vec.content_hash(), tuple.content_hash());
+
+    let swapped = ConstValue::Vec(try_vec![ConstValue::Integer(2), ConstValue::Integer(1)]);
+    assert_ne!(vec.content_hash(), swapped.content_hash());
+    Ok(())
+}
+
+#[test]
+fn test_const_matches() {
+    let out: bool = rune! {
+        const X = 2;
+        const IS_TWO = matches(X, 2);
+        pub fn main() { IS_TWO }
+    };
+    assert_eq!(out, true);
+
+    let out: String = rune! {
+        const NAME = "rune";
+        const VALUE = if matches(NAME, "rust") { "rust" } else { "other" };
+        pub fn main() { VALUE }
+    };
+    assert_eq!(out, "other");
+
+    let out: bool = rune! {
+        enum Variant { A, B }
+        const IS_A = matches(1, Variant::A);
+        pub fn main() { IS_A }
+    };
+    assert_eq!(out, false);
+}
+
+#[test]
+fn test_const_native_fn() -> Result<()> {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::ast::Spanned;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn add(a: i64, b: i64) -> i64 {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        a + b
+    }
+
+    let mut module = Module::new();
+    module.function("add", add).build()?.const_fn(true);
+    module.function("sub", |a: i64, b: i64| a - b).build()?;
+
+    let out: i64 = rune_n! {
+        &module,
+        (),
+        i64 =>
+        const A = add(1, 2);
+        const B = add(1, 2);
+        pub fn main() { A + B }
+    };
+    assert_eq!(out, 6);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+    // Arguments which compare equal but behave differently are not cached
+    // together.
+    let mut recip = Module::new();
+    recip
+        .function("recip", |n: f64| 1.0 / n)
+        .build()?
+        .const_fn(true);
+
+    let out: (f64, f64) = rune_n! {
+        &recip,
+        (),
+        (f64, f64) =>
+        const A = recip(0.0);
+        const B = recip(-0.0);
+        pub fn main() { (A, B) }
+    };
+    assert_eq!(out, (f64::INFINITY, f64::NEG_INFINITY));
+
+    let mut context = Context::with_default_modules()?;
+    context.install(&module)?;
+
+    let error = crate::tests::compile_error(
+        "const A = sub(2, 1); pub fn main() { A }",
+        &context,
+        &crate::Options::default(),
+    );
+
+    assert_eq!(error.span(), span!(10, 19));
+    Ok(())
+}
+
+#[test]
+fn test_const_kind() -> Result<()> {
+    use crate::ast::Spanned;
+    use crate::runtime::{ConstExtension, ConstKind, ConstOp, ConstValue};
+
+    #[derive(Debug, Clone, Any)]
+    struct Seconds(i64);
+
+    impl ConstKind for Seconds {
+        fn content_hash(&self) -> Hash {
+            Hash::new(self.0 as u64)
+        }
+
+        fn fold(&self, op: ConstOp, rhs: &ConstValue) -> VmResult<Option<ConstValue>> {
+            let ConstValue::Extension(rhs) = rhs else {
+                return VmResult::Ok(None);
+            };
+
+            let Some(rhs) = rhs.downcast_ref::<Seconds>() else {
+                return VmResult::Ok(None);
+            };
+
+            VmResult::Ok(match op {
+                ConstOp::Add => Some(ConstValue::Extension(ConstExtension::new(Seconds(
+                    self.0 + rhs.0,
+                )))),
+                ConstOp::Gt => Some(ConstValue::Bool(self.0 > rhs.0)),
+                _ => None,
+            })
+        }
+    }
+
+    let mut module = Module::new();
+    module.ty::<Seconds>()?.const_kind()?;
+    module.function("seconds", Seconds).build()?.const_fn(true);
+
+    let out: bool = rune_n! {
+        &module,
+        (),
+        bool =>
+        const T = seconds(30) + seconds(15);
+        const LONG = T > seconds(40);
+        pub fn main() { LONG }
+    };
+    assert!(out);
+
+    let mut context = Context::with_default_modules()?;
+    context.install(&module)?;
+
+    // Host values can't be stored in the unit, so only the folded constant is.
+    let mut sources = crate::tests::sources(
+        "const T = [seconds(30)]; const LONG = T[0] > seconds(20); pub fn main() { LONG }",
+    );
+
+    let unit = prepare(&mut sources).with_context(&context).build()?;
+    assert!(unit.constant(Hash::type_hash(["T"])).is_none());
+    assert!(matches!(
+        unit.constant(Hash::type_hash(["LONG"])),
+        Some(ConstValue::Bool(true))
+    ));
+
+    let error = crate::tests::compile_error(
+        "const T = seconds(2); pub fn main() { T }",
+        &context,
+        &crate::Options::default(),
+    );
+
+    assert_eq!(error.span(), span!(38, 39));
+    assert_eq!(
+        error.to_string(),
+        "Constant values of type `Seconds` can only be used in constant contexts"
+    );
+
+    let error = crate::tests::compile_error(
+        "const A = seconds(2) - seconds(1); pub fn main() { A }",
+        &context,
+        &crate::Options::default(),
+    );
+
+    assert_eq!(error.span(), span!(10, 33));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::NotConst)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_const_chunks_windows() {
+    let out: Vec<Vec<i64>> = rune! {
+        const DATA = [1, 2, 3, 4, 5];
+        const PAGES = DATA.chunks(2).collect::<Vec>();
+        pub fn main() { PAGES }
+    };
+    assert_eq!(out, vec![vec![1, 2], vec![3, 4], vec![5]]);
+
+    let out: Vec<Vec<i64>> = rune! {
+        const DATA = [1, 2, 3];
+        const PAIRS = DATA.windows(2);
+        pub fn main() { PAIRS }
+    };
+    assert_eq!(out, vec![vec![1, 2], vec![2, 3]]);
+
+    let out: Vec<Vec<i64>> = rune! {
+        const PAIRS = [1].windows(2);
+        pub fn main() { PAIRS }
+    };
+    assert!(out.is_empty());
+
+    assert_errors! {
+        "const PAGES = [1, 2].chunks(0); pub fn main() { PAGES }",
+        span!(28, 29), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Chunk size must be non-zero");
+        }
+    };
+}
+
+#[test]
+fn test_const_reverse() {
+    let out: Vec<i64> = rune! {
+        const SEQ = [1, 2, 3];
+        const R = SEQ.reverse();
+        pub fn main() { R }
+    };
+    assert_eq!(out, vec![3, 2, 1]);
+
+    let out: String = rune! {
+        const R = "héllo wörld".reverse();
+        pub fn main() { R }
+    };
+    assert_eq!(out, "dlröw olléh");
+
+    assert_errors! {
+        "const R = true.reverse(); pub fn main() { R }",
+        span!(10, 14),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_time_const_eval() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.time_const_eval(true);
+
+    let mut sources = crate::tests::sources("const A = [1, 2].iter().sum(); pub fn main() { A }");
+    let unit = prepare(&mut sources).with_options(&options).build()?;
+
+    let mut vm = Vm::without_runtime(Arc::new(unit));
+    let out: i64 = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(out, 3);
+    Ok(())
+}
+
+#[test]
+fn test_const_clamp() {
+    let out: i64 = rune! {
+        const MIN = 1;
+        const MAX = 10;
+        const X = 42.clamp(MIN, MAX);
+        pub fn main() { X }
+    };
+    assert_eq!(out, 10);
+
+    let out: f64 = rune! {
+        const X = 2.5.clamp(0.0, 1.0);
+        pub fn main() { X }
+    };
+    assert_eq!(out, 1.0);
+
+    assert_errors! {
+        "const X = 5.clamp(10, 1); pub fn main() { X }",
+        span!(18, 23), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Lower bound must be less than or equal to the upper bound");
+        }
+    };
+}
+
+#[test]
+fn test_const_while_let() {
+    let out: i64 = rune! {
+        const SUM = {
+            let it = [1, 2, 3].iter();
+            let sum = 0;
+
+            while let Some(x) = it.next() {
+                sum += x;
+            }
+
+            sum
+        };
+
+        pub fn main() { SUM }
+    };
+    assert_eq!(out, 6);
+
+    assert_errors! {
+        "const A = { let it = 1; while let Some(x) = it.next() { } }; pub fn main() { A }",
+        span!(44, 46),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_radix() {
+    let out: ((String, String, String), (i64, i64)) = rune! {
+        const VALUE = 255;
+        const HEX = to_radix(VALUE, 16);
+        const BIN = to_radix(0 - 5, 2);
+        const ZERO = to_radix(0, 36);
+        const FF = from_radix("ff", 16);
+        const NEG = from_radix("-z", 36);
+        pub fn main() { ((HEX, BIN, ZERO), (FF, NEG)) }
+    };
+    assert_eq!(
+        out,
+        (
+            (String::from("ff"), String::from("-101"), String::from("0")),
+            (255, -35)
+        )
+    );
+
+    assert_errors! {
+        "const A = to_radix(10, 37); pub fn main() { A }",
+        span!(23, 25),
+        ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Radix 37 is out of range 2 to 36");
+        }
+    };
+
+    assert_errors! {
+        r#"const A = from_radix("12g", 16); pub fn main() { A }"#,
+        span!(21, 26),
+        ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Invalid digits for an integer in radix 16");
+        }
+    };
+}
+
+#[test]
+fn test_const_split() {
+    let out: (Vec<String>, Vec<String>, String) = rune! {
+        const PATH = "usr/local/bin";
+        const PARTS = PATH.split("/").collect::<Vec>();
+        const FIRST = PATH.splitn(2, "/");
+        const JOINED = PARTS.join("::");
+        pub fn main() { (PARTS, FIRST, JOINED) }
+    };
+    assert_eq!(out.0, ["usr", "local", "bin"]);
+    assert_eq!(out.1, ["usr", "local/bin"]);
+    assert_eq!(out.2, "usr::local::bin");
+
+    assert_errors! {
+        r#"const A = "a/b".split(""); pub fn main() { A }"#,
+        span!(22, 24),
+        ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Separator must not be empty");
+        }
+    };
+}
+
+#[test]
+fn test_const_object_from() {
+    let out: (i64, i64, usize) = rune! {
+        const PAIRS = [("a", 1), ("b", 2), ("a", 3)];
+        const CFG = Object::from(PAIRS);
+        pub fn main() { (CFG.a, CFG.b, CFG.len()) }
+    };
+    assert_eq!(out, (3, 2, 2));
+
+    assert_errors! {
+        r#"const A = Object::from([("a", 1), (2, 3)]); pub fn main() { A }"#,
+        span!(35, 36),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_string_tests() {
+    let out: (bool, bool, bool, bool) = rune! {
+        const NAME = "prefix_value";
+        const STARTS = NAME.starts_with("prefix_");
+        const ENDS = NAME.ends_with("_value");
+        const CONTAINS = NAME.contains("x_v");
+        const MISSING = NAME.contains("suffix");
+        pub fn main() { (STARTS, ENDS, CONTAINS, MISSING) }
+    };
+    assert_eq!(out, (true, true, true, false));
+
+    assert_errors! {
+        r#"const A = "abc".starts_with(1); pub fn main() { A }"#,
+        span!(28, 29),
+        ErrorKind::VmError(..)
+    };
+
+    let out: (bool, bool) = rune! {
+        const FOUND = [1, 2, 3].contains(2);
+        const MISSING = [1, 2, 3].contains(4);
+        pub fn main() { (FOUND, MISSING) }
+    };
+    assert_eq!(out, (true, false));
+
+    assert_errors! {
+        "const A = #{a: 1}.contains(1); pub fn main() { A }",
+        span!(10, 29),
+        ErrorKind::IrError(compile::IrErrorKind::NotConst)
+    };
+}
+
+#[test]
+fn test_const_find_position() {
+    let out: (Option<i64>, Option<i64>, Option<String>) = rune! {
+        const NAMES = ["first", "target", "last"];
+        const IDX = NAMES.position("target");
+        const MISSING = NAMES.position("other");
+        const FOUND = NAMES.find("last");
+        pub fn main() { (IDX, MISSING, FOUND) }
+    };
+    assert_eq!(out, (Some(1), None, Some(String::from("last"))));
+
+    assert_errors! {
+        r#"const A = "abc".position("b"); pub fn main() { A }"#,
+        span!(10, 15),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_when() -> Result<()> {
+    let source = r#"
+    #[when("gpu")]
+    const BACKEND = "vulkan";
+    #[when("cpu")]
+    const BACKEND = "software";
+    pub fn main() { BACKEND }
+    "#;
+
+    for (flag, expected) in [("gpu", "vulkan"), ("cpu", "software")] {
+        let mut options = crate::Options::default();
+        options.when_flag(flag);
+
+        let mut sources = crate::tests::sources(source);
+        let unit = prepare(&mut sources).with_options(&options).build()?;
+
+        let mut vm = Vm::without_runtime(Arc::new(unit));
+        let out: String = from_value(vm.call(["main"], ())?)?;
+        assert_eq!(out, expected);
+    }
+
+    let mut sources = crate::tests::sources(source);
+    assert!(prepare(&mut sources).build().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_const_tuple_vec_conversions() {
+    let out: (Vec<i64>, (i64, i64, i64), (i64, i64)) = rune! {
+        const TUP = (1, 2, 3);
+        const V = TUP.to_vec();
+        const T = V.to_tuple();
+        const SAME = (4, 5).to_tuple();
+        pub fn main() { (V, T, SAME) }
+    };
+    assert_eq!(out, (vec![1, 2, 3], (1, 2, 3), (4, 5)));
+
+    assert_errors! {
+        "const A = 1.to_vec(); pub fn main() { A }",
+        span!(10, 11),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_max_collection_len() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.const_max_collection_len(8);
+
+    let mut sources = crate::tests::sources(r#"const A = "ab".repeat(4); pub fn main() { A }"#);
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let cases = [
+        (
+            r#"const A = "ab".repeat(5); pub fn main() { A }"#,
+            span!(10, 24),
+        ),
+        (
+            r#"const A = { let s = "ab"; while true { s = s + s; } s }; pub fn main() { A }"#,
+            span!(43, 48),
+        ),
+        (
+            "const A = [1, 2, 3, 4, 5, 6, 7, 8, 9]; pub fn main() { A }",
+            span!(10, 37),
+        ),
+        (
+            "const fn build(n) { let v = []; let i = 0; while i < n { v.push(i); i += 1; } v } const A = build(9); pub fn main() { A }",
+            span!(57, 66),
+        ),
+        (
+            "const A = [1, 2, 3, 4, 5].union([6, 7, 8, 9]); pub fn main() { A }",
+            span!(10, 45),
+        ),
+    ];
+
+    for (source, expected) in cases {
+        let error = crate::tests::compile_error(source, &Context::new(), &options);
+
+        assert_eq!(error.span(), expected, "{source}");
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::IrError(compile::IrErrorKind::CollectionTooLarge { max: 8 })
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_const_abs_signum() {
+    let out: ((i64, i64, i64, i64), (f64, f64)) = rune! {
+        const DELTA = 0 - 5;
+        const ABS = DELTA.abs();
+        const S = DELTA.signum();
+        const ZERO = 0.signum();
+        const POS = 7.signum();
+        const FABS = (0.0 - 2.5).abs();
+        const FS = (0.0 - 2.5).signum();
+        pub fn main() { ((ABS, S, ZERO, POS), (FABS, FS)) }
+    };
+    assert_eq!(out, ((5, -1, 0, 1), (2.5, -1.0)));
+
+    assert_errors! {
+        r#"const A = "abc".abs(); pub fn main() { A }"#,
+        span!(10, 15),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_let_type_ascription() {
+    let out: (i64, String, f64) = rune! {
+        const VALUE = {
+            let a: i64 = 1 + 2;
+            let b: String = "hello";
+            let c: f64 = 2.5;
+            (a, b, c)
+        };
+        pub fn main() { VALUE }
+    };
+    assert_eq!(out, (3, String::from("hello"), 2.5));
+
+    assert_errors! {
+        r#"const A = { let x: i64 = "abc"; x }; pub fn main() { A }"#,
+        span!(25, 30), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Expected a value of type `::std::i64` but got `String`");
+        }
+    };
+}
+
+#[test]
+fn test_const_option_closures() {
+    let out: (
+        (Option<i64>, Option<i64>),
+        (Option<i64>, Option<i64>, Option<i64>),
+    ) = rune! {
+        const FOUND = [1, 2, 3].find(2);
+        const MISSING = [1, 2, 3].find(4);
+        const SCALE = 3;
+        const MAPPED = FOUND.map(|n| n * SCALE);
+        const SKIPPED = MISSING.map(|n| n * SCALE);
+        const CHAINED = FOUND.and_then(|n| [10, 20].find(n * 10));
+        const FALLBACK = MISSING.or_else(|| [7].find(7));
+        const KEPT = FOUND.or_else(|| [7].find(7));
+        pub fn main() { ((MAPPED, SKIPPED), (CHAINED, FALLBACK, KEPT)) }
+    };
+    assert_eq!(out, ((Some(6), None), (Some(20), Some(7), Some(2))));
+
+    assert_errors! {
+        "const A = |x| x; pub fn main() { A }",
+        span!(10, 15),
+        ErrorKind::IrError(compile::IrErrorKind::NotConst)
+    };
+
+    assert_errors! {
+        "const A = [1].find(1).map(5); pub fn main() { A }",
+        span!(26, 27), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Expected a closure");
+        }
+    };
+
+    assert_errors! {
+        "const A = [1].find(1).map_err(|e| e); pub fn main() { A }",
+        span!(10, 21),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_cycle() {
+    assert_errors! {
+        "const A = B; const B = A; pub fn main() { A }",
+        span!(23, 24), ErrorKind::IrError(error) => {
+            assert_eq!(error.to_string(), "Constant cycle detected: A -> B -> A");
+        }
+    };
+
+    assert_errors! {
+        "const A = helper(); const fn helper() { A } pub fn main() { A }",
+        span!(40, 41), ErrorKind::IrError(error) => {
+            assert_eq!(error.to_string(), "Constant cycle detected: A -> helper -> A");
+        }
+    };
+
+    assert_errors! {
+        "const X = 1; const A = X + B; const B = A; pub fn main() { A }",
+        span!(40, 41), ErrorKind::IrError(error) => {
+            assert_eq!(error.to_string(), "Constant cycle detected: A -> B -> A");
+        }
+    };
+}
+
+#[test]
+fn test_const_fn_recursion() {
+    let out: i64 = rune! {
+        const fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }
+        const A = fact(5);
+        pub fn main() { A }
+    };
+    assert_eq!(out, 120);
+
+    let out: (bool, bool) = rune! {
+        const fn is_even(n) { if n == 0 { true } else { is_odd(n - 1) } }
+        const fn is_odd(n) { if n == 0 { false } else { is_even(n - 1) } }
+        const A = (is_even(10), is_odd(10));
+        pub fn main() { A }
+    };
+    assert_eq!(out, (true, false));
+}
+
+#[test]
+fn test_const_fill_resize() {
+    let out: (Vec<i64>, Vec<i64>, Vec<i64>) = rune! {
+        const GROWN = { let v = [0, 0, 0, 0]; v.resize(6, 1); v };
+        const SHRUNK = { let v = [1, 2, 3]; v.resize(1, 0); v };
+        const FILLED = { let v = [1, 2, 3]; v.fill(7); v };
+        pub fn main() { (GROWN, SHRUNK, FILLED) }
+    };
+    assert_eq!(out, (vec![0, 0, 0, 0, 1, 1], vec![1], vec![7, 7, 7]));
+
+    assert_errors! {
+        "const A = { let v = []; v.resize(0 - 1, 0); v }; pub fn main() { A }",
+        span!(33, 38), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Count must not be negative");
+        }
+    };
+}
+
+#[test]
+fn test_const_numeric_limits() {
+    let out: ((i64, i64, i64), (f64, f64)) = rune! {
+        const LIMITS = ((i64::MAX, i64::MIN, i64::BITS), (f64::MAX, f64::MIN));
+        pub fn main() { LIMITS }
+    };
+    assert_eq!(out, ((i64::MAX, i64::MIN, 64), (f64::MAX, f64::MIN)));
+
+    assert_errors! {
+        "const A = Vec::MAX; pub fn main() { A }",
+        span!(10, 18), ErrorKind::Custom { error } => {
+            assert_eq!(
+                error.to_string(),
+                "Numeric limit `MAX` is only available on primitive numeric types, like `i64::MAX`, but `::std::vec::Vec` is not one"
+            );
+        }
+    };
+}
+
+#[test]
+fn test_const_eval_budget() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.const_eval_budget(1000);
+
+    let mut sources = crate::tests::sources(
+        "const A = { let n = 0; while n < 10 { n += 1; } n }; pub fn main() { A }",
+    );
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let error = crate::tests::compile_error(
+        "const A = { let n = 0; while n < 10000 { n += 1; } n }; pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::BudgetExceeded { limit: 1000 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_const_recursion_limit() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.const_recursion_limit(3);
+
+    let mut sources = crate::tests::sources(
+        "const A = { let x = #{a: #{b: #{c: 1}}}; x.a.b }; pub fn main() { A }",
+    );
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let error = crate::tests::compile_error(
+        "const A = { let x = #{a: #{b: #{c: 1}}}; x.a.b.c }; pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(41, 42));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::RecursionLimit { limit: 3 })
+    ));
+
+    let mut sources = crate::tests::sources(
+        "const fn a() { b() } const fn b() { c() } const fn c() { 1 } const A = a(); pub fn main() { A }",
+    );
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let error = crate::tests::compile_error(
+        "const fn a() { b() } const fn b() { c() } const fn c() { d() } const fn d() { 1 } const A = a(); pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(57, 60));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::RecursionLimit { limit: 3 })
+    ));
+
+    let mut sources = crate::tests::sources(
+        "const fn f(n) { if n == 0 { 0 } else { f(n - 1) } } const A = f(2); pub fn main() { A }",
+    );
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let error = crate::tests::compile_error(
+        "const fn f(n) { if n == 0 { 0 } else { f(n - 1) } } const A = f(5); pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(39, 47));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::RecursionLimit { limit: 3 })
+    ));
+
+    let error = crate::tests::compile_error(
+        "const fn a(n) { if n == 0 { 0 } else { b(n - 1) } } const fn b(n) { a(n) } const A = a(5); pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(39, 47));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::RecursionLimit { limit: 3 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_const_total_alloc_limit() -> Result<()> {
+    let mut options = crate::Options::default();
+    options.const_total_alloc_limit(10);
+
+    let mut sources = crate::tests::sources(r#"const A = "ab" + "cdef"; pub fn main() { A }"#);
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    // Each constant is within the limit on its own, but not together.
+    let error = crate::tests::compile_error(
+        r#"const A = "ab" + "cdef"; const B = A + "gh"; pub fn main() { (A, B) }"#,
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(35, 43));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::AllocationLimit { limit: 10 })
+    ));
+
+    // Three values for the vector, and two for each window.
+    let limit = core::mem::size_of::<Value>() * 8;
+    options.const_total_alloc_limit(limit);
+
+    let mut sources = crate::tests::sources("const A = [1, 2, 3].windows(3); pub fn main() { A }");
+    assert!(prepare(&mut sources).with_options(&options).build().is_ok());
+
+    let error = crate::tests::compile_error(
+        "const A = [1, 2, 3].windows(1); pub fn main() { A }",
+        &Context::new(),
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(10, 30));
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::IrError(compile::IrErrorKind::AllocationLimit { limit: actual })
+            if *actual == limit
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_const_is_empty() {
+    let out: ((bool, bool, bool), (bool, bool, bool, bool)) = rune! {
+        const ITEMS = [1, 2];
+        const EMPTY = (
+            (ITEMS.is_empty(), "".is_empty(), "a".is_empty()),
+            ([].is_empty(), (1,).is_empty(), #{}.is_empty(), b"ab".is_empty()),
+        );
+        pub fn main() { EMPTY }
+    };
+    assert_eq!(out, ((false, true, false), (true, false, true, false)));
+
+    assert_errors! {
+        "const A = true.is_empty(); pub fn main() { A }",
+        span!(10, 14),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_loop_labels() {
+    let out: (i64, i64) = rune! {
+        const TOTAL = {
+            let total = 0;
+            let i = 0;
+
+            'outer: while i < 5 {
+                i = i + 1;
+                let j = 0;
+
+                while j < 5 {
+                    j = j + 1;
+
+                    if j == 2 {
+                        continue;
+                    }
+
+                    if j > i {
+                        continue 'outer;
+                    }
+
+                    if i == 4 {
+                        break 'outer;
+                    }
+
+                    total = total + j;
+                }
+            }
+
+            total
+        };
+
+        const INNER = {
+            let n = 0;
+
+            'outer: loop {
+                loop {
+                    break;
+                }
+
+                n = n + 1;
+                break 'outer;
+            }
+
+            n
+        };
+
+        pub fn main() { (TOTAL, INNER) }
+    };
+    assert_eq!(out, (6, 1));
+
+    assert_errors! {
+        "const A = { loop { break 'x; } }; pub fn main() { A }",
+        span!(19, 27),
+        ErrorKind::MissingLoopLabel { .. }
+    };
+}
+
+#[test]
+fn test_const_chars() {
+    let out: ((i64, i64, i64), (Option<char>, i64)) = rune! {
+        const NAME = "héllo wörld";
+        const N = NAME.chars().count();
+        const L = NAME.chars().filter(|c| c == 'l').count();
+        const O = NAME.chars().map(|c| c == 'ö').filter(|o| o).count();
+        const FIRST = NAME.chars().next();
+
+        const ACCENTED = {
+            let it = NAME.chars();
+            let n = 0;
+
+            while let Some(c) = it.next() {
+                if c > 'z' {
+                    n = n + 1;
+                }
+            }
+
+            n
+        };
+
+        pub fn main() { ((N, L, O), (FIRST, ACCENTED)) }
+    };
+    assert_eq!(out, ((11, 3, 1), (Some('h'), 2)));
+
+    assert_errors! {
+        "const A = true.chars(); pub fn main() { A }",
+        span!(10, 14),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_get_or_insert() {
+    let out: (i64, i64, i64, i64) = rune! {
+        const BASE = #{host: 1};
+
+        const CFG = {
+            let o = BASE;
+            let port = o.get_or_insert("port", 8080);
+            let host = o.get_or_insert("host", 2);
+            (port, host, o.port, BASE.get_or_insert("port", 0))
+        };
+
+        pub fn main() { CFG }
+    };
+    assert_eq!(out, (8080, 1, 8080, 0));
+
+    assert_errors! {
+        r#"const A = [1].get_or_insert("a", 1); pub fn main() { A }"#,
+        span!(10, 13),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+
+    assert_errors! {
+        "const A = #{}.get_or_insert(1, 1); pub fn main() { A }",
+        span!(28, 29),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_flatten() {
+    let out: Vec<i64> = rune! {
+        const NESTED = [[1, 2], [3], []];
+        const F = NESTED.flatten();
+        pub fn main() { F }
+    };
+    assert_eq!(out, [1, 2, 3]);
+
+    let out: Vec<i64> = rune! {
+        const F = [[1], [[2, 3]]].flatten();
+        pub fn main() { F[1] }
+    };
+    assert_eq!(out, [2, 3]);
+
+    let out: Vec<i64> = rune! {
+        const F = [1, [2, 3], 4].flatten(false);
+        pub fn main() { F }
+    };
+    assert_eq!(out, [1, 2, 3, 4]);
+
+    assert_errors! {
+        "const A = [1, [2]].flatten(true); pub fn main() { A }",
+        span!(10, 18),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_hash() -> Result<()> {
+    assert_errors! {
+        "const A = hash(1); pub fn main() { A }",
+        span!(10, 17),
+        ErrorKind::Custom { .. }
+    };
+
+    let context = Context::with_default_modules()?;
+    let mut options = crate::Options::default();
+    options.const_hash(true);
+
+    let mut sources = crate::tests::sources(
+        r#"
+        const KEY = hash([1, "a"]);
+        const TUPLE = hash((1, "a"));
+
+        pub fn main() { (KEY == std::ops::hash([1, "a"]), KEY == TUPLE) }
+        "#,
+    );
+
+    let unit = prepare(&mut sources)
+        .with_context(&context)
+        .with_options(&options)
+        .build()?;
+
+    let mut vm = Vm::new(Arc::new(context.runtime()?), Arc::new(unit));
+    let out: (bool, bool) = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(out, (true, true));
+
+    let error = crate::tests::compile_error(
+        "const A = hash(#{}); pub fn main() { A }",
+        &context,
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(15, 18));
+    assert!(matches!(error.kind(), ErrorKind::VmError(..)));
+    Ok(())
+}
+
+#[test]
+fn test_const_insert_remove() {
+    let out: (Vec<i64>, i64) = rune! {
+        const TABLE = {
+            let v = [1, 2, 3];
+            v.insert(0, 0);
+            v.insert(4, 4);
+            let removed = v.remove(2);
+            (v, removed)
+        };
+
+        pub fn main() { TABLE }
+    };
+    assert_eq!(out, (vec![0, 1, 3, 4], 2));
+
+    assert_errors! {
+        "const A = { let v = [1]; v.insert(2, 0); v }; pub fn main() { A }",
+        span!(34, 35),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 2 })
+    };
+
+    assert_errors! {
+        "const A = [1].remove(1); pub fn main() { A }",
+        span!(21, 22),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 1 })
+    };
+}
+
+#[test]
+fn test_const_char_digits() {
+    let out: (Option<i64>, Option<i64>, Option<char>, Option<char>) = rune! {
+        const C = 'f';
+        const D = C.to_digit(16);
+        const N = 'z'.to_digit(16);
+        const A = char::from_digit(10, 16);
+        const B = char::from_digit(10, 10);
+
+        pub fn main() { (D, N, A, B) }
+    };
+    assert_eq!(out, (Some(15), None, Some('a'), None));
+
+    assert_errors! {
+        "const A = '1'.to_digit(37); pub fn main() { A }",
+        span!(23, 25), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Radix 37 is out of range 2 to 36");
+        }
+    };
+
+    assert_errors! {
+        "const A = char::from_digit(1, 1); pub fn main() { A }",
+        span!(30, 31), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Radix 1 is out of range 2 to 36");
+        }
+    };
+}
+
+#[test]
+fn test_const_zip_and() -> Result<()> {
+    let out: (Option<(i64, i64)>, Option<(i64, i64)>, Option<i64>) = rune! {
+        const A = [1, 2].find(1);
+        const B = [1, 2].find(2);
+        const MISSING = [1, 2].find(3);
+        const ZIP = A.zip(B);
+        const NONE = A.zip(MISSING);
+        const AND = A.and(B);
+        pub fn main() { (ZIP, NONE, AND) }
+    };
+    assert_eq!(out, (Some((1, 2)), None, Some(2)));
+
+    let mut module = Module::new();
+    module
+        .function("check", |n: i64| if n > 0 { Ok(n) } else { Err(n) })
+        .build()?
+        .const_fn(true);
+
+    let out: (Result<i64, i64>, Result<i64, i64>) = rune_n! {
+        &module,
+        (),
+        (Result<i64, i64>, Result<i64, i64>) =>
+        const OK = check(1).and(check(2));
+        const ERR = check(0).and(check(2));
+        pub fn main() { (OK, ERR) }
+    };
+    assert_eq!(out, (Ok(2), Err(0)));
+
+    assert_errors! {
+        "const A = [1].find(1).zip(2); pub fn main() { A }",
+        span!(26, 27),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+    Ok(())
+}
+
+#[test]
+fn test_const_panic() {
+    let out: i64 = rune! {
+        const X = 2;
+        const A = if X == 2 { 1 } else { panic("bad") };
+        const B = if X == 2 { 2 } else { unreachable() };
+        pub fn main() { A + B }
+    };
+    assert_eq!(out, 3);
+
+    assert_errors! {
+        r#"const A = if 1 == 2 { 1 } else { panic("bad") }; pub fn main() { A }"#,
+        span!(33, 45),
+        ErrorKind::IrError(compile::IrErrorKind::Panic { message }) => {
+            assert_eq!(&*message, "bad");
+        }
+    };
+
+    assert_errors! {
+        r#"const A = unreachable("oops"); pub fn main() { A }"#,
+        span!(10, 29),
+        ErrorKind::IrError(compile::IrErrorKind::Panic { message }) => {
+            assert_eq!(&*message, "Entered unreachable code: oops");
+        }
+    };
+}
+
+#[test]
+fn test_const_first_last() {
+    let out: (Option<i64>, Option<i64>, Option<String>, Option<i64>) = rune! {
+        const ITEMS = [1, 2, 3];
+        const F = ITEMS.first();
+        const L = ITEMS.last();
+        const T = (1, "end").last();
+        const E = [].first();
+        pub fn main() { (F, L, T, E) }
+    };
+    assert_eq!(out, (Some(1), Some(3), Some(String::from("end")), None));
+
+    assert_errors! {
+        r#"const A = "abc".first(); pub fn main() { A }"#,
+        span!(10, 15),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_lines() {
+    let out: (Vec<String>, usize) = rune! {
+        const TEXT = "name,port\r\nweb,80\ndb,5432\n";
+        const ROWS = TEXT.lines().collect::<Vec>();
+        const EMPTY = "".lines().count();
+        pub fn main() { (ROWS, EMPTY) }
+    };
+    assert_eq!(
+        out,
+        (
+            vec![
+                String::from("name,port"),
+                String::from("web,80"),
+                String::from("db,5432")
+            ],
+            0
+        )
+    );
+
+    assert_errors! {
+        "const A = [1].lines(); pub fn main() { A }",
+        span!(10, 13),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_checked() {
+    let out: (
+        (Option<i64>, Option<i64>, Option<i64>),
+        (Option<i64>, Option<i64>, Option<i64>),
+    ) = rune! {
+        const MAX = 9223372036854775807;
+        const A = MAX.checked_add(1);
+        const B = 40.checked_add(2);
+        const C = 1.checked_sub(3);
+        const D = MAX.checked_mul(2);
+        const E = 7.checked_div(2);
+        const F = 7.checked_div(0);
+        pub fn main() { ((A, B, C), (D, E, F)) }
+    };
+    assert_eq!(out, ((None, Some(42), Some(-2)), (None, Some(3), None)));
+
+    assert_errors! {
+        "const A = 1.checked_add(2.0); pub fn main() { A }",
+        span!(24, 27),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_enumerate() {
+    let out: (i64, i64, i64, usize) = rune! {
+        const NAMES = ["web", "db", "cache"];
+        const IDX = NAMES.iter().enumerate().map(|(i, n)| (n, i)).collect::<Object>();
+        pub fn main() { (IDX.web, IDX.db, IDX.cache, IDX.len()) }
+    };
+    assert_eq!(out, (0, 1, 2, 3));
+
+    let out: Vec<i64> = rune! {
+        const FIRST = [(1, 2, 3), (4, 5, 6)].iter().map(|(a, ..)| a).collect::<Vec>();
+        pub fn main() { FIRST }
+    };
+    assert_eq!(out, [1, 4]);
+
+    assert_errors! {
+        "const A = [1].iter().map(|#{a}| a).collect::<Vec>(); pub fn main() { A }",
+        span!(26, 30),
+        ErrorKind::IrError(compile::IrErrorKind::NotConst)
+    };
+
+    assert_errors! {
+        "const A = [1].iter().map(|(a, b)| a).collect::<Vec>(); pub fn main() { A }",
+        span!(25, 35),
+        ErrorKind::Custom { .. }
+    };
+}
+
+#[test]
+fn test_const_wrapping() {
+    let out: ((i64, i64, i64), (i64, i64, i64, i64)) = rune! {
+        const MAX = 9223372036854775807;
+        const A = MAX.wrapping_add(2);
+        const B = (0 - MAX).wrapping_sub(2);
+        const C = MAX.wrapping_mul(2);
+        const D = (-1).wrapping_shl(7);
+        const E = 1.wrapping_shl(65);
+        const F = (-128).wrapping_shr(7);
+        const H = 7.wrapping_mul(31).wrapping_add(3);
+        pub fn main() { ((A, B, C), (D, E, F, H)) }
+    };
+    assert_eq!(out, ((i64::MIN + 1, i64::MAX, -2), (-128, 2, -1, 220)));
+
+    let out: bool = rune! {
+        const H = 1469598103934665603.wrapping_mul(1099511628211).wrapping_add(97);
+        pub fn main() { H == 1469598103934665603.wrapping_mul(1099511628211).wrapping_add(97) }
+    };
+    assert!(out);
+
+    assert_errors! {
+        "const A = 1.wrapping_add(\"a\"); pub fn main() { A }",
+        span!(25, 28),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_object_path() {
+    let out: ((bool, bool, bool), (Option<String>, Option<String>)) = rune! {
+        const CFG = #{server: #{port: 80, tls: #{cert: "cert.pem"}}};
+        const HAS = CFG.has_path("server.tls.cert");
+        const MISSING = CFG.has_path("server.tls.key");
+        const THROUGH = CFG.has_path("server.port.number");
+        const CERT = CFG.get_path("server.tls.cert");
+        const NONE = CFG.get_path("client");
+        pub fn main() { ((HAS, MISSING, THROUGH), (CERT, NONE)) }
+    };
+    assert_eq!(
+        out,
+        ((true, false, false), (Some(String::from("cert.pem")), None))
+    );
+
+    assert_errors! {
+        "const A = #{}.has_path(1); pub fn main() { A }",
+        span!(23, 24),
+        ErrorKind::VmError(..)
+    };
+
+    assert_errors! {
+        "const A = [1].get_path(\"a\"); pub fn main() { A }",
+        span!(10, 13),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_by_key() {
+    let out: (Option<String>, Option<String>, Option<i64>, Option<i64>) = rune! {
+        const ITEMS = [
+            #{name: "a", score: 3},
+            #{name: "b", score: 7},
+            #{name: "c", score: 1},
+            #{name: "d", score: 7},
+        ];
+        const BEST = ITEMS.max_by_key(|i| i.score).map(|i| i.name);
+        const WORST = ITEMS.min_by_key(|i| i.score).map(|i| i.name);
+        const FIRST = [2, 1, 1].iter().min_by_key(|n| n * 0);
+        const NONE = [].max_by_key(|n| n);
+        pub fn main() { (BEST, WORST, FIRST, NONE) }
+    };
+    assert_eq!(
+        out,
+        (
+            Some(String::from("d")),
+            Some(String::from("c")),
+            Some(2),
+            None
+        )
+    );
+
+    assert_errors! {
+        "const A = [1, 2].max_by_key(|n| if n == 1 { 1 } else { \"a\" }); pub fn main() { A }",
+        span!(28, 60),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_expect() {
+    let out: (i64, i64) = rune! {
+        const A = [1, 2, 3].find(2).expect("missing two");
+        const B = [1, 3, 5].binary_search(3).expect("missing three");
+        pub fn main() { (A, B) }
+    };
+    assert_eq!(out, (2, 1));
+
+    assert_errors! {
+        r#"const A = [1].find(2).expect("config must set X"); pub fn main() { A }"#,
+        span!(10, 49),
+        ErrorKind::IrError(compile::IrErrorKind::Panic { message }) => {
+            assert_eq!(&*message, "config must set X");
+        }
+    };
+
+    assert_errors! {
+        r#"const A = [1].binary_search(2).expect(1); pub fn main() { A }"#,
+        span!(38, 39),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_swap_rotate() {
+    let out: (Vec<i64>, Vec<i64>, Vec<i64>) = rune! {
+        const TABLE = {
+            let a = [1, 2, 3, 4];
+            a.swap(0, 3);
+            let b = [1, 2, 3, 4];
+            b.rotate_left(1);
+            let c = [1, 2, 3, 4];
+            c.rotate_right(4);
+            (a, b, c)
+        };
+
+        pub fn main() { TABLE }
+    };
+    assert_eq!(out, (vec![4, 2, 3, 1], vec![2, 3, 4, 1], vec![1, 2, 3, 4]));
+
+    assert_errors! {
+        "const A = [1, 2].swap(0, 2); pub fn main() { A }",
+        span!(25, 26),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 2 })
+    };
+
+    assert_errors! {
+        "const A = [1, 2].rotate_left(3); pub fn main() { A }",
+        span!(29, 30),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 3 })
+    };
+}
+
+#[test]
+fn test_const_binary_search() {
+    let out: (Result<i64, i64>, Result<i64, i64>, Result<i64, i64>) = rune! {
+        const SORTED = [1, 3, 5, 7];
+        const FOUND = SORTED.binary_search(5);
+        const MISSING = SORTED.binary_search(4);
+        const EMPTY = [].binary_search(4);
+        pub fn main() { (FOUND, MISSING, EMPTY) }
+    };
+    assert_eq!(out, (Ok(2), Err(2), Err(0)));
+
+    assert_errors! {
+        r#"const A = [1, "a"].binary_search(2); pub fn main() { A }"#,
+        span!(10, 18),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_escape() {
+    let out: (String, String, String) = rune! {
+        const RAW = "say \"hi\"\n\tC:\\ it's\u{1}";
+        const ESCAPED = escape(RAW);
+        const UNESCAPED = unescape("a\\x41\\u{1F600}\\\\b\\\"");
+        const ROUNDTRIP = unescape(escape(RAW)) == RAW;
+        pub fn main() { (ESCAPED, UNESCAPED, if ROUNDTRIP { "ok" } else { "bad" }) }
+    };
+    assert_eq!(
+        out,
+        (
+            String::from(r#"say \"hi\"\n\tC:\\ it\'s\u{1}"#),
+            String::from("aA\u{1F600}\\b\""),
+            String::from("ok"),
+        )
+    );
+
+    assert_errors! {
+        r#"const A = unescape("\\q"); pub fn main() { A }"#,
+        span!(19, 24),
+        ErrorKind::UnescapeError(..)
+    };
+}
+
+#[test]
+fn test_const_try_into() {
+    let out: (
+        Result<u8, String>,
+        Result<u8, String>,
+        i64,
+        Result<char, String>,
+    ) = rune! {
+        const SMALL = 200.try_into::<u8>();
+        const LARGE = 300.try_into::<u8>();
+        const WIDE = b'a'.try_into::<i64>().map(|n| n + 1);
+        const INVALID = 0x110000.try_into::<char>();
+        pub fn main() { (SMALL, LARGE, WIDE.unwrap(), INVALID) }
+    };
+    assert_eq!(
+        out,
+        (
+            Ok(200),
+            Err(String::from(
+                "out of range integral type conversion attempted"
+            )),
+            98,
+            Err(String::from("converted integer out of range for `char`")),
+        )
+    );
+
+    assert_errors! {
+        r#"const A = "a".try_into::<u8>(); pub fn main() { A }"#,
+        span!(10, 13),
+        ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_bool_cast() {
+    let out: (bool, bool, i64) = rune! {
+        const N = 4;
+        const D = N != 0;
+        const E = 'a' != 'a';
+        const F = if N != 0 { 1 } else { 0 };
+        pub fn main() { (D, E, F) }
+    };
+    assert_eq!(out, (true, false, 1));
+
+    assert_errors! {
+        r#"const A = true as i64; pub fn main() { A }"#,
+        span!(10, 21), ErrorKind::Custom { error } => {
+            assert_eq!(
+                error.to_string(),
+                "Booleans can't be cast to integers, use `if b { 1 } else { 0 }` instead"
+            );
+        }
+    };
+
+    assert_errors! {
+        r#"const A = true as u8; pub fn main() { A }"#,
+        span!(10, 20), ErrorKind::Custom { .. }
+    };
+
+    assert_errors! {
+        r#"const A = 1 as bool; pub fn main() { A }"#,
+        span!(10, 19), ErrorKind::Custom { error } => {
+            assert_eq!(
+                error.to_string(),
+                "Integers can't be cast to `bool`, compare against zero instead, like `n != 0`"
+            );
+        }
+    };
+}
+
+#[test]
+fn test_const_numeric_cast() {
+    let out: (f64, u8, i64, i64, f64) = rune! {
+        const A = 1 as f64;
+        const B = 300 as u8;
+        const C = -2.9 as i64;
+        const D = b'a' as i64;
+        const E = b'a' as f64;
+        pub fn main() { (A, B, C, D, E) }
+    };
+    assert_eq!(out, (1.0, 44, -2, 97, 97.0));
+}
+
+#[test]
+fn test_const_time_helpers() {
+    let out: (i64, i64) = rune! {
+        const TIMEOUT = seconds(30) + millis(500);
+        const TICK = micros(250) * 4;
+        pub fn main() { (TIMEOUT, TICK) }
+    };
+    assert_eq!(out, (30_500_000, 1_000));
+
+    assert_errors! {
+        r#"const T = seconds(9223372036854775807); pub fn main() { T }"#,
+        span!(10, 38), ErrorKind::IrError(compile::IrErrorKind::IntegerOverflow)
+    };
+
+    assert_errors! {
+        r#"const T = seconds(9223372036854) + seconds(9223372036854); pub fn main() { T }"#,
+        span!(10, 57), ErrorKind::IrError(compile::IrErrorKind::IntegerOverflow)
+    };
+}
+
+#[test]
+fn test_const_collect_object() {
+    let out: (i64, i64, usize) = rune! {
+        const ENTRIES = [#{name: "a", value: 1}, #{name: "b", value: 2}, #{name: "a", value: 3}];
+        const M = ENTRIES.iter().map(|e| (e.name, e.value)).collect::<Object>();
+        pub fn main() { (M.a, M.b, M.len()) }
+    };
+    assert_eq!(out, (3, 2, 2));
+
+    assert_errors! {
+        r#"const M = [1, 2].iter().collect::<Object>(); pub fn main() { M }"#,
+        span!(10, 23), ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+
+    assert_errors! {
+        r#"const M = [(1, 2)].iter().collect::<Object>(); pub fn main() { M }"#,
+        span!(10, 25), ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_split_at() {
+    let out: ((Vec<i64>, Vec<i64>), (String, String), (Vec<i64>, Vec<i64>)) = rune! {
+        const DATA = [1, 2, 3, 4, 5, 6];
+        const PARTS = DATA.split_at(4);
+        const NAME = "héllo".split_at(3);
+        const EMPTY = [].split_at(0);
+        pub fn main() { (PARTS, NAME, EMPTY) }
+    };
+    assert_eq!(
+        out,
+        (
+            (vec![1, 2, 3, 4], vec![5, 6]),
+            (String::from("hé"), String::from("llo")),
+            (vec![], vec![])
+        )
+    );
+
+    assert_errors! {
+        r#"const A = [1, 2].split_at(3); pub fn main() { A }"#,
+        span!(26, 27), ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 3 })
+    };
+
+    assert_errors! {
+        r#"const A = "é".split_at(1); pub fn main() { A }"#,
+        span!(24, 25), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Index is not on a character boundary");
+        }
+    };
+}
+
+#[test]
+fn test_const_fn_push() {
+    let out: Vec<i64> = rune! {
+        const fn squares(n) {
+            let table = [];
+            let i = 0;
+
+            while i < n {
+                table.push(i * i);
+                i += 1;
+            }
+
+            table
+        }
+
+        const TABLE = squares(5);
+        pub fn main() { TABLE }
+    };
+    assert_eq!(out, vec![0, 1, 4, 9, 16]);
+}
+
+#[test]
+fn test_const_object_retain() {
+    let out: ((i64, i64), (usize, usize), bool) = rune! {
+        const CFG = #{name: 1, secret: 2, port: 3};
+        const TRIMMED = CFG.retain(|k, v| k != "secret");
+        const SMALL = CFG.filter(|k, v| v < 3);
+        pub fn main() { ((TRIMMED.name, TRIMMED.port), (TRIMMED.len(), SMALL.len()), "a" < "b") }
+    };
+    assert_eq!(out, ((1, 3), (2, 2), true));
+
+    assert_errors! {
+        r#"const A = [1].retain(|k, v| true); pub fn main() { A }"#,
+        span!(10, 13), ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+
+    assert_errors! {
+        r#"const A = #{a: 1}.retain(1); pub fn main() { A }"#,
+        span!(25, 26), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Expected a closure");
+        }
+    };
+}
+
+#[test]
+fn test_const_explain() -> Result<()> {
+    use crate::runtime::ConstValue;
+    use crate::SourceId;
+
+    #[derive(Default)]
+    struct ExplainVisitor {
+        steps: Vec<(String, &'static str, Vec<usize>, Option<i64>)>,
+    }
+
+    impl compile::CompileVisitor for ExplainVisitor {
+        fn visit_const_explained(
+            &mut self,
+            _: SourceId,
+            item: &Item,
+            steps: &[compile::FoldStep],
+        ) -> Result<(), compile::MetaError> {
+            for step in steps {
+                let output = match step.output {
+                    Some(ConstValue::Integer(value)) => Some(value),
+                    _ => None,
+                };
+
+                self.steps.push((
+                    item.to_string(),
+                    step.kind,
+                    step.inputs.iter().copied().collect(),
+                    output,
+                ));
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut options = crate::Options::default();
+    options.explain_const(true);
+
+    let mut sources = crate::tests::sources("const A = 1 + 2; pub fn main() { A }");
+    let mut visitor = ExplainVisitor::default();
+
+    let _ = prepare(&mut sources)
+        .with_options(&options)
+        .with_visitor(&mut visitor)?
+        .build()?;
+
+    assert_eq!(
+        visitor.steps,
+        [
+            (String::from("A"), "value", vec![], Some(1)),
+            (String::from("A"), "value", vec![], Some(2)),
+            (String::from("A"), "binary", vec![0, 1], Some(3)),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_const_char_range() {
+    let out: (Vec<char>, Vec<char>, Vec<i64>) = rune! {
+        const LETTERS = ('a'..='e').collect::<Vec>();
+        const DIGITS = ('0'..'3').collect();
+        const NUMBERS = (2..5).collect();
+        pub fn main() { (LETTERS, DIGITS, NUMBERS) }
+    };
+    assert_eq!(
+        out,
+        (
+            vec!['a', 'b', 'c', 'd', 'e'],
+            vec!['0', '1', '2'],
+            vec![2, 3, 4]
+        )
+    );
+
+    assert_errors! {
+        r#"const A = ('b'..'a').collect(); pub fn main() { A }"#,
+        span!(11, 19), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Character range must not start after it ends");
+        }
+    };
+
+    assert_errors! {
+        r#"const A = ('a'..='\u{10000}').collect(); pub fn main() { A }"#,
+        span!(11, 28), ErrorKind::Custom { error } => {
+            assert_eq!(error.to_string(), "Character range must not cross a Unicode plane");
+        }
+    };
+}
+
+#[test]
+fn test_const_if_let() {
+    let out: ((i64, i64, i64, i64), (bool, i64)) = rune! {
+        const MAYBE = [1, 2, 3].find(2);
+        const MISSING = [1, 2, 3].find(4);
+        const A = if let Some(v) = MAYBE { v } else { 0 };
+        const B = if let Some(v) = MISSING { v } else { 0 };
+        const C = if let Ok(i) = [1, 3, 5].binary_search(3) { i } else { 100 };
+        const D = if let Err(i) = [1, 3, 5].binary_search(4) { i } else { 100 };
+        const E = if let Some(2) = MAYBE { true } else { false };
+        const F = if let None = MISSING { 1 } else { 2 };
+        pub fn main() { ((A, B, C, D), (E, F)) }
+    };
+    assert_eq!(out, ((2, 0, 1, 2), (true, 1)));
+
+    assert_errors! {
+        "const A = if let [a] = [1] { a } else { 0 }; pub fn main() { A }",
+        span!(17, 20),
+        ErrorKind::IrError(compile::IrErrorKind::NotConst)
+    };
+}
+
+#[test]
+fn test_const_match() {
+    let out: (i64, bool, i64, Vec<String>) = rune! {
+        const fn field(kind, required) {
+            match kind {
+                "int" => #{kind: "integer", min: 0},
+                "str" if required => #{kind: "string", required: true},
+                "str" => #{kind: "string"},
+                other => #{kind: "any", of: [other]},
+            }
+        }
+
+        const INT = field("int", false);
+        const REQUIRED = field("str", true);
+        const OPTIONAL = field("str", false);
+        const ANY = field("x", false);
+        pub fn main() { (INT.min, REQUIRED.required, OPTIONAL.len(), ANY.of) }
+    };
+    assert_eq!(out, (0, true, 1, vec![String::from("x")]));
+
+    let out: (i64, i64, i64, i64) = rune! {
+        const ENV = "prod";
+        const LEVEL = match ENV { "prod" => 3, "dev" => 1, _ => 0 };
+        const NUMBER = match 2 { 1 => 10, 2 => 20, _ => 0 };
+        const FLAG = match false { true => 1, false => 2 };
+        const PAIR = match (1, (2, 3)) { (1, (a, b)) => a + b, _ => 0 };
+        pub fn main() { (LEVEL, NUMBER, FLAG, PAIR) }
+    };
+    assert_eq!(out, (3, 20, 2, 5));
+
+    assert_errors! {
+        "const A = match 3 { 1 => 2 }; pub fn main() { A }",
+        span!(10, 28),
+        ErrorKind::Custom { .. }
+    };
+}
+
+#[test]
+fn test_const_object_entries() {
+    let out: (Vec<(String, i64)>, Vec<(String, i64)>) = rune! {
+        const CFG = #{port: 3, name: 1, secret: 2};
+        const E = CFG.entries();
+        const EMPTY = #{}.entries();
+        pub fn main() { (E, EMPTY) }
+    };
+    assert_eq!(
+        out,
+        (
+            vec![
+                (String::from("name"), 1),
+                (String::from("port"), 3),
+                (String::from("secret"), 2)
+            ],
+            vec![]
+        )
+    );
+
+    assert_errors! {
+        r#"const A = [1].entries(); pub fn main() { A }"#,
+        span!(10, 13), ErrorKind::IrError(compile::IrErrorKind::Expected { .. })
+    };
+}
+
+#[test]
+fn test_const_impl_items() {
+    let out: (i64, i64, i64) = rune! {
+        struct Foo;
+
+        impl Foo {
+            const MAX = 100;
+            const DOUBLE = Foo::MAX * 2;
+
+            fn max() {
+                Foo::MAX
+            }
+        }
+
+        const LIMIT = Foo::DOUBLE + 1;
+
+        pub fn main() { (LIMIT, Foo::max(), Foo::MAX) }
+    };
+    assert_eq!(out, (201, 100, 100));
+}
+
+#[test]
+fn test_const_partition() {
+    let out: ((Vec<i64>, Vec<i64>), (Vec<i64>, Vec<i64>)) = rune! {
+        const NUMS = [1, 2, 3, 4, 5];
+        const PARTS = NUMS.iter().partition(|n| n > 2);
+        const EMPTY = [].partition(|n| true);
+        pub fn main() { (PARTS, EMPTY) }
+    };
+    assert_eq!(out, ((vec![3, 4, 5], vec![1, 2]), (vec![], vec![])));
+
+    assert_errors! {
+        r#"const A = [1].partition(|n| n); pub fn main() { A }"#,
+        span!(24, 29), ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_fold() {
+    let out: (i64, Vec<i64>, Option<i64>, Option<i64>) = rune! {
+        const NUMS = [3, 1, 4, 1, 5];
+        const SUM = NUMS.iter().fold(0, |acc, n| acc + n);
+        const RUNNING = NUMS.iter().fold([], |acc, n| { acc.push(n * 2); acc });
+        const MAX = NUMS.iter().reduce(|a, b| if a > b { a } else { b });
+        const EMPTY = [].iter().reduce(|a, b| a + b);
+        pub fn main() { (SUM, RUNNING, MAX, EMPTY) }
+    };
+    assert_eq!(out, (14, vec![6, 2, 8, 2, 10], Some(5), None));
+
+    assert_errors! {
+        r#"const A = [1].fold(0, |a| a); pub fn main() { A }"#,
+        span!(22, 27),
+        ErrorKind::IrError(compile::IrErrorKind::ArgumentCountMismatch { .. })
+    };
+}
+
+#[test]
+fn test_const_rng() {
+    let out: (Vec<i64>, bool, Vec<i64>) = rune! {
+        const SEED_DATA = rng(42).take(3).collect::<Vec>();
+        const A = rng(7).take(10).collect::<Vec>();
+        const B = rng(7).take(10).collect::<Vec>();
+        const FIRST = [1, 2, 3].take(2).collect::<Vec>();
+        pub fn main() { (SEED_DATA, A == B, FIRST) }
+    };
+    assert_eq!(
+        out,
+        (
+            vec![
+                6839728766377637706,
+                1474913046063446145,
+                2569641874231381929
+            ],
+            true,
+            vec![1, 2],
+        )
+    );
+
+    assert_errors! {
+        r#"const A = rng("a"); pub fn main() { A }"#,
+        span!(14, 17),
+        ErrorKind::VmError(..)
+    };
+}
+
+#[test]
+fn test_const_path() -> Result<()> {
+    use crate::runtime::ConstValue;
+
+    let prefix = |item: &Item| -> alloc::Result<ItemBuf> {
+        let mut path = ItemBuf::with_item(["myplugin"])?;
+        path.extend(item)?;
+        Ok(path)
+    };
+
+    let mut sources = crate::tests::sources("const MAX = 10; mod a { pub const MAX = 20; }");
+    let consts = prepare(&mut sources)
+        .with_const_path(&prefix)
+        .build_consts()?;
+
+    let items = consts
+        .iter()
+        .map(|(item, _)| item.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(items, ["myplugin::MAX", "myplugin::a::MAX"]);
+
+    let mut sources = crate::tests::sources("const MAX = 10; pub fn main() { MAX }");
+    let unit = prepare(&mut sources).with_const_path(&prefix).build()?;
+
+    assert!(matches!(
+        unit.constant(Hash::type_hash(["myplugin", "MAX"])),
+        Some(ConstValue::Integer(10))
+    ));
+    assert!(unit.constant(Hash::type_hash(["MAX"])).is_none());
+
+    let mut vm = Vm::without_runtime(Arc::new(unit));
+    let output: i64 = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(output, 10);
+
+    let mut sources = crate::tests::sources("const MAX = 10; mod a { pub const MAX = 20; }");
+    let last = |item: &Item| -> alloc::Result<ItemBuf> { ItemBuf::with_item(item.last()) };
+    let mut diagnostics = Diagnostics::new();
+
+    let result = prepare(&mut sources)
+        .with_diagnostics(&mut diagnostics)
+        .with_const_path(&last)
+        .build_consts();
+
+    assert!(result.is_err());
+
+    let error = crate::tests::first_compile_error(diagnostics);
+
+    assert!(matches!(error.kind(), ErrorKind::MetaError(..)));
+    Ok(())
+}
+
+#[test]
+fn test_const_eval_no_cache() -> Result<()> {
+    use crate::SourceId;
+
+    #[derive(Default)]
+    struct CountVisitor {
+        evaluated: Vec<String>,
+    }
+
+    impl compile::CompileVisitor for CountVisitor {
+        fn visit_const_explained(
+            &mut self,
+            _: SourceId,
+            item: &Item,
+            _: &[compile::FoldStep],
+        ) -> Result<(), compile::MetaError> {
+            self.evaluated.push(item.to_string());
+            Ok(())
+        }
+    }
+
+    let count = |no_cache: bool| -> Result<usize> {
+        let mut options = crate::Options::default();
+        options.explain_const(true);
+        options.const_eval_no_cache(no_cache);
+
+        let mut sources =
+            crate::tests::sources("const A = 1 + 2; const B = A + A; pub fn main() { B }");
+        let mut visitor = CountVisitor::default();
+
+        let unit = prepare(&mut sources)
+            .with_options(&options)
+            .with_visitor(&mut visitor)?
+            .build()?;
+
+        let mut vm = Vm::without_runtime(Arc::new(unit));
+        let output: i64 = from_value(vm.call(["main"], ())?)?;
+        assert_eq!(output, 6);
+
+        Ok(visitor.evaluated.iter().filter(|item| *item == "A").count())
+    };
+
+    assert_eq!(count(false)?, 1);
+    assert!(count(true)? > 1);
+
+    let mut options = crate::Options::default();
+    options.const_eval_no_cache(true);
+
+    let mut sources = crate::tests::sources("const A = B; const B = A; pub fn main() { A }");
+    let mut diagnostics = Diagnostics::new();
+
+    let result = prepare(&mut sources)
+        .with_options(&options)
+        .with_diagnostics(&mut diagnostics)
+        .build();
+
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_const_fn_memo() -> Result<()> {
+    const SOURCE: &str = r#"
+    const fn heavy(n) {
+        let i = 0;
+        while i < 50000 { i += 1; }
+        n + i
+    }
+
+    const A = heavy(1) + heavy(1) + heavy(1) + heavy(1)
+        + heavy(1) + heavy(1) + heavy(1) + heavy(1);
+
+    pub fn main() { A }
+    "#;
+
+    let mut sources = crate::tests::sources(SOURCE);
+    let unit = prepare(&mut sources).build()?;
+
+    let mut vm = Vm::without_runtime(Arc::new(unit));
+    let output: i64 = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(output, 8 * 50001);
+
+    let mut options = crate::Options::default();
+    options.const_eval_no_cache(true);
+
+    let mut sources = crate::tests::sources(SOURCE);
+    let mut diagnostics = Diagnostics::new();
+
+    let result = prepare(&mut sources)
+        .with_options(&options)
+        .with_diagnostics(&mut diagnostics)
+        .build();
+
+    assert!(result.is_err());
+
+    let out: i64 = rune! {
+        const fn grow(v) { v.push(0); v.count() }
+        const A = { let a = []; let b = []; grow(a); grow(b); a.count() + b.count() };
+        pub fn main() { A }
+    };
+    assert_eq!(out, 2);
+
+    let out: (f64, f64) = rune! {
+        const fn inv(x) { 1.0 / x }
+        const A = (inv(0.0), inv(-0.0));
+        pub fn main() { A }
+    };
+    assert_eq!(out, (f64::INFINITY, f64::NEG_INFINITY));
+    Ok(())
+}
+
+#[test]
+fn test_const_require_pure() -> Result<()> {
+    let mut module = Module::new();
+    module
+        .function("add", |a: i64, b: i64| a + b)
+        .build()?
+        .const_fn(true);
+    module.function("sub", |a: i64, b: i64| a - b).build()?;
+
+    let mut context = Context::with_default_modules()?;
+    context.install(&module)?;
+
+    let mut options = crate::Options::default();
+    options.require_pure_consts(true);
+
+    let mut sources = crate::tests::sources("const A = 1 + 2; pub fn main() { A }");
+
+    let unit = prepare(&mut sources)
+        .with_context(&context)
+        .with_options(&options)
+        .build()?;
+
+    let mut vm = Vm::new(Arc::new(context.runtime()?), Arc::new(unit));
+    let output: i64 = from_value(vm.call(["main"], ())?)?;
+    assert_eq!(output, 3);
+
+    options.const_coverage(true);
+
+    let error = crate::tests::compile_error(
+        "const fn double(n) { add(n, n) } const A = double(2); pub fn main() { A }",
+        &context,
+        &options,
+    );
+
+    let ErrorKind::IrError(compile::IrErrorKind::ImpureConst { path, function }) = error.kind()
+    else {
+        panic!("expected impure constant error, got {error}");
+    };
+
+    let path = path.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+    assert_eq!(path, ["A", "double"]);
+    assert_eq!(&**function, "add");
+
+    // Functions which aren't safe to call in constant contexts are reported as
+    // impure as well.
+    let error = crate::tests::compile_error(
+        "const A = sub(2, 1); pub fn main() { A }",
+        &context,
+        &options,
+    );
+
+    assert_eq!(error.span(), span!(10, 19));
+
+    let ErrorKind::IrError(compile::IrErrorKind::ImpureConst { path, function }) = error.kind()
+    else {
+        panic!("expected impure constant error, got {error}");
+    };
+
+    let path = path.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+    assert_eq!(path, ["A"]);
+    assert_eq!(&**function, "sub");
+    Ok(())
+}
+
+#[test]
+fn test_const_include() -> Result<()> {
+    use std::path::Path;
+
+    use crate::ast::Spanned;
+    use crate::compile::SourceLoader;
+
+    struct TableLoader;
+
+    impl SourceLoader for TableLoader {
+        fn load(&mut self, _: &Path, _: &Item, span: &dyn Spanned) -> compile::Result<Source> {
+            Err(compile::Error::msg(
+                span,
+                "Loading modules is not supported",
+            ))
+        }
+
+        fn include(
+            &mut self,
+            root: &Path,
+            path: &str,
+            span: &dyn Spanned,
+        ) -> compile::Result<alloc::Vec<u8>> {
+            assert_eq!(root, Path::new("scripts/main.rn"));
+
+            if path != "table.csv" {
+                return Err(compile::Error::msg(span, "Denied"));
+            }
+
+            let mut bytes = alloc::Vec::new();
+            bytes.try_extend_from_slice(b"a,1\nb,2\n")?;
+            Ok(bytes)
+        }
+    }
+
+    let build = |source: &str| -> Result<crate::Unit> {
+        let mut sources = Sources::new();
+        sources.insert(Source::with_path("main", source, "scripts/main.rn")?)?;
+        let mut loader = TableLoader;
+
+        let unit = prepare(&mut sources)
+            .with_source_loader(&mut loader)
+            .build()?;
+
+        Ok(unit)
+    };
+
+    let unit = build(
+        r#"
+        const DATA = include_str("table.csv");
+        const ROWS = DATA.lines().count();
+        const BYTES = include_bytes("table.csv");
+        pub fn main() { (DATA, ROWS, BYTES) }
+        "#,
+    )?;
+
+    let mut vm = Vm::without_runtime(Arc::new(unit));
+    let (data, rows, bytes): (String, i64, Bytes) = from_value(vm.call(["main"], ())?)?;
+
+    assert_eq!(data, "a,1\nb,2\n");
+    assert_eq!(rows, 2);
+    assert_eq!(bytes.as_slice(), b"a,1\nb,2\n");
+
+    assert!(build("const A = include_str(\"secret.txt\"); pub fn main() { A }").is_err());
+
+    assert_errors! {
+        "const A = include_str(\"table.csv\"); pub fn main() { A }",
+        span!(10, 34),
+        ErrorKind::Custom { .. }
+    };
+
+    Ok(())
+}
+
+#[test]
+fn test_const_include_files() -> Result<()> {
+    let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+    let build = |source: &str,
+                 loader: Option<&mut dyn compile::SourceLoader>|
+     -> Result<Result<crate::Unit, compile::Error>> {
+        let mut sources = Sources::new();
+        sources.insert(Source::with_path("main", source, manifest)?)?;
+        let mut diagnostics = Diagnostics::new();
+
+        let result = match loader {
+            Some(loader) => prepare(&mut sources)
+                .with_diagnostics(&mut diagnostics)
+                .with_source_loader(loader)
+                .build(),
+            None => prepare(&mut sources)
+                .with_diagnostics(&mut diagnostics)
+                .build(),
+        };
+
+        Ok(result.map_err(|_| crate::tests::first_compile_error(diagnostics)))
+    };
+
+    // The default source loader doesn't permit reading files.
+    let Err(error) = build(
+        "const A = include_str(\"Cargo.toml\"); pub fn main() { A }",
+        None,
+    )?
+    else {
+        panic!("expected including files to be denied");
+    };
+
+    assert_eq!(error.span(), span!(10, 35));
+    assert_eq!(error.to_string(), "Including files is not supported");
+
+    let mut loader = compile::FileIncludeSourceLoader::new();
+
+    let unit = build(
+        "const A = include_str(\"Cargo.toml\"); pub fn main() { A }",
+        Some(&mut loader),
+    )??;
+
+    let mut vm = Vm::without_runtime(Arc::new(unit));
+    let manifest: String = from_value(vm.call(["main"], ())?)?;
+    assert!(manifest.contains("[package]"));
+
+    for path in ["../Cargo.toml", "src/../../Cargo.toml", "/etc/passwd"] {
+        let source = format!("const A = include_str({path:?}); pub fn main() {{ A }}");
+
+        let Err(error) = build(&source, Some(&mut loader))? else {
+            panic!("expected {path} to be rejected");
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Included paths must be relative and can't contain `..` or root components",
+            "{path}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_const_graph() -> Result<()> {
+    #[derive(Default)]
+    struct GraphVisitor {
+        nodes: Vec<String>,
+        edges: Vec<(String, String)>,
+    }
+
+    impl compile::CompileVisitor for GraphVisitor {
+        fn visit_const_graph(&mut self, graph: &compile::ConstGraph) {
+            self.nodes = graph.nodes().map(|item| item.to_string()).collect();
+
+            self.edges = graph
+                .edges()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect();
+        }
+    }
+
+    let mut options = crate::Options::default();
+    options.const_graph(true);
+
+    let mut sources = crate::tests::sources(
+        r#"
+        const fn double(n) { n * 2 }
+        const A = 1;
+        const B = double(A);
+        const C = B + A;
+        pub fn main() { C }
+        "#,
+    );
+
+    let mut visitor = GraphVisitor::default();
+
+    let _ = prepare(&mut sources)
+        .with_options(&options)
+        .with_visitor(&mut visitor)?
+        .build()?;
+
+    visitor.nodes.sort();
+    visitor.edges.sort();
+
+    assert_eq!(visitor.nodes, ["A", "B", "C", "double"]);
+
+    assert_eq!(
+        visitor.edges,
+        [
+            (String::from("B"), String::from("A")),
+            (String::from("B"), String::from("double")),
+            (String::from("C"), String::from("A")),
+            (String::from("C"), String::from("B")),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_const_negative_index() {
+    let out: (i64, i64, Vec<i64>, i64) = rune! {
+        const fn last(values) { values[-1] }
+        const LAST = last([1, 2, 3]);
+        const FIRST = { let v = [1, 2, 3]; v[-3] };
+        const UPDATED = { let v = [1, 2, 3]; v[-1] = 9; v[-3] += 1; v };
+        const NESTED = { let v = [[1, 2], [3, 4]]; v[-1][-2] };
+        pub fn main() { (LAST, FIRST, UPDATED, NESTED) }
+    };
+    assert_eq!(out, (3, 1, vec![2, 2, 9], 3));
+
+    assert_errors! {
+        "const A = { let v = [1, 2]; v[-3] }; pub fn main() { A }",
+        span!(28, 33),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: -3 })
+    };
+
+    assert_errors! {
+        "const A = { let v = [1, 2]; v[2] = 0; v }; pub fn main() { A }",
+        span!(28, 32),
+        ErrorKind::IrError(compile::IrErrorKind::MissingIndex { index: 2 })
+    };
+}
+
+#[test]
+fn test_const_string_methods() {
+    let out: ((String, String, String), (String, String), (i64, i64)) = rune! {
+        const NAME = "foo" + "bar";
+        const UP = "hi".to_uppercase();
+        const LOW = "MiXeD".to_lowercase();
+        const KEY = "  cache key \n".trim();
+        const PATH = "a.b.c".replace(".", "::");
+        const LEN = (NAME.len(), [1, 2, 3].len());
+        pub fn main() { ((NAME, UP, LOW), (KEY, PATH), LEN) }
+    };
+    assert_eq!(
+        out,
+        (
+            (
+                String::from("foobar"),
+                String::from("HI"),
+                String::from("mixed")
+            ),
+            (String::from("cache key"), String::from("a::b::c")),
+            (6, 3)
+        )
+    );
+
+    assert_errors! {
+        r#"const A = "a".replace("a"); pub fn main() { A }"#,
+        span!(10, 26),
+        ErrorKind::IrError(compile::IrErrorKind::ArgumentCountMismatch { actual: 1, expected: 2 })
+    };
+
+    assert_errors! {
+        r#"const A = "a".replace(1, "b"); pub fn main() { A }"#,
+        span!(22, 23),
+        ErrorKind::VmError(..)
+    };
+
+    assert_errors! {
+        "const A = true.trim(); pub fn main() { A }",
+        span!(10, 14),
+        ErrorKind::VmError(..)
+    };
+}
@@ -2,8 +2,8 @@
 
 use crate::alloc::prelude::*;
 use crate::alloc::{self, Box, HashMap, Vec, VecDeque};
-use crate::ast::{self, Span};
-use crate::compile::{self, ModId};
+use crate::ast::{self, Span, Spanned};
+use crate::compile::{self, ModId, WithSpan};
 use crate::indexing::index;
 use crate::indexing::items::Items;
 use crate::indexing::{IndexItem, Indexer, Scopes};
@@ -115,6 +115,13 @@ impl<'a, 'arena> Worker<'a, 'arena> {
                                     true,
                                 )?;
 
+                                for (item, _) in &ast.items {
+                                    self.q
+                                        .parse_visitor
+                                        .visit_item(source_id, item, item.span())
+                                        .with_span(item)?;
+                                }
+
                                 let mut idx = indexer!();
 
                                 index::file(&mut idx, &mut ast)?;
@@ -227,6 +234,17 @@ impl<'a, 'arena> Worker<'a, 'arena> {
                         index::item_fn_immediate(&mut idx, Box::into_inner(f.ast))?;
                     }
 
+                    let removed = idx
+                        .q
+                        .inner
+                        .impl_consts
+                        .remove(&entry.id)
+                        .unwrap_or_default();
+
+                    for c in removed {
+                        index::item_const(&mut idx, Box::into_inner(c.ast))?;
+                    }
+
                     Ok::<_, compile::Error>(())
                 };
 